@@ -1,8 +1,33 @@
-const COMMANDS: &[&str] = &["ping"];
+const COMMANDS: &[&str] = &[
+    "ping",
+    "assert_external_contract",
+    "detect_contract",
+    "verify_path",
+    "verify_named",
+    "add_workspace",
+    "remove_workspace",
+    "workspaces",
+    "verify_archive",
+    "verify",
+    "tree_json",
+    "verify_streamed",
+    "repair_plan",
+    "apply_repair",
+    "write_report",
+    "last_verification_stats",
+    "wait_until_ready",
+    "last_verification",
+    "history",
+    "structure_version",
+    "clean_cache",
+    "clean_temp",
+    "invalidate_verification_cache",
+    "generate_manifest",
+    "verify_manifest",
+    "watch",
+    "unwatch",
+];
 
 fn main() {
-    tauri_plugin::Builder::new(COMMANDS)
-        .android_path("android")
-        .ios_path("ios")
-        .build();
+    tauri_plugin::Builder::new(COMMANDS).ios_path("ios").build();
 }
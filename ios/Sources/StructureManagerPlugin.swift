@@ -0,0 +1,36 @@
+import Foundation
+import Tauri
+
+class PingArgs: Decodable {
+  let value: String?
+}
+
+class AppGroupContainerArgs: Decodable {
+  let group: String
+}
+
+class StructureManagerPlugin: Plugin {
+  @objc public func ping(_ invoke: Invoke) throws {
+    let args = try invoke.parseArgs(PingArgs.self)
+    invoke.resolve(["value": args.value ?? ""])
+  }
+
+  /// Resolves the shared container of an app group, verified by the Rust side like any other root.
+  @objc public func appGroupContainer(_ invoke: Invoke) throws {
+    let args = try invoke.parseArgs(AppGroupContainerArgs.self)
+    guard
+      let url = FileManager.default.containerURL(
+        forSecurityApplicationGroupIdentifier: args.group)
+    else {
+      invoke.reject(
+        "No container for app group \(args.group), is it declared in the App Groups entitlement?")
+      return
+    }
+    invoke.resolve(["path": url.path])
+  }
+}
+
+@_cdecl("init_plugin_structure_manager")
+func initPlugin() -> Plugin {
+  return StructureManagerPlugin()
+}
@@ -0,0 +1,332 @@
+use std::path::Path;
+
+use crate::models::AclTemplate;
+
+/// Returns the name of an ACL template, as in the configuration.
+pub(crate) fn name(template: AclTemplate) -> &'static str {
+    match template {
+        AclTemplate::OwnerOnly => "ownerOnly",
+        AclTemplate::UsersRead => "usersRead",
+        AclTemplate::EveryoneRead => "everyoneRead",
+    }
+}
+
+/// An access allowed entry of a DACL: the SID, the access mask and the inheritance flags.
+#[cfg(windows)]
+type Entry = (Vec<u8>, u32, u8);
+
+/// Returns the entries granted by an ACL template: full control for the current user and the system account, and
+/// read access for the local users or everyone.
+///
+/// Well-known SIDs are used rather than account names, so the entries don't depend on the system language.
+#[cfg(windows)]
+fn entries(template: AclTemplate) -> std::result::Result<Vec<Entry>, String> {
+    use windows_sys::Win32::{
+        Security::{
+            WinBuiltinUsersSid, WinLocalSystemSid, WinWorldSid, CONTAINER_INHERIT_ACE,
+            OBJECT_INHERIT_ACE,
+        },
+        Storage::FileSystem::{FILE_ALL_ACCESS, FILE_GENERIC_EXECUTE, FILE_GENERIC_READ},
+    };
+
+    let inherit = (OBJECT_INHERIT_ACE | CONTAINER_INHERIT_ACE) as u8;
+    let read = FILE_GENERIC_READ | FILE_GENERIC_EXECUTE;
+    let mut entries = vec![
+        (current_user()?, FILE_ALL_ACCESS, inherit),
+        (well_known(WinLocalSystemSid)?, FILE_ALL_ACCESS, inherit),
+    ];
+    match template {
+        AclTemplate::OwnerOnly => {}
+        AclTemplate::UsersRead => entries.push((well_known(WinBuiltinUsersSid)?, read, inherit)),
+        AclTemplate::EveryoneRead => entries.push((well_known(WinWorldSid)?, read, inherit)),
+    }
+    entries.sort();
+    Ok(entries)
+}
+
+/// Returns a well-known SID, e.g. the one of the system account.
+#[cfg(windows)]
+fn well_known(
+    kind: windows_sys::Win32::Security::WELL_KNOWN_SID_TYPE,
+) -> std::result::Result<Vec<u8>, String> {
+    use windows_sys::Win32::Security::{CreateWellKnownSid, SECURITY_MAX_SID_SIZE};
+
+    let mut sid = vec![0u8; SECURITY_MAX_SID_SIZE as usize];
+    let mut size = SECURITY_MAX_SID_SIZE;
+    // SAFETY: the buffer holds `size` bytes, the largest SID.
+    let created = unsafe {
+        CreateWellKnownSid(
+            kind,
+            std::ptr::null_mut(),
+            sid.as_mut_ptr().cast(),
+            &mut size,
+        )
+    };
+    if created == 0 {
+        return Err(format!(
+            "Failed to create a well-known SID, error: {:?}",
+            std::io::Error::last_os_error()
+        ));
+    }
+    sid.truncate(size as usize);
+    Ok(sid)
+}
+
+/// Returns the SID of the user running the app.
+#[cfg(windows)]
+fn current_user() -> std::result::Result<Vec<u8>, String> {
+    use windows_sys::Win32::{
+        Foundation::CloseHandle,
+        Security::{GetTokenInformation, TokenUser, TOKEN_QUERY, TOKEN_USER},
+        System::Threading::{GetCurrentProcess, OpenProcessToken},
+    };
+
+    let failed = || {
+        format!(
+            "Failed to resolve the current user for ACL template, error: {:?}",
+            std::io::Error::last_os_error()
+        )
+    };
+
+    let mut token = std::ptr::null_mut();
+    // SAFETY: the pseudo handle of the current process doesn't need to be closed.
+    if unsafe { OpenProcessToken(GetCurrentProcess(), TOKEN_QUERY, &mut token) } == 0 {
+        return Err(failed());
+    }
+
+    let mut size = 0;
+    // SAFETY: the first call only returns the size of the information, into `size`.
+    unsafe { GetTokenInformation(token, TokenUser, std::ptr::null_mut(), 0, &mut size) };
+    // `u64`s, so the information is aligned for the pointer it starts with.
+    let mut buffer = vec![0u64; (size as usize + 7) / 8];
+    // SAFETY: the buffer holds at least `size` bytes, and the token is closed once read.
+    let read = unsafe {
+        let read = GetTokenInformation(
+            token,
+            TokenUser,
+            buffer.as_mut_ptr().cast(),
+            size,
+            &mut size,
+        );
+        CloseHandle(token);
+        read
+    };
+    if read == 0 {
+        return Err(failed());
+    }
+
+    // SAFETY: the buffer holds a `TOKEN_USER`, whose SID points into the buffer.
+    Ok(unsafe { sid_bytes((*buffer.as_ptr().cast::<TOKEN_USER>()).User.Sid) })
+}
+
+/// Copies a SID.
+///
+/// # Safety
+///
+/// `sid` must point to a valid SID.
+#[cfg(windows)]
+unsafe fn sid_bytes(sid: windows_sys::Win32::Security::PSID) -> Vec<u8> {
+    let length = windows_sys::Win32::Security::GetLengthSid(sid) as usize;
+    std::slice::from_raw_parts(sid.cast::<u8>(), length).to_vec()
+}
+
+/// Returns the path as a null-terminated wide string, for the security APIs.
+#[cfg(windows)]
+fn wide(path: &Path) -> Vec<u16> {
+    use std::os::windows::ffi::OsStrExt;
+
+    crate::long_path::extended(path)
+        .as_os_str()
+        .encode_wide()
+        .chain(Some(0))
+        .collect()
+}
+
+/// Returns whether the ACL of an existing directory differs from an ACL template.
+///
+/// On Windows, the DACL of the directory must not inherit entries from its parent and must hold exactly the entries
+/// granted by the template. On other platforms nothing differs.
+#[cfg(windows)]
+pub(crate) fn differs(path: &Path, template: AclTemplate) -> std::result::Result<bool, String> {
+    use windows_sys::Win32::{
+        Foundation::{LocalFree, ERROR_SUCCESS},
+        Security::{
+            AclSizeInformation,
+            Authorization::{GetNamedSecurityInfoW, SE_FILE_OBJECT},
+            GetAce, GetAclInformation, GetSecurityDescriptorControl, ACCESS_ALLOWED_ACE,
+            ACE_HEADER, ACL, ACL_SIZE_INFORMATION, DACL_SECURITY_INFORMATION, SE_DACL_PROTECTED,
+        },
+        System::SystemServices::ACCESS_ALLOWED_ACE_TYPE,
+    };
+
+    let expected = entries(template)?;
+    let name = wide(path);
+    let mut dacl: *mut ACL = std::ptr::null_mut();
+    let mut descriptor = std::ptr::null_mut();
+    // SAFETY: the name is null-terminated, and the descriptor holding the DACL is freed below.
+    let error = unsafe {
+        GetNamedSecurityInfoW(
+            name.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &mut dacl,
+            std::ptr::null_mut(),
+            &mut descriptor,
+        )
+    };
+    if error != ERROR_SUCCESS {
+        return Err(format!(
+            "Failed to read the ACL of {:?}, error: {:?}",
+            path,
+            std::io::Error::from_raw_os_error(error as i32)
+        ));
+    }
+
+    // SAFETY: the DACL lives in the descriptor, each entry is read within the size reported by its header.
+    let actual = unsafe {
+        let mut control = 0;
+        let mut revision = 0;
+        let protected = GetSecurityDescriptorControl(descriptor, &mut control, &mut revision) != 0
+            && control & SE_DACL_PROTECTED != 0;
+        let mut information = ACL_SIZE_INFORMATION {
+            AceCount: 0,
+            AclBytesInUse: 0,
+            AclBytesFree: 0,
+        };
+        // A null DACL grants everything to everyone.
+        let read = !dacl.is_null()
+            && GetAclInformation(
+                dacl,
+                (&mut information as *mut ACL_SIZE_INFORMATION).cast(),
+                std::mem::size_of::<ACL_SIZE_INFORMATION>() as u32,
+                AclSizeInformation,
+            ) != 0;
+
+        let mut actual = Vec::new();
+        if protected && read {
+            for index in 0..information.AceCount {
+                let mut ace = std::ptr::null_mut();
+                if GetAce(dacl, index, &mut ace) == 0 {
+                    actual.clear();
+                    break;
+                }
+                let header = *ace.cast::<ACE_HEADER>();
+                if u32::from(header.AceType) != ACCESS_ALLOWED_ACE_TYPE {
+                    // A denied or audited entry isn't part of any template.
+                    actual.clear();
+                    break;
+                }
+                let allowed = ace.cast::<ACCESS_ALLOWED_ACE>();
+                actual.push((
+                    sid_bytes(std::ptr::addr_of_mut!((*allowed).SidStart).cast()),
+                    (*allowed).Mask,
+                    header.AceFlags,
+                ));
+            }
+        }
+        LocalFree(descriptor);
+        actual.sort();
+        actual
+    };
+
+    Ok(actual != expected)
+}
+
+/// Returns whether the ACL of an existing directory differs from an ACL template.
+///
+/// On Windows, the DACL of the directory must not inherit entries from its parent and must hold exactly the entries
+/// granted by the template. On other platforms nothing differs.
+#[cfg(not(windows))]
+pub(crate) fn differs(_path: &Path, _template: AclTemplate) -> std::result::Result<bool, String> {
+    Ok(false)
+}
+
+/// Applies an ACL template to the given path.
+///
+/// On Windows, the DACL of the directory is replaced with `SetNamedSecurityInfoW` by a protected one holding the
+/// entries granted by the template, so the entries inherited from its parent are removed. On other platforms this is
+/// a no-op.
+#[cfg(windows)]
+pub(crate) fn apply(path: &Path, template: AclTemplate) -> std::result::Result<(), String> {
+    use windows_sys::Win32::{
+        Foundation::ERROR_SUCCESS,
+        Security::{
+            AddAccessAllowedAceEx,
+            Authorization::{SetNamedSecurityInfoW, SE_FILE_OBJECT},
+            InitializeAcl, ACCESS_ALLOWED_ACE, ACL, ACL_REVISION, DACL_SECURITY_INFORMATION,
+            PROTECTED_DACL_SECURITY_INFORMATION,
+        },
+    };
+
+    let failed = |e: std::io::Error| {
+        format!(
+            "Failed to apply ACL template {:?} to {:?}, error: {:?}",
+            template, path, e
+        )
+    };
+
+    let entries = entries(template)?;
+    // Each entry ends with its SID, in place of the `SidStart` field.
+    let size = std::mem::size_of::<ACL>()
+        + entries
+            .iter()
+            .map(|(sid, ..)| {
+                std::mem::size_of::<ACCESS_ALLOWED_ACE>() - std::mem::size_of::<u32>() + sid.len()
+            })
+            .sum::<usize>();
+    // `u32`s, as an ACL is aligned on a `u32`.
+    let mut buffer = vec![0u32; (size + 3) / 4];
+    let acl = buffer.as_mut_ptr().cast::<ACL>();
+
+    // SAFETY: the ACL is built in a buffer large enough for all of its entries, and outlives the call setting it.
+    unsafe {
+        if InitializeAcl(acl, (buffer.len() * 4) as u32, ACL_REVISION) == 0 {
+            return Err(failed(std::io::Error::last_os_error()));
+        }
+        for (sid, mask, flags) in &entries {
+            if AddAccessAllowedAceEx(
+                acl,
+                ACL_REVISION,
+                u32::from(*flags),
+                *mask,
+                sid.as_ptr().cast_mut().cast(),
+            ) == 0
+            {
+                return Err(failed(std::io::Error::last_os_error()));
+            }
+        }
+
+        let name = wide(path);
+        let error = SetNamedSecurityInfoW(
+            name.as_ptr(),
+            SE_FILE_OBJECT,
+            DACL_SECURITY_INFORMATION | PROTECTED_DACL_SECURITY_INFORMATION,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            acl,
+            std::ptr::null(),
+        );
+        if error != ERROR_SUCCESS {
+            return Err(failed(std::io::Error::from_raw_os_error(error as i32)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Applies an ACL template to the given path.
+///
+/// On Windows, the DACL of the directory is replaced with `SetNamedSecurityInfoW` by a protected one holding the
+/// entries granted by the template, so the entries inherited from its parent are removed. On other platforms this is
+/// a no-op.
+#[cfg(not(windows))]
+pub(crate) fn apply(path: &Path, template: AclTemplate) -> std::result::Result<(), String> {
+    log::debug!(
+        "Ignoring ACL template {:?} for {:?} on this platform",
+        template,
+        path
+    );
+    Ok(())
+}
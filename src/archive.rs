@@ -0,0 +1,400 @@
+use std::path::{Path, PathBuf};
+#[cfg(feature = "archive")]
+use std::{collections::BTreeMap, fs::File, io};
+
+use tauri::{path::BaseDirectory, Manager, Runtime};
+
+use crate::roots;
+#[cfg(feature = "archive")]
+use crate::{
+    conditions::is_active, ignore::IgnoreSet, long_path::extended, models::*,
+    normalization::same_name,
+};
+
+/// Returns the bundled archive of an `archive` option, relative to `resource_dir()`.
+pub(crate) fn source_of<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    archive: &str,
+) -> std::result::Result<PathBuf, String> {
+    roots::resolve(manager, BaseDirectory::Resource).map(|resources| resources.join(archive))
+}
+
+/// Extracts a zip archive as the missing directory `target`.
+///
+/// The archive is extracted next to `target` first, then renamed to it, so a failed extraction leaves no partly
+/// extracted directory behind. Entries escaping the directory (e.g. `../settings.json`) fail the extraction, and
+/// symbolic links are left out.
+#[cfg(feature = "archive")]
+pub(crate) fn extract(archive: &Path, target: &Path) -> std::result::Result<(), String> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("extract_archive", archive = %archive.display()).entered();
+
+    let name = target
+        .file_name()
+        .ok_or_else(|| format!("Invalid directory: {:?}", target))?;
+    let mut temporary_name = std::ffi::OsString::from(".");
+    temporary_name.push(name);
+    temporary_name.push(".extracting");
+    let temporary = target.with_file_name(temporary_name);
+
+    // A previous extraction may have been interrupted.
+    if extended(&temporary).exists() {
+        std::fs::remove_dir_all(extended(&temporary))
+            .map_err(|e| format!("Failed to delete: {:?}, error: {:?}", temporary, e))?;
+    }
+
+    let result = extract_into(archive, &temporary).and_then(|_| {
+        std::fs::rename(extended(&temporary), extended(target)).map_err(|e| {
+            format!(
+                "Failed to rename {:?} to {:?}, error: {:?}",
+                temporary, target, e
+            )
+        })
+    });
+    if result.is_err() {
+        let _ = std::fs::remove_dir_all(extended(&temporary));
+    }
+    result
+}
+
+/// Fails to extract a zip archive, extractions require the `archive` feature.
+#[cfg(not(feature = "archive"))]
+pub(crate) fn extract(archive: &Path, _target: &Path) -> std::result::Result<(), String> {
+    Err(format!(
+        "Failed to extract {:?}: the `archive` feature of the plugin is disabled",
+        archive
+    ))
+}
+
+/// Returns the size of the files of a zip archive once extracted, as declared by the archive.
+#[cfg(feature = "archive")]
+pub(crate) fn extracted_size(archive: &Path) -> std::result::Result<u64, String> {
+    let file = File::open(extended(archive))
+        .map_err(|e| format!("Failed to open archive: {:?}, error: {:?}", archive, e))?;
+    let mut zip = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive: {:?}, error: {}", archive, e))?;
+
+    let mut size = 0;
+    for index in 0..zip.len() {
+        let entry = zip
+            .by_index_raw(index)
+            .map_err(|e| format!("Failed to read archive: {:?}, error: {}", archive, e))?;
+        size += entry.size();
+    }
+    Ok(size)
+}
+
+/// Returns nothing to extract without the `archive` feature, extractions fail anyway.
+#[cfg(not(feature = "archive"))]
+pub(crate) fn extracted_size(_archive: &Path) -> std::result::Result<u64, String> {
+    Ok(0)
+}
+
+#[cfg(feature = "archive")]
+fn extract_into(archive: &Path, directory: &Path) -> std::result::Result<(), String> {
+    let file = File::open(extended(archive))
+        .map_err(|e| format!("Failed to open archive: {:?}, error: {:?}", archive, e))?;
+    let mut zip = zip::ZipArchive::new(file)
+        .map_err(|e| format!("Failed to read archive: {:?}, error: {}", archive, e))?;
+
+    std::fs::create_dir_all(extended(directory)).map_err(|e| {
+        format!(
+            "Failed to create directory: {:?}, error: {:?}",
+            directory, e
+        )
+    })?;
+    for index in 0..zip.len() {
+        let mut entry = zip
+            .by_index(index)
+            .map_err(|e| format!("Failed to read archive: {:?}, error: {}", archive, e))?;
+        let Some(name) = entry.enclosed_name() else {
+            return Err(format!(
+                "Invalid entry in archive: {:?}, entry: {:?}",
+                archive,
+                entry.name()
+            ));
+        };
+        if entry.is_symlink() {
+            continue;
+        }
+
+        let path = directory.join(name);
+        if entry.is_dir() {
+            std::fs::create_dir_all(extended(&path))
+                .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", path, e))?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(extended(parent))
+                .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", parent, e))?;
+        }
+        let mut output = File::create(extended(&path))
+            .map_err(|e| format!("Failed to create file: {:?}, error: {:?}", path, e))?;
+        io::copy(&mut entry, &mut output)
+            .map_err(|e| format!("Failed to extract {:?}, error: {:?}", path, e))?;
+    }
+
+    Ok(())
+}
+
+/// The entries of a zip or tar.gz archive, read without extracting it, see `StructureManagerExt::verify_archive`.
+#[cfg(feature = "archive")]
+pub struct ArchiveRoot {
+    path: PathBuf,
+    /// The entries of the archive by path, relative to its root with `/` separators.
+    entries: BTreeMap<String, EntryKind>,
+}
+
+#[cfg(feature = "archive")]
+impl ArchiveRoot {
+    /// Reads the entries of a `.zip`, `.tar.gz` or `.tgz` archive.
+    ///
+    /// Directories without an entry of their own in the archive are inferred from the paths of their contents, and
+    /// entries escaping the archive (e.g. `../settings.json`) are left out.
+    pub fn open(path: impl AsRef<Path>) -> std::result::Result<Self, String> {
+        let path = path.as_ref().to_path_buf();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let file = File::open(extended(&path))
+            .map_err(|e| format!("Failed to open archive: {:?}, error: {:?}", path, e))?;
+
+        let mut archive = ArchiveRoot {
+            path,
+            entries: BTreeMap::new(),
+        };
+        if name.ends_with(".zip") {
+            let zip = zip::ZipArchive::new(file).map_err(|e| archive.read_error(e))?;
+            for name in zip.file_names() {
+                archive.insert(name, name.ends_with('/'));
+            }
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+            let entries = tar.entries().map_err(|e| archive.read_error(e))?;
+            for entry in entries {
+                let entry = entry.map_err(|e| archive.read_error(e))?;
+                let entry_path = entry.path().map_err(|e| archive.read_error(e))?;
+                let is_dir = entry.header().entry_type().is_dir();
+                archive.insert(&entry_path.to_string_lossy(), is_dir);
+            }
+        } else {
+            return Err(format!(
+                "Unsupported archive format: {:?}, expected a .zip, .tar.gz or .tgz file",
+                archive.path
+            ));
+        }
+
+        Ok(archive)
+    }
+
+    /// Returns the path of the archive.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the kind of an entry of the archive, given its path relative to the root of the archive with `/`
+    /// separators, or `None` if it doesn't exist.
+    pub fn entry(&self, path: &str) -> Option<EntryKind> {
+        self.entries.get(path).copied()
+    }
+
+    fn read_error(&self, e: impl std::fmt::Display) -> String {
+        format!("Failed to read archive: {:?}, error: {}", self.path, e)
+    }
+
+    fn insert(&mut self, name: &str, is_dir: bool) {
+        let mut components = Vec::new();
+        for component in name.split(['/', '\\']) {
+            match component {
+                "" | "." => continue,
+                ".." => return,
+                component => components.push(component),
+            }
+        }
+
+        for depth in 1..components.len() {
+            self.entries
+                .insert(components[..depth].join("/"), EntryKind::Directory);
+        }
+        if !components.is_empty() {
+            let kind = match is_dir {
+                true => EntryKind::Directory,
+                false => EntryKind::File,
+            };
+            self.entries.entry(components.join("/")).or_insert(kind);
+        }
+    }
+
+    /// Returns the names and kinds of the entries directly inside a directory of the archive, `""` being its root.
+    fn children<'a>(&'a self, dir: &'a str) -> impl Iterator<Item = (&'a str, EntryKind)> + 'a {
+        self.entries.iter().filter_map(move |(path, kind)| {
+            let name = match dir {
+                "" => path.as_str(),
+                dir => path.strip_prefix(dir)?.strip_prefix('/')?,
+            };
+            (!name.contains('/')).then_some((name, *kind))
+        })
+    }
+
+    /// Returns the path of a declared entry of a directory of the archive, see `normalization::locate`.
+    fn locate(
+        &self,
+        dir: &str,
+        name: &str,
+        form: Option<UnicodeNormalization>,
+    ) -> Option<(String, EntryKind)> {
+        let path = join(dir, name);
+        if let Some(kind) = self.entry(&path) {
+            return Some((path, kind));
+        }
+        if form.is_none() || name.is_ascii() {
+            return None;
+        }
+
+        self.children(dir)
+            .find(|(entry_name, _)| same_name(entry_name, name, form))
+            .map(|(entry_name, kind)| (join(dir, entry_name), kind))
+    }
+}
+
+#[cfg(feature = "archive")]
+fn join(dir: &str, name: &str) -> String {
+    match dir {
+        "" => name.to_string(),
+        dir => format!("{}/{}", dir, name),
+    }
+}
+
+/// Verifies a structure item against the entries of an archive, like `Walker` does on disk.
+///
+/// Only the presence of the declared entries (and the absence of undeclared ones with `strict`) is verified:
+/// validators, attributes and quotas need the contents of the entries, and nothing is repaired.
+#[cfg(feature = "archive")]
+pub(crate) struct ArchiveVerifier<'a, R: Runtime, M: Manager<R>> {
+    manager: &'a M,
+    archive: &'a ArchiveRoot,
+    normalization: Option<UnicodeNormalization>,
+    ignore: Vec<String>,
+    _runtime: std::marker::PhantomData<R>,
+}
+
+#[cfg(feature = "archive")]
+impl<'a, R: Runtime, M: Manager<R>> ArchiveVerifier<'a, R, M> {
+    /// Creates a verifier for the root item of a verification, see `Walker::new`.
+    pub(crate) fn new(manager: &'a M, archive: &'a ArchiveRoot, root: &StructureItem) -> Self {
+        Self {
+            manager,
+            archive,
+            normalization: root.unicode_normalization(),
+            ignore: Vec::new(),
+            _runtime: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the glob patterns of entry names ignored by strict comparisons in every directory.
+    pub(crate) fn with_ignore(mut self, ignore: Vec<String>) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
+    /// Verifies the root of the archive against `structure_item`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the archive contains the structure, or `Err(String)` with the first error found.
+    pub(crate) fn verify(&self, structure_item: &StructureItem) -> std::result::Result<(), String> {
+        #[cfg(feature = "tracing")]
+        let _span =
+            tracing::info_span!("archive_verification", archive = %self.archive.path.display())
+                .entered();
+
+        self.verify_within("", structure_item, None)
+    }
+
+    fn verify_within(
+        &self,
+        dir: &str,
+        structure_item: &StructureItem,
+        depth: Option<usize>,
+    ) -> std::result::Result<(), String> {
+        if !is_active(self.manager, structure_item)? {
+            return Ok(());
+        }
+        let depth = structure_item.depth_limit(depth);
+
+        for file in structure_item.files.iter().flatten() {
+            match self.archive.locate(dir, &file.name, self.normalization) {
+                Some((_, EntryKind::File)) => {}
+                _ => self.report(
+                    file.missing_severity(),
+                    format!(
+                        "File not found in archive {:?}: {:?}",
+                        self.archive.path,
+                        join(dir, &file.name)
+                    ),
+                )?,
+            }
+        }
+
+        for (dir_name, sub_item) in structure_item.dirs.iter().flatten() {
+            if !is_active(self.manager, sub_item)? {
+                continue;
+            }
+
+            let sub_dir = match self.archive.locate(dir, dir_name, self.normalization) {
+                Some((sub_dir, EntryKind::Directory)) => sub_dir,
+                _ => {
+                    self.report(
+                        sub_item.missing_severity(),
+                        format!(
+                            "Directory not found in archive {:?}: {:?}",
+                            self.archive.path,
+                            join(dir, dir_name)
+                        ),
+                    )?;
+                    continue;
+                }
+            };
+            // Below the depth limit, directories are only checked for existence.
+            if depth != Some(0) {
+                self.verify_within(&sub_dir, sub_item, depth.map(|depth| depth - 1))?;
+            }
+        }
+
+        if structure_item.options.as_ref().and_then(|o| o.strict) == Some(true) {
+            let own_ignore = structure_item
+                .options
+                .as_ref()
+                .and_then(|options| options.ignore.as_deref())
+                .unwrap_or_default();
+            let ignore = IgnoreSet::from_patterns(self.ignore.iter().chain(own_ignore))?;
+            for (name, _) in self.archive.children(dir) {
+                if structure_item.declares(name, self.normalization) || ignore.is_ignored(name) {
+                    continue;
+                }
+
+                self.report(
+                    structure_item.severity(),
+                    format!(
+                        "Unexpected entry in archive {:?}: {:?}",
+                        self.archive.path,
+                        join(dir, name)
+                    ),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fails the verification with an error, or logs a warning or info, depending on the severity of the rule broken.
+    fn report(&self, severity: Severity, message: String) -> std::result::Result<(), String> {
+        match severity {
+            Severity::Error => return Err(message),
+            Severity::Warning => log::warn!("{}", message),
+            Severity::Info => log::info!("{}", message),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use crate::models::EntryAttributes;
+
+/// The attributes, with their `attrib` flag and their `FILE_ATTRIBUTE_*` bit.
+#[cfg(windows)]
+const ATTRIBUTES: [(&str, char, u32); 3] = [
+    ("hidden", 'H', 0x2),
+    ("readonly", 'R', 0x1),
+    ("system", 'S', 0x4),
+];
+
+/// Returns the expected value of each attribute, in the order of `ATTRIBUTES`.
+#[cfg(windows)]
+fn expected(attributes: &EntryAttributes) -> [Option<bool>; 3] {
+    [attributes.hidden, attributes.readonly, attributes.system]
+}
+
+/// Returns the attributes of an existing entry that differ from the expected ones, as `(name, expected value)`.
+///
+/// On Windows, the `hidden`, `readonly` and `system` attributes are read from the entry. On other platforms nothing
+/// differs.
+#[cfg(windows)]
+pub(crate) fn mismatches(
+    path: &Path,
+    attributes: &EntryAttributes,
+) -> std::result::Result<Vec<(&'static str, bool)>, String> {
+    use std::os::windows::fs::MetadataExt;
+
+    let actual = std::fs::metadata(crate::long_path::extended(path))
+        .map_err(|e| format!("Failed to read attributes: {:?}, error: {:?}", path, e))?
+        .file_attributes();
+
+    Ok(ATTRIBUTES
+        .iter()
+        .zip(expected(attributes))
+        .filter_map(|((name, _, bit), value)| {
+            value
+                .filter(|value| ((actual & bit) != 0) != *value)
+                .map(|value| (*name, value))
+        })
+        .collect())
+}
+
+/// Returns the attributes of an existing entry that differ from the expected ones, as `(name, expected value)`.
+///
+/// On Windows, the `hidden`, `readonly` and `system` attributes are read from the entry. On other platforms nothing
+/// differs.
+#[cfg(not(windows))]
+pub(crate) fn mismatches(
+    _path: &Path,
+    _attributes: &EntryAttributes,
+) -> std::result::Result<Vec<(&'static str, bool)>, String> {
+    Ok(Vec::new())
+}
+
+/// Sets the expected attributes of an existing entry.
+///
+/// On Windows, the attributes are set using `attrib`. On other platforms this is a no-op.
+#[cfg(windows)]
+pub(crate) fn apply(path: &Path, attributes: &EntryAttributes) -> std::result::Result<(), String> {
+    use std::process::Command;
+
+    let mut command = Command::new("attrib");
+    for ((_, flag, _), value) in ATTRIBUTES.iter().zip(expected(attributes)) {
+        if let Some(value) = value {
+            command.arg(format!("{}{}", if value { '+' } else { '-' }, flag));
+        }
+    }
+    command.arg(path);
+
+    let output = command
+        .output()
+        .map_err(|e| format!("Failed to run attrib on {:?}, error: {:?}", path, e))?;
+    // `attrib` reports some failures on stdout with a success status, the attributes are read back instead.
+    let remaining = mismatches(path, attributes)?;
+    if !output.status.success() || !remaining.is_empty() {
+        return Err(format!(
+            "Failed to set attributes of {:?} ({}): {}",
+            path,
+            describe(&remaining),
+            String::from_utf8_lossy(&output.stdout).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Sets the expected attributes of an existing entry.
+///
+/// On Windows, the attributes are set using `attrib`. On other platforms this is a no-op.
+#[cfg(not(windows))]
+pub(crate) fn apply(path: &Path, attributes: &EntryAttributes) -> std::result::Result<(), String> {
+    log::debug!(
+        "Ignoring attributes {:?} for {:?} on this platform",
+        attributes,
+        path
+    );
+    Ok(())
+}
+
+/// A permission of a file checked on every platform it applies to, see the `readonly` and `executable` file options.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Permission {
+    /// The read-only attribute on Windows, no write permission for anyone on Unix.
+    Readonly,
+    /// An execute permission on Unix, not checked elsewhere.
+    Executable,
+}
+
+impl Permission {
+    /// Returns the name of the permission, as in the configuration.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Permission::Readonly => "readonly",
+            Permission::Executable => "executable",
+        }
+    }
+
+    /// Returns whether an existing file has the permission. Outside of Unix, a file is always executable.
+    pub(crate) fn is_set(self, path: &Path) -> std::result::Result<bool, String> {
+        let permissions = std::fs::metadata(crate::long_path::extended(path))
+            .map_err(|e| format!("Failed to read permissions: {:?}, error: {:?}", path, e))?
+            .permissions();
+
+        Ok(match self {
+            Permission::Readonly => permissions.readonly(),
+            #[cfg(unix)]
+            Permission::Executable => {
+                use std::os::unix::fs::PermissionsExt;
+                permissions.mode() & 0o111 != 0
+            }
+            #[cfg(not(unix))]
+            Permission::Executable => true,
+        })
+    }
+
+    /// Grants or removes the permission of an existing file.
+    ///
+    /// On Unix, making a file writable only grants the owner's write permission, and making it executable grants
+    /// the execute permission to those who can read it.
+    pub(crate) fn set(self, path: &Path, value: bool) -> std::result::Result<(), String> {
+        let mut permissions = std::fs::metadata(crate::long_path::extended(path))
+            .map_err(|e| format!("Failed to read permissions: {:?}, error: {:?}", path, e))?
+            .permissions();
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = permissions.mode();
+            permissions.set_mode(match (self, value) {
+                (Permission::Readonly, true) => mode & !0o222,
+                (Permission::Readonly, false) => mode | 0o200,
+                (Permission::Executable, true) => mode | ((mode & 0o444) >> 2),
+                (Permission::Executable, false) => mode & !0o111,
+            });
+        }
+        #[cfg(not(unix))]
+        match self {
+            #[allow(clippy::permissions_set_readonly_false)]
+            Permission::Readonly => permissions.set_readonly(value),
+            Permission::Executable => return Ok(()),
+        }
+
+        std::fs::set_permissions(crate::long_path::extended(path), permissions)
+            .map_err(|e| format!("Failed to set permissions of {:?}, error: {:?}", path, e))
+    }
+}
+
+/// Returns whether an entry is a junction, a symbolic link or another reparse point, e.g. a OneDrive placeholder.
+///
+/// Reparse points are only detected on Windows, on other platforms this is always false.
+#[cfg(windows)]
+pub(crate) fn is_reparse_point(path: &Path) -> bool {
+    use std::os::windows::fs::MetadataExt;
+
+    const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+
+    std::fs::symlink_metadata(crate::long_path::extended(path))
+        .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0)
+}
+
+/// Returns whether an entry is a junction, a symbolic link or another reparse point, e.g. a OneDrive placeholder.
+///
+/// Reparse points are only detected on Windows, on other platforms this is always false.
+#[cfg(not(windows))]
+pub(crate) fn is_reparse_point(_path: &Path) -> bool {
+    false
+}
+
+/// Describes the expected value of mismatched attributes, e.g. `hidden, not readonly`.
+pub(crate) fn describe(mismatches: &[(&str, bool)]) -> String {
+    mismatches
+        .iter()
+        .map(|(name, value)| match value {
+            true => name.to_string(),
+            false => format!("not {}", name),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
@@ -0,0 +1,164 @@
+use std::path::{Path, PathBuf};
+
+use crate::models::*;
+
+/// A security issue found by the `securityAudit` option.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(not(unix), allow(dead_code))]
+pub(crate) enum Finding {
+    /// A directory anyone can write to, without the sticky bit.
+    WorldWritable,
+    /// A file running with the privileges of its owner or group: setuid or setgid.
+    Setuid,
+    /// An entry owned by another user than its declared `owner`, or the user the app runs as.
+    UnexpectedOwner { expected: u32, found: u32 },
+}
+
+/// Audits the permissions of a root and of every entry inside it, declared or not, leaving out the other configured
+/// roots nested inside it.
+///
+/// Entries are expected to be owned by the user the app runs as, unless they (or the declared directory they are in)
+/// declare another `owner`. Symbolic links aren't followed.
+///
+/// Only Unix permissions are audited, on other platforms nothing is found.
+#[cfg(unix)]
+pub(crate) fn audit(
+    path: &Path,
+    structure_item: &StructureItem,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<Vec<(PathBuf, Finding)>, String> {
+    let mut auditor = Auditor {
+        normalization,
+        nested_roots,
+        findings: Vec::new(),
+    };
+    if let Ok(metadata) = std::fs::symlink_metadata(crate::long_path::extended(path)) {
+        auditor.permissions(path, &metadata);
+    }
+    auditor.audit_dir(path, Some(structure_item), uzers::get_effective_uid())?;
+
+    Ok(auditor.findings)
+}
+
+/// Audits the permissions of a root and of every entry inside it, declared or not, leaving out the other configured
+/// roots nested inside it.
+///
+/// Entries are expected to be owned by the user the app runs as, unless they (or the declared directory they are in)
+/// declare another `owner`. Symbolic links aren't followed.
+///
+/// Only Unix permissions are audited, on other platforms nothing is found.
+#[cfg(not(unix))]
+pub(crate) fn audit(
+    _path: &Path,
+    _structure_item: &StructureItem,
+    _normalization: Option<UnicodeNormalization>,
+    _nested_roots: &[PathBuf],
+) -> std::result::Result<Vec<(PathBuf, Finding)>, String> {
+    Ok(Vec::new())
+}
+
+#[cfg(unix)]
+struct Auditor<'a> {
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &'a [PathBuf],
+    findings: Vec<(PathBuf, Finding)>,
+}
+
+#[cfg(unix)]
+impl Auditor<'_> {
+    /// Audits the entries of a directory, recursively.
+    ///
+    /// # Arguments
+    ///
+    /// * `structure_item` - The structure item declaring the directory, `None` below an undeclared directory.
+    /// * `owner` - The user id its undeclared entries are expected to be owned by.
+    fn audit_dir(
+        &mut self,
+        path: &Path,
+        structure_item: Option<&StructureItem>,
+        owner: u32,
+    ) -> std::result::Result<(), String> {
+        use std::os::unix::fs::MetadataExt;
+
+        let entries = match std::fs::read_dir(crate::long_path::extended(path)) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read directory: {:?}, error: {:?}",
+                    path, e
+                ))
+            }
+        };
+        for entry in entries {
+            let entry = entry
+                .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+            let entry_path = path.join(entry.file_name());
+            if self.nested_roots.contains(&entry_path) {
+                continue;
+            }
+            let Ok(metadata) = std::fs::symlink_metadata(crate::long_path::extended(&entry_path))
+            else {
+                continue;
+            };
+
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let declared_dir = structure_item.and_then(|item| {
+                item.dirs
+                    .iter()
+                    .flatten()
+                    .find(|(declared, _)| {
+                        crate::normalization::same_name(declared, &name, self.normalization)
+                    })
+                    .map(|(_, dir)| dir)
+            });
+            let declared_owner = match declared_dir {
+                Some(dir) => dir
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.owner.as_deref()),
+                None => structure_item
+                    .and_then(|item| {
+                        item.files.iter().flatten().find(|file| {
+                            crate::normalization::same_name(&file.name, &name, self.normalization)
+                        })
+                    })
+                    .and_then(|file| file.options.owner.as_deref()),
+            };
+            let expected = match declared_owner {
+                Some(declared) => crate::owner::user_id(declared)?,
+                None => owner,
+            };
+
+            if metadata.uid() != expected {
+                self.findings.push((
+                    entry_path.clone(),
+                    Finding::UnexpectedOwner {
+                        expected,
+                        found: metadata.uid(),
+                    },
+                ));
+            }
+            self.permissions(&entry_path, &metadata);
+            if metadata.is_dir() {
+                self.audit_dir(&entry_path, declared_dir, expected)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Audits the mode of an entry.
+    fn permissions(&mut self, path: &Path, metadata: &std::fs::Metadata) {
+        use std::os::unix::fs::MetadataExt;
+
+        let mode = metadata.mode();
+        if metadata.is_dir() && mode & 0o002 != 0 && mode & 0o1000 == 0 {
+            self.findings
+                .push((path.to_path_buf(), Finding::WorldWritable));
+        } else if metadata.is_file() && mode & 0o6000 != 0 {
+            self.findings.push((path.to_path_buf(), Finding::Setuid));
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! Helpers for the build script of the host app.
+
+use std::path::{Path, PathBuf};
+
+use crate::models::StructureConfig;
+
+/// Validates a structure configuration file from a build script, failing the build if it is malformed.
+///
+/// See [`try_validate`] for the checks performed.
+///
+/// ```no_run
+/// // build.rs, before `tauri_build::build()`
+/// tauri_plugin_structure_manager::build::validate("structure.json");
+/// ```
+///
+/// # Panics
+///
+/// Panics with the validation error if the configuration is malformed.
+pub fn validate(path: impl AsRef<Path>) {
+    if let Err(e) = try_validate(path) {
+        panic!("{}", e);
+    }
+}
+
+/// Validates a structure configuration file, also asking Cargo to run the build script again when it changes.
+///
+/// The file is rejected if it can't be parsed, declares unknown fields, declares names that aren't a single path
+/// component, or has unknown or cyclic `$ref`s (with and without each of its profiles applied).
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the configuration is valid, or `Err(String)` with every unknown field or the first error found.
+pub fn try_validate(path: impl AsRef<Path>) -> std::result::Result<(), String> {
+    let path = path.as_ref();
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {:?}, error: {:?}", path, e))?;
+
+    parse(&content, path).map(|_| ())
+}
+
+/// Parses and validates a structure configuration, see [`try_validate`].
+///
+/// # Arguments
+///
+/// * `content` - The JSON structure configuration.
+/// * `path` - The path of the configuration file, used in error messages.
+fn parse(content: &str, path: &Path) -> std::result::Result<StructureConfig, String> {
+    let config = parse_strict(
+        &mut serde_json::Deserializer::from_str(content),
+        &path.to_string_lossy(),
+    )?;
+
+    let invalid = |e: String| format!("Invalid structure configuration {:?}: {}", path, e);
+    config.check_entry_names().map_err(invalid)?;
+    config.clone().resolve_refs().map_err(invalid)?;
+    for name in config.profiles.iter().flat_map(|profiles| profiles.keys()) {
+        let mut profiled = config.clone();
+        profiled.apply_profile(name).map_err(invalid)?;
+        profiled.check_entry_names().map_err(invalid)?;
+        profiled
+            .resolve_refs()
+            .map_err(|e| invalid(format!("profile `{}`: {}", name, e)))?;
+    }
+
+    Ok(config)
+}
+
+/// Deserializes a structure configuration, rejecting unknown fields instead of ignoring them.
+///
+/// # Arguments
+///
+/// * `deserializer` - The deserializer of the configuration.
+/// * `origin` - Where the configuration comes from, used in error messages.
+pub(crate) fn parse_strict<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+    origin: &str,
+) -> std::result::Result<StructureConfig, String> {
+    let mut unknown_fields = Vec::new();
+    let config: StructureConfig =
+        serde_ignored::deserialize(deserializer, |field| unknown_fields.push(field.to_string()))
+            .map_err(|e| format!("Invalid structure configuration {:?}: {}", origin, e))?;
+
+    if !unknown_fields.is_empty() {
+        return Err(format!(
+            "Unknown fields in structure configuration {:?}: {}",
+            origin,
+            unknown_fields.join(", ")
+        ));
+    }
+
+    Ok(config)
+}
+
+/// Validates a structure configuration file and embeds it in the app for [`include_structure!`](crate::include_structure),
+/// from the build script of the app.
+///
+/// The file is validated like [`try_validate`] and copied to `OUT_DIR`. When it is invalid, the error is reported by
+/// the compiler where `include_structure!` is used, as a `compile_error!`, so the build fails.
+///
+/// ```no_run
+/// // build.rs, before `tauri_build::build()`
+/// tauri_plugin_structure_manager::build::embed("structure.json");
+/// ```
+///
+/// # Panics
+///
+/// Panics if it isn't called from a build script, or if `OUT_DIR` can't be written.
+pub fn embed(path: impl AsRef<Path>) {
+    let path = path.as_ref();
+    let out_dir = PathBuf::from(
+        std::env::var_os("OUT_DIR").expect("`embed` must be called from a build script"),
+    );
+
+    println!("cargo:rerun-if-changed={}", path.display());
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {:?}, error: {:?}", path, e));
+    let check = match content.clone().and_then(|content| parse(&content, path)) {
+        Ok(_) => "()".to_string(),
+        Err(e) => format!("compile_error!({:?})", e),
+    };
+
+    for (name, content) in [
+        ("structure-manager.json", content.unwrap_or_default()),
+        ("structure-manager.rs", check),
+    ] {
+        let target = out_dir.join(name);
+        if let Err(e) = std::fs::write(&target, content) {
+            panic!("Failed to write {:?}, error: {:?}", target, e);
+        }
+    }
+}
+
+/// Parses a configuration embedded by [`include_structure!`](crate::include_structure), validated by [`embed`].
+#[doc(hidden)]
+pub fn parse_embedded(content: &str) -> StructureConfig {
+    match serde_json::from_str(content) {
+        Ok(config) => config,
+        Err(e) => panic!("Invalid embedded structure configuration: {}", e),
+    }
+}
+
+/// Returns the structure configuration embedded by [`embed`] from the build script, as a `&'static StructureConfig`.
+///
+/// The configuration is validated when building, an invalid file fails the build. It is deserialized once, the first
+/// time it is used.
+///
+/// ```ignore
+/// tauri::Builder::default()
+///     .plugin(tauri_plugin_structure_manager::init_with_config(
+///         tauri_plugin_structure_manager::include_structure!().clone(),
+///     ))
+/// ```
+#[macro_export]
+macro_rules! include_structure {
+    () => {{
+        include!(concat!(env!("OUT_DIR"), "/structure-manager.rs"));
+        static CONFIG: ::std::sync::OnceLock<$crate::StructureConfig> =
+            ::std::sync::OnceLock::new();
+        CONFIG.get_or_init(|| {
+            $crate::build::parse_embedded(include_str!(concat!(
+                env!("OUT_DIR"),
+                "/structure-manager.json"
+            )))
+        })
+    }};
+}
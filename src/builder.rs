@@ -0,0 +1,470 @@
+use std::path::PathBuf;
+
+use tauri::path::BaseDirectory;
+
+use crate::models::*;
+
+/// Fluent builder of a [`StructureItem`].
+///
+/// ```
+/// use tauri_plugin_structure_manager::StructureItem;
+///
+/// let item = StructureItem::builder()
+///     .file("settings.json")
+///     .dir(StructureItem::dir("db").file("index.json").repair(true))
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StructureItemBuilder {
+    name: String,
+    item: StructureItem,
+}
+
+impl StructureItem {
+    /// Creates a builder for an item without a name, e.g. the root of a structure.
+    pub fn builder() -> StructureItemBuilder {
+        StructureItemBuilder::default()
+    }
+
+    /// Creates a builder for a directory named `name`, to be added to its parent with [`StructureItemBuilder::dir`].
+    pub fn dir(name: impl Into<String>) -> StructureItemBuilder {
+        StructureItemBuilder {
+            name: name.into(),
+            item: StructureItem::default(),
+        }
+    }
+}
+
+impl StructureItemBuilder {
+    /// Declares a file, by name or as a [`StructureFile`] with options.
+    pub fn file(mut self, file: impl Into<StructureFile>) -> Self {
+        self.item
+            .files
+            .get_or_insert_with(Vec::new)
+            .push(file.into());
+        self
+    }
+
+    /// Declares several files.
+    pub fn files<I, S>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<StructureFile>,
+    {
+        self.item
+            .files
+            .get_or_insert_with(Vec::new)
+            .extend(files.into_iter().map(Into::into));
+        self
+    }
+
+    /// Declares a directory created with [`StructureItem::dir`].
+    pub fn dir(mut self, dir: StructureItemBuilder) -> Self {
+        self.item
+            .dirs
+            .get_or_insert_with(Default::default)
+            .insert(dir.name, dir.item);
+        self
+    }
+
+    /// References a reusable structure of `StructureConfig::definitions`.
+    pub fn reference(mut self, name: impl Into<String>) -> Self {
+        self.item.reference = Some(name.into());
+        self
+    }
+
+    /// Sets the conditions under which the item is verified.
+    pub fn when(mut self, condition: StructureCondition) -> Self {
+        self.item.when = Some(condition);
+        self
+    }
+
+    /// Sets the `repair` option.
+    pub fn repair(mut self, repair: bool) -> Self {
+        self.options().repair = Some(repair);
+        self
+    }
+
+    /// Sets the `strict` option.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options().strict = Some(strict);
+        self
+    }
+
+    /// Sets the `acl` option.
+    pub fn acl(mut self, acl: AclTemplate) -> Self {
+        self.options().acl = Some(acl);
+        self
+    }
+
+    /// Sets the `tuning` option.
+    pub fn tuning(mut self, tuning: IoTuning) -> Self {
+        self.options().tuning = Some(tuning);
+        self
+    }
+
+    /// Sets the `maxChildren` option.
+    pub fn max_children(mut self, max_children: usize) -> Self {
+        self.options().max_children = Some(max_children);
+        self
+    }
+
+    /// Sets the `pruneEmptyDirs` option.
+    pub fn prune_empty_dirs(mut self, prune_empty_dirs: bool) -> Self {
+        self.options().prune_empty_dirs = Some(prune_empty_dirs);
+        self
+    }
+
+    /// Sets the `retention` option, deleting undeclared entries older than `max_age_days`.
+    pub fn retention(mut self, max_age_days: u64) -> Self {
+        self.options().retention = Some(Retention {
+            max_age_days,
+            archive_to: None,
+        });
+        self
+    }
+
+    /// Sets the `retention` option, moving undeclared entries older than `max_age_days` into the `archive_to`
+    /// subdirectory.
+    pub fn retention_archived(mut self, max_age_days: u64, archive_to: impl Into<String>) -> Self {
+        self.options().retention = Some(Retention {
+            max_age_days,
+            archive_to: Some(archive_to.into()),
+        });
+        self
+    }
+
+    /// Sets the `maxEntries` option.
+    pub fn max_entries(mut self, max_entries: u64) -> Self {
+        self.options().max_entries = Some(max_entries);
+        self
+    }
+
+    /// Sets the `maxTotalSize` option, in bytes.
+    pub fn max_total_size(mut self, max_total_size: u64) -> Self {
+        self.options().max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// Sets the `quotaPolicy` option.
+    pub fn quota_policy(mut self, quota_policy: QuotaPolicy) -> Self {
+        self.options().quota_policy = Some(quota_policy);
+        self
+    }
+
+    /// Sets the `exclusive` option.
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.options().exclusive = Some(exclusive);
+        self
+    }
+
+    /// Adds names to the `sharedEntries` option.
+    pub fn shared_entries<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options()
+            .shared_entries
+            .get_or_insert_with(Vec::new)
+            .extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the `maxDepth` option.
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.options().max_depth = Some(max_depth);
+        self
+    }
+
+    /// Adds glob patterns to the `ignore` option.
+    pub fn ignore<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.options()
+            .ignore
+            .get_or_insert_with(Vec::new)
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the `optional` option.
+    pub fn optional(mut self, optional: bool) -> Self {
+        self.options().optional = Some(optional);
+        self
+    }
+
+    /// Sets the `severity` option.
+    pub fn severity(mut self, severity: Severity) -> Self {
+        self.options().severity = Some(severity);
+        self
+    }
+
+    /// Sets the `aggregate` option.
+    pub fn aggregate(mut self, aggregate: bool) -> Self {
+        self.options().aggregate = Some(aggregate);
+        self
+    }
+
+    /// Sets the `createRoot` option.
+    pub fn create_root(mut self, create_root: bool) -> Self {
+        self.options().create_root = Some(create_root);
+        self
+    }
+
+    /// Sets the `verifyOnStartup` option, e.g. `true` or a [`VerifyOnStartupOptions`].
+    pub fn verify_on_startup(mut self, verify_on_startup: impl Into<VerifyOnStartup>) -> Self {
+        self.options().verify_on_startup = Some(verify_on_startup.into());
+        self
+    }
+
+    /// Sets the `timeout` option, in seconds.
+    pub fn timeout(mut self, seconds: u64) -> Self {
+        self.options().timeout = Some(seconds);
+        self
+    }
+
+    /// Sets the `securityAudit` option.
+    pub fn security_audit(mut self, security_audit: bool) -> Self {
+        self.options().security_audit = Some(security_audit);
+        self
+    }
+
+    /// Sets the `protected` option.
+    pub fn protected(mut self, protected: bool) -> Self {
+        self.options().protected = Some(protected);
+        self
+    }
+
+    /// Sets the `attributes` option.
+    pub fn attributes(mut self, attributes: EntryAttributes) -> Self {
+        self.options().attributes = Some(attributes);
+        self
+    }
+
+    /// Sets the `owner` option, a user name or a numeric id.
+    pub fn owner(mut self, owner: impl Into<String>) -> Self {
+        self.options().owner = Some(owner.into());
+        self
+    }
+
+    /// Sets the `group` option, a group name or a numeric id.
+    pub fn group(mut self, group: impl Into<String>) -> Self {
+        self.options().group = Some(group.into());
+        self
+    }
+
+    /// Sets the `reparsePoint` option.
+    pub fn reparse_point(mut self, policy: ReparsePointPolicy) -> Self {
+        self.options().reparse_point = Some(policy);
+        self
+    }
+
+    /// Sets the `seedFrom` option.
+    pub fn seed_from(mut self, source: SeedSource) -> Self {
+        self.options().seed_from = Some(source);
+        self
+    }
+
+    /// Sets the `archive` option, a zip archive of the bundled resources.
+    pub fn archive(mut self, archive: impl Into<String>) -> Self {
+        self.options().archive = Some(archive.into());
+        self
+    }
+
+    /// Adds a previous name of the directory to the `movedFrom` option, renamed by the repair when the directory is
+    /// missing.
+    pub fn moved_from(mut self, name: impl Into<String>) -> Self {
+        self.options()
+            .moved_from
+            .get_or_insert_with(Vec::new)
+            .push(name.into());
+        self
+    }
+
+    /// Sets the `message` option, an explanation of the violations of the directory written for the user.
+    pub fn message(mut self, message: impl Into<String>) -> Self {
+        self.options().message = Some(message.into());
+        self
+    }
+
+    /// Sets the `helpUrl` option.
+    pub fn help_url(mut self, help_url: impl Into<String>) -> Self {
+        self.options().help_url = Some(help_url.into());
+        self
+    }
+
+    /// Builds the item.
+    pub fn build(self) -> StructureItem {
+        self.item
+    }
+
+    fn options(&mut self) -> &mut StructureItemOptions {
+        self.item.options.get_or_insert_with(Default::default)
+    }
+}
+
+impl From<StructureItemBuilder> for StructureItem {
+    fn from(builder: StructureItemBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Fluent builder of a [`StructureConfig`].
+///
+/// ```
+/// use tauri::path::BaseDirectory;
+/// use tauri_plugin_structure_manager::{StructureConfig, StructureItem};
+///
+/// let config = StructureConfig::builder()
+///     .root(
+///         BaseDirectory::AppData,
+///         StructureItem::builder().dir(StructureItem::dir("db").repair(true)),
+///     )
+///     .build();
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct StructureConfigBuilder {
+    config: StructureConfig,
+}
+
+impl StructureConfig {
+    /// Creates a builder for a structure configuration.
+    pub fn builder() -> StructureConfigBuilder {
+        StructureConfigBuilder::default()
+    }
+}
+
+impl StructureConfigBuilder {
+    /// Sets the structure of a base directory.
+    pub fn root(mut self, directory: BaseDirectory, item: impl Into<StructureItem>) -> Self {
+        if let Some(root) = self.config.root_mut(directory) {
+            *root = Some(item.into());
+        }
+        self
+    }
+
+    /// Declares a reusable structure, referenced with `$ref`.
+    pub fn definition(mut self, name: impl Into<String>, item: impl Into<StructureItem>) -> Self {
+        self.config
+            .definitions
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), item.into());
+        self
+    }
+
+    /// Declares a contract describing the layout of an external tool.
+    pub fn contract(mut self, name: impl Into<String>, contract: StructureContract) -> Self {
+        self.config
+            .contracts
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), contract);
+        self
+    }
+
+    /// Declares a named structure, verified against any directory with `verify_named`.
+    pub fn structure(mut self, name: impl Into<String>, item: impl Into<StructureItem>) -> Self {
+        self.config
+            .structures
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), item.into());
+        self
+    }
+
+    /// Declares a structure rooted at an absolute path.
+    pub fn custom(
+        mut self,
+        name: impl Into<String>,
+        path: impl Into<PathBuf>,
+        item: impl Into<StructureItem>,
+    ) -> Self {
+        self.config
+            .custom
+            .get_or_insert_with(Default::default)
+            .insert(
+                name.into(),
+                CustomRoot {
+                    path: path.into(),
+                    structure: item.into(),
+                },
+            );
+        self
+    }
+
+    /// Declares the structure of the shared container of an iOS app group.
+    pub fn app_group(mut self, group: impl Into<String>, item: impl Into<StructureItem>) -> Self {
+        self.config
+            .app_groups
+            .get_or_insert_with(Default::default)
+            .insert(group.into(), item.into());
+        self
+    }
+
+    /// Declares a named profile.
+    pub fn profile(mut self, name: impl Into<String>, profile: StructureConfig) -> Self {
+        self.config
+            .profiles
+            .get_or_insert_with(Default::default)
+            .insert(name.into(), profile);
+        self
+    }
+
+    /// Sets the version of the structure.
+    pub fn version(mut self, version: u32) -> Self {
+        self.config.version = Some(version);
+        self
+    }
+
+    /// Declares a migration upgrading the layout on disk of a previous version of the structure.
+    pub fn migration(mut self, migration: Migration) -> Self {
+        self.config
+            .migrations
+            .get_or_insert_with(Vec::new)
+            .push(migration);
+        self
+    }
+
+    /// Adds glob patterns of entry names ignored in every directory.
+    pub fn ignore<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config
+            .ignore
+            .get_or_insert_with(Vec::new)
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the `skipUnresolvedRoots` option.
+    pub fn skip_unresolved_roots(mut self, skip_unresolved_roots: bool) -> Self {
+        self.config.skip_unresolved_roots = Some(skip_unresolved_roots);
+        self
+    }
+
+    /// Sets the `failOnStartup` option.
+    pub fn fail_on_startup(mut self, fail_on_startup: bool) -> Self {
+        self.config.fail_on_startup = Some(fail_on_startup);
+        self
+    }
+
+    /// Sets the top-level `verifyOnStartup` option, e.g. `true` or a [`VerifyOnStartupOptions`].
+    pub fn verify_on_startup(mut self, verify_on_startup: impl Into<VerifyOnStartup>) -> Self {
+        self.config.verify_on_startup = Some(verify_on_startup.into());
+        self
+    }
+
+    /// Builds the configuration.
+    pub fn build(self) -> StructureConfig {
+        self.config
+    }
+}
+
+impl From<StructureConfigBuilder> for StructureConfig {
+    fn from(builder: StructureConfigBuilder) -> Self {
+        builder.build()
+    }
+}
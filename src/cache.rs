@@ -0,0 +1,139 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use tauri::{Manager, Runtime};
+
+use crate::{
+    models::*,
+    roots::{self, RootSelector},
+};
+
+/// A cached verification result, see [`ResultCache`].
+struct CachedResult {
+    verified_at: Instant,
+    result: std::result::Result<(), String>,
+}
+
+#[derive(Default)]
+struct Entries {
+    results: HashMap<(String, String), CachedResult>,
+    /// The roots verified or invalidated in this session, whose persisted result is stale.
+    settled: HashSet<String>,
+    /// Whether every root was invalidated in this session.
+    all_settled: bool,
+}
+
+/// Caches the results of `StructureManagerExt::verify` by root and configuration hash for a time, so repeated
+/// verifications, e.g. from several windows, don't walk the same structure again. Disabled by default, see
+/// `Builder::result_cache`.
+#[derive(Default)]
+pub(crate) struct ResultCache {
+    ttl: Option<Duration>,
+    entries: Mutex<Entries>,
+}
+
+impl ResultCache {
+    /// Creates a cache keeping results for `ttl`.
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl: Some(ttl),
+            entries: Mutex::default(),
+        }
+    }
+
+    /// Returns the hash of the structure of a configured root, the key of its results along with its name, or
+    /// `None` if the cache is disabled or the root isn't configured.
+    pub(crate) fn config_hash<R: Runtime, M: Manager<R>>(
+        &self,
+        manager: &M,
+        root: &RootSelector,
+    ) -> Option<String> {
+        self.ttl?;
+
+        let state_mutex = manager.state::<Mutex<StructureConfig>>();
+        let structure_config = state_mutex.lock().unwrap();
+        roots::select(
+            &structure_config,
+            root,
+            |directory| roots::resolve(manager, directory),
+            |group| roots::resolve_app_group(manager, group),
+        )
+        .ok()
+        .map(|(_, structure_item)| crate::hash::config_hash(structure_item))
+    }
+
+    /// Returns the result of the last verification of a root with this configuration hash, if it is recent enough.
+    ///
+    /// With the `store` feature, the last verification persisted by a previous session is reused too.
+    pub(crate) fn get<R: Runtime, M: Manager<R>>(
+        &self,
+        manager: &M,
+        root: &str,
+        config_hash: &str,
+    ) -> Option<std::result::Result<(), String>> {
+        let ttl = self.ttl?;
+        let key = (root.to_string(), config_hash.to_string());
+        let entries = self.entries.lock().unwrap();
+        if let Some(cached) = entries.results.get(&key) {
+            return (cached.verified_at.elapsed() < ttl).then(|| cached.result.clone());
+        }
+        let settled = entries.all_settled || entries.settled.contains(root);
+        drop(entries);
+
+        #[cfg(feature = "store")]
+        if let (false, Ok(Some(last))) = (settled, crate::store::last(manager, root)) {
+            let now = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64);
+            let age = Duration::from_millis(now.saturating_sub(last.verified_at));
+            if last.config_hash == config_hash && age < ttl {
+                return Some(match last.error {
+                    Some(error) => Err(error),
+                    None => Ok(()),
+                });
+            }
+        }
+        #[cfg(not(feature = "store"))]
+        let _ = (manager, settled);
+
+        None
+    }
+
+    /// Caches the result of a verification.
+    pub(crate) fn insert(
+        &self,
+        root: &str,
+        config_hash: String,
+        result: &std::result::Result<(), String>,
+    ) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.settled.insert(root.to_string());
+        entries.results.insert(
+            (root.to_string(), config_hash),
+            CachedResult {
+                verified_at: Instant::now(),
+                result: result.clone(),
+            },
+        );
+    }
+
+    /// Forgets the cached results of a root, or of every root if `None`.
+    pub(crate) fn invalidate(&self, root: Option<&str>) {
+        let mut entries = self.entries.lock().unwrap();
+        match root {
+            Some(root) => {
+                entries
+                    .results
+                    .retain(|(cached_root, _), _| cached_root != root);
+                entries.settled.insert(root.to_string());
+            }
+            None => {
+                entries.results.clear();
+                entries.all_settled = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+
+use tauri::path::BaseDirectory;
+
+use crate::{
+    ignore::is_marker, long_path::extended, models::*, normalization::same_name,
+    repair_scope::RepairScope,
+};
+
+/// Returns the configured cache roots, cleaned by `StructureManagerExt::clean_cache`.
+pub(crate) fn cache_roots(structure_config: &StructureConfig) -> Vec<BaseDirectory> {
+    [BaseDirectory::AppCache, BaseDirectory::Cache]
+        .into_iter()
+        .filter(|directory| structure_config.root(*directory).is_some())
+        .collect()
+}
+
+/// Empties the declared directories of a structure while keeping the directories themselves,
+/// see `StructureManagerExt::clean`.
+pub(crate) struct Cleaner<'a> {
+    repair_scope: &'a RepairScope,
+    root: PathBuf,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: Vec<PathBuf>,
+    dry_run: bool,
+    deleted: Vec<PathBuf>,
+    freed: u64,
+}
+
+impl<'a> Cleaner<'a> {
+    /// Creates a cleaner for the root item of a structure, see `Walker::new`.
+    pub(crate) fn new(repair_scope: &'a RepairScope, path: &Path, root: &StructureItem) -> Self {
+        Self {
+            repair_scope,
+            root: path.to_path_buf(),
+            normalization: root.unicode_normalization(),
+            nested_roots: Vec::new(),
+            dry_run: false,
+            deleted: Vec::new(),
+            freed: 0,
+        }
+    }
+
+    /// Sets the paths of the other configured roots nested inside the cleaned root, see `Walker::with_nested_roots`.
+    pub(crate) fn with_nested_roots(mut self, nested_roots: Vec<PathBuf>) -> Self {
+        self.nested_roots = nested_roots;
+        self
+    }
+
+    /// Sets whether the entries are only listed, without deleting anything.
+    pub(crate) fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Cleans `root`, at `path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `owned` - Whether the root belongs to the app, its undeclared entries are deleted too. Those of a shared
+    ///   root (e.g. `temp`) belong to other software and are left alone.
+    ///
+    /// # Returns
+    ///
+    /// Returns the deleted entries (to be deleted with `dry_run`), or `Err(String)` if an entry can't be deleted.
+    pub(crate) fn clean(
+        mut self,
+        root: &str,
+        path: &Path,
+        structure_item: &StructureItem,
+        owned: bool,
+    ) -> std::result::Result<CleanSummary, String> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("clean", root).entered();
+
+        self.clean_dir(path, structure_item, owned)?;
+        if !self.dry_run {
+            log::info!(
+                "Cleaned {}: {} entries deleted, {} bytes freed",
+                root,
+                self.deleted.len(),
+                self.freed
+            );
+        }
+
+        Ok(CleanSummary {
+            root: root.to_string(),
+            dry_run: self.dry_run,
+            deleted: self.deleted,
+            freed: self.freed,
+        })
+    }
+
+    fn clean_dir(
+        &mut self,
+        path: &Path,
+        structure_item: &StructureItem,
+        owned: bool,
+    ) -> std::result::Result<(), String> {
+        if !extended(path).is_dir() {
+            return Ok(());
+        }
+
+        let entries = std::fs::read_dir(extended(path))
+            .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+            .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        for entry in entries {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if is_marker(&name) {
+                continue;
+            }
+            let entry_path = path.join(entry.file_name());
+            if self
+                .nested_roots
+                .iter()
+                .any(|root| root.starts_with(&entry_path))
+            {
+                continue;
+            }
+
+            let is_dir = entry.file_type().is_ok_and(|file_type| file_type.is_dir());
+            let declared_dir = structure_item
+                .dirs
+                .iter()
+                .flatten()
+                .find(|(declared, _)| same_name(declared, &name, self.normalization))
+                .map(|(_, dir)| dir);
+            match declared_dir {
+                // The declared skeleton is kept, below it everything belongs to the app.
+                Some(dir) if is_dir => self.clean_dir(&entry_path, dir, true)?,
+                _ if owned || structure_item.declares(&name, self.normalization) => {
+                    self.delete(&entry_path, is_dir)?
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn delete(&mut self, path: &Path, is_dir: bool) -> std::result::Result<(), String> {
+        self.repair_scope.check(Some(&self.root), path)?;
+
+        let size = size_of(path);
+        if !self.dry_run {
+            let result = if is_dir {
+                std::fs::remove_dir_all(extended(path))
+            } else {
+                std::fs::remove_file(extended(path))
+            };
+            result.map_err(|e| format!("Failed to delete: {:?}, error: {:?}", path, e))?;
+        }
+
+        self.freed += size;
+        self.deleted.push(path.to_path_buf());
+        Ok(())
+    }
+}
+
+/// Returns the size of the files of an entry, recursively. Symbolic links aren't followed.
+fn size_of(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(extended(path)) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+
+    std::fs::read_dir(extended(path))
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| size_of(&entry.path()))
+                .sum()
+        })
+        .unwrap_or(0)
+}
@@ -0,0 +1,44 @@
+use crate::{models::ViolationKind, Error};
+
+impl ViolationKind {
+    /// Returns the stable code of the kind, e.g. `SM0101` for a missing file, for frontends and support tooling to
+    /// branch on. Codes of violations start at `SM0101`, codes of errors at `SM0001`.
+    pub fn code(self) -> &'static str {
+        match self {
+            ViolationKind::MissingFile => "SM0101",
+            ViolationKind::MissingDirectory => "SM0102",
+            ViolationKind::UnexpectedEntry => "SM0103",
+            ViolationKind::QuotaExceeded => "SM0104",
+            ViolationKind::ValidationFailed => "SM0105",
+            ViolationKind::SchemaViolation => "SM0106",
+            ViolationKind::InvalidFormat => "SM0107",
+            ViolationKind::RepairFailed => "SM0108",
+            ViolationKind::ForeignEntry => "SM0109",
+            ViolationKind::AttributeMismatch => "SM0110",
+            ViolationKind::OwnerMismatch => "SM0111",
+            ViolationKind::SecurityIssue => "SM0112",
+            ViolationKind::ReparsePoint => "SM0113",
+            ViolationKind::DepthLimitExceeded => "SM0114",
+            ViolationKind::VersionMismatch => "SM0115",
+            ViolationKind::TimedOut => "SM0116",
+            ViolationKind::AclMismatch => "SM0117",
+        }
+    }
+}
+
+impl Error {
+    /// Returns the stable code of the error, e.g. `SM0004` for a path outside of the scope, serialized next to its
+    /// message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "SM0001",
+            Error::Tauri(_) => "SM0002",
+            Error::Verification(_) => "SM0003",
+            Error::PathNotAllowed(_) => "SM0004",
+            Error::RootNotAllowed(_) => "SM0005",
+            Error::InsufficientSpace { .. } => "SM0006",
+            #[cfg(mobile)]
+            Error::PluginInvoke(_) => "SM0007",
+        }
+    }
+}
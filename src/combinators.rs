@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use crate::models::*;
+
+/// Combinators composing structures from reusable fragments.
+///
+/// Conflicts are always resolved the same way: when both items declare the same file, directory option or
+/// condition, the declaration of `other` wins. Options are merged field by field, so an option only set on one
+/// side is kept.
+impl StructureItem {
+    /// Returns an item declaring everything declared by this item or `other`.
+    ///
+    /// Directories declared by both items are merged recursively.
+    pub fn merge(&self, other: &StructureItem) -> StructureItem {
+        self.clone().overlay(other)
+    }
+
+    /// Returns this item without the declarations of `other`.
+    ///
+    /// A directory of `other` declaring no files nor directories removes the whole directory, otherwise only its
+    /// declarations are subtracted from the directory. The options and conditions of this item are kept.
+    pub fn subtract(&self, other: &StructureItem) -> StructureItem {
+        let mut item = self.clone();
+
+        if let (Some(files), Some(removed)) = (item.files.as_mut(), &other.files) {
+            files.retain(|file| !removed.iter().any(|removed| removed.name == file.name));
+        }
+
+        if let (Some(dirs), Some(removed)) = (item.dirs.as_mut(), &other.dirs) {
+            for (name, removed) in removed {
+                if removed.files.is_none() && removed.dirs.is_none() {
+                    dirs.remove(name);
+                } else if let Some(dir) = dirs.get_mut(name) {
+                    *dir = dir.subtract(removed);
+                }
+            }
+        }
+
+        item
+    }
+
+    /// Returns an item declaring only what is declared by both this item and `other`.
+    ///
+    /// Directories declared by both items are intersected recursively, options and conditions are merged as by
+    /// [`StructureItem::merge`].
+    pub fn intersect(&self, other: &StructureItem) -> StructureItem {
+        let mut item = StructureItem {
+            files: None,
+            dirs: None,
+            ..self.clone()
+        }
+        .overlay(&StructureItem {
+            files: None,
+            dirs: None,
+            ..other.clone()
+        });
+
+        if let (Some(files), Some(others)) = (&self.files, &other.files) {
+            item.files = Some(
+                others
+                    .iter()
+                    .filter(|other| files.iter().any(|file| file.name == other.name))
+                    .cloned()
+                    .collect(),
+            );
+        }
+
+        if let (Some(dirs), Some(others)) = (&self.dirs, &other.dirs) {
+            item.dirs = Some(
+                dirs.iter()
+                    .filter_map(|(name, dir)| {
+                        let other = others.get(name)?;
+                        Some((name.clone(), dir.intersect(other)))
+                    })
+                    .collect::<BTreeMap<_, _>>(),
+            );
+        }
+
+        item
+    }
+}
@@ -1,8 +1,18 @@
-use tauri::{command, AppHandle, Runtime};
+use std::{collections::BTreeMap, path::PathBuf, sync::Mutex};
 
+use tauri::{
+    command,
+    ipc::{Channel, CommandScope, GlobalScope},
+    path::BaseDirectory,
+    AppHandle, Manager, Runtime,
+};
+
+use crate::clean;
 use crate::models::*;
-use crate::Result;
+use crate::roots;
+use crate::scope::{self, ScopeEntry};
 use crate::StructureManagerExt;
+use crate::{Error, Result};
 
 #[command]
 #[allow(dead_code)]
@@ -12,3 +22,425 @@ pub(crate) async fn ping<R: Runtime>(
 ) -> Result<PingResponse> {
     app.structure_manager().ping(payload)
 }
+
+#[command]
+pub(crate) async fn assert_external_contract<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    path: PathBuf,
+    contract: String,
+) -> Result<()> {
+    if !scope::is_allowed(&app, &command_scope, &global_scope, &path)? {
+        return Err(Error::PathNotAllowed(path));
+    }
+    app.assert_external_contract(path, &contract)
+        .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn detect_contract<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    path: PathBuf,
+) -> Result<Vec<(String, f64)>> {
+    if !scope::is_allowed(&app, &command_scope, &global_scope, &path)? {
+        return Err(Error::PathNotAllowed(path));
+    }
+    app.detect_contract(path).map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn verify_path<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    path: PathBuf,
+    structure: StructureItem,
+    dry_run: Option<bool>,
+) -> Result<Option<RepairPlan>> {
+    if !scope::is_allowed(&app, &command_scope, &global_scope, &path)? {
+        return Err(Error::PathNotAllowed(path));
+    }
+    structure.check_entry_names().map_err(Error::Verification)?;
+
+    if dry_run.unwrap_or(false) {
+        return app
+            .plan_path(path, &structure)
+            .map(Some)
+            .map_err(Error::Verification);
+    }
+    app.verify_path(path, &structure)
+        .map(|_| None)
+        .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn verify_named<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    name: String,
+    path: PathBuf,
+) -> Result<()> {
+    if !scope::is_allowed(&app, &command_scope, &global_scope, &path)? {
+        return Err(Error::PathNotAllowed(path));
+    }
+
+    app.verify_named(&name, path).map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn add_workspace<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    name: String,
+    path: PathBuf,
+    structure: String,
+) -> Result<()> {
+    if !scope::is_allowed(&app, &command_scope, &global_scope, &path)? {
+        return Err(Error::PathNotAllowed(path));
+    }
+
+    app.add_workspace(name, path, structure)
+        .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn remove_workspace<R: Runtime>(app: AppHandle<R>, name: String) -> Result<bool> {
+    app.remove_workspace(&name).map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn workspaces<R: Runtime>(app: AppHandle<R>) -> Result<Vec<WorkspaceRoot>> {
+    Ok(app.workspaces())
+}
+
+#[cfg(feature = "archive")]
+#[command]
+pub(crate) async fn verify_archive<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    path: PathBuf,
+    structure: StructureItem,
+) -> Result<()> {
+    if !scope::is_allowed(&app, &command_scope, &global_scope, &path)? {
+        return Err(Error::PathNotAllowed(path));
+    }
+    structure.check_entry_names().map_err(Error::Verification)?;
+
+    app.verify_archive(path, structure)
+        .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn verify<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+    dry_run: Option<bool>,
+) -> Result<Option<RepairPlan>> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+    if dry_run.unwrap_or(false) {
+        return app.repair_plan(root).map(Some).map_err(Error::Verification);
+    }
+    app.verify(root).map(|_| None).map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn verify_streamed<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+    on_event: Channel<VerificationEvent>,
+    dry_run: Option<bool>,
+) -> Result<Option<RepairPlan>> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+    if dry_run.unwrap_or(false) {
+        return app.repair_plan(root).map(Some).map_err(Error::Verification);
+    }
+    app.verify_streamed(root, &|event| {
+        if let Err(e) = on_event.send(event) {
+            log::warn!("Failed to send verification event: {}", e);
+        }
+    })
+    .map(|_| None)
+    .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn repair_plan<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+) -> Result<RepairPlan> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+    app.repair_plan(root).map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn apply_repair<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    plan: RepairPlan,
+    dry_run: Option<bool>,
+) -> Result<ReconciliationReport> {
+    for operation in &plan.operations {
+        let path = operation.path();
+        if !scope::is_allowed(&app, &command_scope, &global_scope, path)? {
+            return Err(Error::PathNotAllowed(path.to_path_buf()));
+        }
+    }
+
+    Ok(app.apply_repair(&plan, dry_run.unwrap_or(false)))
+}
+
+#[command]
+pub(crate) async fn write_report<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+    path: PathBuf,
+    format: Option<ReportFormat>,
+) -> Result<VerificationReport> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+    if !scope::is_allowed(&app, &command_scope, &global_scope, &path)? {
+        return Err(Error::PathNotAllowed(path));
+    }
+
+    app.write_report(root, path, format.unwrap_or_default())
+        .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn last_verification_stats<R: Runtime>(
+    app: AppHandle<R>,
+) -> Result<BTreeMap<String, VerificationStats>> {
+    Ok(app.last_verification_stats())
+}
+
+#[command]
+pub(crate) async fn wait_until_ready<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+) -> Result<StartupVerification> {
+    // The verification on startup may take a while, it is waited for off the async runtime.
+    let mut outcome = tauri::async_runtime::spawn_blocking(move || app.wait_until_ready())
+        .await
+        .map_err(|e| Error::Verification(e.to_string()))?;
+    // Only the roots allowed to the window are returned.
+    outcome
+        .verified
+        .retain(|root| scope::is_root_allowed(&command_scope, &global_scope, root));
+    outcome
+        .failed
+        .retain(|root, _| scope::is_root_allowed(&command_scope, &global_scope, root));
+    Ok(outcome)
+}
+
+#[command]
+pub(crate) async fn history<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<VerificationRecord>> {
+    // Only the verifications of the roots allowed to the window are returned.
+    let mut records = app
+        .history(root.as_deref(), usize::MAX)
+        .map_err(Error::Verification)?;
+    records.retain(|record| scope::is_root_allowed(&command_scope, &global_scope, &record.root));
+    records.truncate(limit.unwrap_or(100));
+    Ok(records)
+}
+
+#[command]
+pub(crate) async fn clean_cache<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    dry_run: Option<bool>,
+) -> Result<Vec<CleanSummary>> {
+    let directories = {
+        let state_mutex = app.state::<Mutex<StructureConfig>>();
+        let structure_config = state_mutex.lock().unwrap();
+        clean::cache_roots(&structure_config)
+    };
+    for directory in directories {
+        if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(directory)) {
+            return Err(Error::RootNotAllowed(
+                roots::field_name(directory).to_string(),
+            ));
+        }
+    }
+
+    app.clean_cache(dry_run.unwrap_or(false))
+        .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn clean_temp<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    dry_run: Option<bool>,
+) -> Result<Option<CleanSummary>> {
+    let root = roots::field_name(BaseDirectory::Temp);
+    if !scope::is_root_allowed(&command_scope, &global_scope, root) {
+        return Err(Error::RootNotAllowed(root.to_string()));
+    }
+
+    app.clean_temp(dry_run.unwrap_or(false))
+        .map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn invalidate_verification_cache<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: Option<BaseDirectory>,
+) -> Result<()> {
+    if let Some(root) = root {
+        if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+            return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+        }
+        app.invalidate_verification_cache(Some(root.into()));
+        return Ok(());
+    }
+
+    // Without a root, only the results of the roots allowed to the window are forgotten.
+    for root in roots::ROOTS {
+        if scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+            app.invalidate_verification_cache(Some(root.into()));
+        }
+    }
+    Ok(())
+}
+
+#[command]
+pub(crate) async fn structure_version<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+) -> Result<Option<u32>> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+
+    app.structure_version(root).map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn generate_manifest<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+) -> Result<Manifest> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+
+    app.generate_manifest(root).map_err(Error::Verification)
+}
+
+#[command]
+pub(crate) async fn verify_manifest<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+) -> Result<ManifestDiff> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+
+    app.verify_manifest(root).map_err(Error::Verification)
+}
+
+#[cfg(feature = "store")]
+#[command]
+pub(crate) async fn last_verification<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+) -> Result<Option<LastVerification>> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+
+    app.last_verification(root).map_err(Error::Verification)
+}
+
+#[cfg(feature = "watch")]
+#[command]
+pub(crate) async fn watch<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+    on_event: Channel<VerificationEvent>,
+) -> Result<()> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+
+    app.watch(root, move |event| {
+        if let Err(e) = on_event.send(event) {
+            log::warn!("Failed to send verification event: {}", e);
+        }
+    })
+    .map_err(Error::Verification)
+}
+
+#[cfg(feature = "watch")]
+#[command]
+pub(crate) async fn unwatch<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+) -> Result<bool> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+
+    Ok(app.unwatch(root))
+}
+
+#[command]
+pub(crate) async fn tree_json<R: Runtime>(
+    app: AppHandle<R>,
+    command_scope: CommandScope<ScopeEntry>,
+    global_scope: GlobalScope<ScopeEntry>,
+    root: BaseDirectory,
+    path: Option<PathBuf>,
+    options: Option<TreeOptions>,
+) -> Result<Vec<TreeNode>> {
+    if !scope::is_root_allowed(&command_scope, &global_scope, roots::field_name(root)) {
+        return Err(Error::RootNotAllowed(roots::field_name(root).to_string()));
+    }
+    app.tree_json(root, path, &options.unwrap_or_default())
+        .map_err(Error::Verification)
+}
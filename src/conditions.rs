@@ -0,0 +1,107 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::RwLock,
+};
+
+use tauri::{Manager, Runtime};
+
+use crate::models::*;
+
+/// The feature flags supplied when building the plugin, used to evaluate `when` clauses.
+#[derive(Default, Debug)]
+pub(crate) struct Features(pub(crate) HashSet<String>);
+
+/// The named conditions set at runtime with `StructureManager::set_condition`, used to evaluate `when` clauses.
+#[derive(Default, Debug)]
+pub(crate) struct Conditions(pub(crate) RwLock<HashMap<String, bool>>);
+
+impl Conditions {
+    pub(crate) fn set(&self, name: String, value: bool) {
+        self.0.write().unwrap().insert(name, value);
+    }
+}
+
+impl StructureCondition {
+    /// Evaluates the condition against the running app.
+    ///
+    /// # Arguments
+    ///
+    /// * `version` - The version of the app.
+    /// * `features` - The feature flags supplied when building the plugin.
+    /// * `conditions` - The named conditions set at runtime, a condition that was never set is false.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if every declared condition matches, or `Err(String)` if the version requirement is invalid.
+    pub fn matches(
+        &self,
+        version: &semver::Version,
+        features: &HashSet<String>,
+        conditions: &HashMap<String, bool>,
+    ) -> std::result::Result<bool, String> {
+        if let Some(platforms) = &self.platform {
+            let os = std::env::consts::OS;
+            let family = if cfg!(any(target_os = "android", target_os = "ios")) {
+                "mobile"
+            } else {
+                "desktop"
+            };
+            if !platforms.iter().any(|p| p == os || p == family) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(requirement) = &self.version {
+            let requirement = semver::VersionReq::parse(requirement).map_err(|e| {
+                format!(
+                    "Invalid version requirement: {:?}, error: {:?}",
+                    requirement, e
+                )
+            })?;
+            if !requirement.matches(version) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(build) = self.build {
+            let current = if cfg!(debug_assertions) {
+                BuildProfile::Debug
+            } else {
+                BuildProfile::Release
+            };
+            if build != current {
+                return Ok(false);
+            }
+        }
+
+        if let Some(required) = &self.features {
+            if !required.iter().all(|f| features.contains(f)) {
+                return Ok(false);
+            }
+        }
+
+        if let Some(name) = &self.condition {
+            if conditions.get(name) != Some(&true) {
+                return Ok(false);
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+/// Returns whether the `when` clause of a structure item matches the running app.
+pub(crate) fn is_active<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    structure_item: &StructureItem,
+) -> std::result::Result<bool, String> {
+    match &structure_item.when {
+        Some(condition) => {
+            let features = manager.state::<Features>();
+            let conditions = manager.state::<Conditions>();
+            let conditions = conditions.0.read().unwrap();
+            condition.matches(&manager.package_info().version, &features.0, &conditions)
+        }
+        None => Ok(true),
+    }
+}
@@ -0,0 +1,480 @@
+use std::path::{Component, Path};
+
+use crate::{attributes::Permission, models::*, normalization::same_name};
+
+impl StructureConfig {
+    /// Returns the configured structure of every root, in declaration order.
+    pub(crate) fn roots(&self) -> [&Option<StructureItem>; 23] {
+        [
+            &self.app_cache,
+            &self.app_config,
+            &self.app_data,
+            &self.app_local_data,
+            &self.app_log,
+            &self.audio,
+            &self.cache,
+            &self.config,
+            &self.data,
+            &self.desktop,
+            &self.document,
+            &self.download,
+            &self.executable,
+            &self.font,
+            &self.home,
+            &self.local_data,
+            &self.picture,
+            &self.public,
+            &self.resource,
+            &self.runtime,
+            &self.temp,
+            &self.template,
+            &self.video,
+        ]
+    }
+
+    /// Returns the configured structure of every root mutably, in declaration order.
+    pub(crate) fn roots_mut(&mut self) -> [&mut Option<StructureItem>; 23] {
+        [
+            &mut self.app_cache,
+            &mut self.app_config,
+            &mut self.app_data,
+            &mut self.app_local_data,
+            &mut self.app_log,
+            &mut self.audio,
+            &mut self.cache,
+            &mut self.config,
+            &mut self.data,
+            &mut self.desktop,
+            &mut self.document,
+            &mut self.download,
+            &mut self.executable,
+            &mut self.font,
+            &mut self.home,
+            &mut self.local_data,
+            &mut self.picture,
+            &mut self.public,
+            &mut self.resource,
+            &mut self.runtime,
+            &mut self.temp,
+            &mut self.template,
+            &mut self.video,
+        ]
+    }
+
+    /// Applies a named profile from `profiles` over the configuration, in place.
+    ///
+    /// Each root declared by the profile is laid over the matching root: options set by the profile win,
+    /// files are combined and directories are laid over recursively. Definitions, contracts, named structures,
+    /// custom roots and app groups of the profile are added to (or replace) the ones of the configuration.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the profile was applied, or `Err(String)` if it is not declared.
+    pub fn apply_profile(&mut self, name: &str) -> std::result::Result<(), String> {
+        let profile = match self.profiles.as_ref().and_then(|p| p.get(name)) {
+            Some(profile) => profile.clone(),
+            None => return Err(format!("Structure profile `{}` not found", name)),
+        };
+
+        for (root, overlay) in self.roots_mut().into_iter().zip(profile.roots()) {
+            if let Some(overlay) = overlay {
+                *root = Some(match root.take() {
+                    Some(base) => base.overlay(overlay),
+                    None => overlay.clone(),
+                });
+            }
+        }
+
+        if let Some(definitions) = profile.definitions {
+            self.definitions
+                .get_or_insert_with(Default::default)
+                .extend(definitions);
+        }
+        if let Some(contracts) = profile.contracts {
+            self.contracts
+                .get_or_insert_with(Default::default)
+                .extend(contracts);
+        }
+        if let Some(structures) = profile.structures {
+            self.structures
+                .get_or_insert_with(Default::default)
+                .extend(structures);
+        }
+        if let Some(custom) = profile.custom {
+            self.custom
+                .get_or_insert_with(Default::default)
+                .extend(custom);
+        }
+        if let Some(app_groups) = profile.app_groups {
+            self.app_groups
+                .get_or_insert_with(Default::default)
+                .extend(app_groups);
+        }
+        if let Some(ignore) = profile.ignore {
+            self.ignore.get_or_insert_with(Vec::new).extend(ignore);
+        }
+        if let Some(create_root) = profile.create_root {
+            self.create_root = Some(create_root);
+        }
+        if let Some(skip_unresolved_roots) = profile.skip_unresolved_roots {
+            self.skip_unresolved_roots = Some(skip_unresolved_roots);
+        }
+        if let Some(fail_on_startup) = profile.fail_on_startup {
+            self.fail_on_startup = Some(fail_on_startup);
+        }
+        if let Some(verify_on_startup) = profile.verify_on_startup {
+            self.verify_on_startup = Some(verify_on_startup);
+        }
+
+        Ok(())
+    }
+
+    /// Verifies that every entry name declared by the configuration is a single path component,
+    /// see [`StructureItem::check_entry_names`].
+    pub(crate) fn check_entry_names(&self) -> std::result::Result<(), String> {
+        for root in self.roots().into_iter().flatten() {
+            root.check_entry_names()?;
+        }
+        for item in self.definitions.iter().flat_map(|d| d.values()) {
+            item.check_entry_names()?;
+        }
+        for contract in self.contracts.iter().flat_map(|c| c.values()) {
+            contract.structure.check_entry_names()?;
+        }
+        for item in self.structures.iter().flat_map(|s| s.values()) {
+            item.check_entry_names()?;
+        }
+        for root in self.custom.iter().flat_map(|c| c.values()) {
+            root.structure.check_entry_names()?;
+        }
+        for item in self.app_groups.iter().flat_map(|g| g.values()) {
+            item.check_entry_names()?;
+        }
+        for migration in self.migrations.iter().flatten() {
+            for action in &migration.actions {
+                action.check_paths()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the canonical form of the configuration, so that semantically identical configurations are identical.
+    ///
+    /// References are resolved, files are sorted by name and de-duplicated, default options are made explicit
+    /// and the `desktop` / `mobile` platform filters are expanded to the platforms they stand for.
+    /// Profiles are overlays, making their defaults explicit would change their meaning, so they are kept as declared.
+    ///
+    /// # Returns
+    ///
+    /// Returns the canonical configuration, or `Err(String)` if a reference is unknown or cyclic.
+    pub fn canonicalize(&self) -> std::result::Result<StructureConfig, String> {
+        let mut config = self.clone();
+        config.resolve_refs()?;
+
+        for root in config.roots_mut().into_iter().flatten() {
+            root.canonicalize();
+        }
+        for item in config.definitions.iter_mut().flat_map(|d| d.values_mut()) {
+            item.canonicalize();
+        }
+        for contract in config.contracts.iter_mut().flat_map(|c| c.values_mut()) {
+            contract.structure.canonicalize();
+        }
+        for item in config.structures.iter_mut().flat_map(|s| s.values_mut()) {
+            item.canonicalize();
+        }
+        for root in config.custom.iter_mut().flat_map(|c| c.values_mut()) {
+            root.structure.canonicalize();
+        }
+        for item in config.app_groups.iter_mut().flat_map(|g| g.values_mut()) {
+            item.canonicalize();
+        }
+        if let Some(ignore) = config.ignore.as_mut() {
+            ignore.sort();
+            ignore.dedup();
+        }
+
+        Ok(config)
+    }
+}
+
+impl StructureItem {
+    /// Verifies that every declared file and directory name (recursively) is a single path component,
+    /// so that the structure can't reach outside of the directory it is verified in.
+    pub(crate) fn check_entry_names(&self) -> std::result::Result<(), String> {
+        let names = self
+            .files
+            .iter()
+            .flatten()
+            .map(|file| &file.name)
+            .chain(self.dirs.iter().flat_map(|dirs| dirs.keys()))
+            .chain(
+                self.options
+                    .iter()
+                    .flat_map(|options| options.moved_from.iter().flatten()),
+            )
+            .chain(self.options.iter().flat_map(|options| {
+                options
+                    .retention
+                    .iter()
+                    .flat_map(|retention| &retention.archive_to)
+            }));
+        for name in names {
+            let mut components = Path::new(name).components();
+            match (components.next(), components.next()) {
+                (Some(Component::Normal(_)), None) => {}
+                _ => return Err(format!("Invalid structure entry name: {:?}", name)),
+            }
+        }
+
+        for dir in self.dirs.iter().flat_map(|dirs| dirs.values()) {
+            dir.check_entry_names()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the severity of the violations of the item.
+    pub(crate) fn severity(&self) -> Severity {
+        self.options
+            .as_ref()
+            .and_then(|options| options.severity)
+            .unwrap_or_default()
+    }
+
+    /// Returns the severity of the item missing: its `severity`, or a warning if it is `optional`.
+    pub(crate) fn missing_severity(&self) -> Severity {
+        let options = self.options.as_ref();
+        match options.and_then(|options| options.severity) {
+            Some(severity) => severity,
+            None if options.and_then(|options| options.optional) == Some(true) => Severity::Warning,
+            None => Severity::Error,
+        }
+    }
+
+    /// Returns the `message` and `helpUrl` options of the item, carried by its violations.
+    pub(crate) fn explanation(&self) -> (Option<&str>, Option<&str>) {
+        let options = self.options.as_ref();
+        (
+            options.and_then(|options| options.message.as_deref()),
+            options.and_then(|options| options.help_url.as_deref()),
+        )
+    }
+
+    /// Returns where the repair of the item copies missing entries from, if anywhere.
+    pub(crate) fn seed_from(&self) -> Option<SeedSource> {
+        self.options.as_ref().and_then(|options| options.seed_from)
+    }
+
+    /// Returns what to do when the directory of the item is a junction or another reparse point.
+    pub(crate) fn reparse_point_policy(&self) -> ReparsePointPolicy {
+        self.options
+            .as_ref()
+            .and_then(|options| options.reparse_point)
+            .unwrap_or_default()
+    }
+
+    /// Returns whether `name` is declared as a file or a directory of the item, ignoring the normalization of the
+    /// names when a form is set.
+    pub(crate) fn declares(&self, name: &str, form: Option<UnicodeNormalization>) -> bool {
+        let declared = self
+            .files
+            .as_ref()
+            .is_some_and(|files| files.iter().any(|file| file.name == name))
+            || self
+                .dirs
+                .as_ref()
+                .is_some_and(|dirs| dirs.contains_key(name));
+        if declared || form.is_none() || name.is_ascii() {
+            return declared;
+        }
+
+        self.files
+            .iter()
+            .flatten()
+            .map(|file| file.name.as_str())
+            .chain(
+                self.dirs
+                    .iter()
+                    .flat_map(|dirs| dirs.keys().map(String::as_str)),
+            )
+            .any(|declared| same_name(declared, name, form))
+    }
+
+    /// Returns the normalization form of the names of a verification, read from its root item.
+    pub(crate) fn unicode_normalization(&self) -> Option<UnicodeNormalization> {
+        self.options
+            .as_ref()
+            .and_then(|options| options.unicode_normalization)
+    }
+
+    /// Returns the number of levels of directories below the item that are verified, given the limit inherited from
+    /// its parents and its own `maxDepth` option, the smallest one wins.
+    pub(crate) fn depth_limit(&self, inherited: Option<usize>) -> Option<usize> {
+        let own = self.options.as_ref().and_then(|options| options.max_depth);
+        match (inherited, own) {
+            (Some(inherited), Some(own)) => Some(inherited.min(own)),
+            (inherited, own) => inherited.or(own),
+        }
+    }
+
+    /// Lays another structure item over this one, the other item's declarations take precedence.
+    pub(crate) fn overlay(mut self, other: &StructureItem) -> StructureItem {
+        if other.reference.is_some() {
+            self.reference = other.reference.clone();
+        }
+        if other.when.is_some() {
+            self.when = other.when.clone();
+        }
+
+        self.options = match (self.options, &other.options) {
+            (Some(base), Some(options)) => Some(base.overlay(options)),
+            (base, None) => base,
+            (None, options) => options.clone(),
+        };
+
+        if let Some(files) = &other.files {
+            let own = self.files.get_or_insert_with(Vec::new);
+            for file in files {
+                match own.iter_mut().find(|own| own.name == file.name) {
+                    Some(own) => *own = file.clone(),
+                    None => own.push(file.clone()),
+                }
+            }
+        }
+
+        if let Some(dirs) = &other.dirs {
+            let own = self.dirs.get_or_insert_with(Default::default);
+            for (name, dir) in dirs {
+                let dir = match own.remove(name) {
+                    Some(base) => base.overlay(dir),
+                    None => dir.clone(),
+                };
+                own.insert(name.clone(), dir);
+            }
+        }
+
+        self
+    }
+
+    /// Puts the item in canonical form, in place. See [`StructureConfig::canonicalize`].
+    fn canonicalize(&mut self) {
+        let options = self.options.get_or_insert_with(Default::default);
+        options.repair.get_or_insert(false);
+        options.strict.get_or_insert(false);
+        options.quota_policy.get_or_insert(QuotaPolicy::default());
+        options.exclusive.get_or_insert(false);
+        options.optional.get_or_insert(false);
+        options.severity.get_or_insert(Severity::default());
+        options.aggregate.get_or_insert(false);
+        options
+            .reparse_point
+            .get_or_insert(ReparsePointPolicy::default());
+        if let Some(shared_entries) = options.shared_entries.as_mut() {
+            shared_entries.sort();
+            shared_entries.dedup();
+        }
+        if let Some(ignore) = options.ignore.as_mut() {
+            ignore.sort();
+            ignore.dedup();
+        }
+
+        if let Some(platforms) = self.when.as_mut().and_then(|when| when.platform.as_mut()) {
+            let mut expanded: Vec<String> = platforms
+                .iter()
+                .flat_map(|platform| match platform.as_str() {
+                    "desktop" => vec!["linux", "macos", "windows"],
+                    "mobile" => vec!["android", "ios"],
+                    platform => vec![platform],
+                })
+                .map(String::from)
+                .collect();
+            expanded.sort();
+            expanded.dedup();
+            *platforms = expanded;
+        }
+
+        let files = self.files.get_or_insert_with(Vec::new);
+        files.sort_by(|a, b| a.name.cmp(&b.name));
+        files.dedup_by(|a, b| a.name == b.name);
+
+        for dir in self.dirs.get_or_insert_with(Default::default).values_mut() {
+            dir.canonicalize();
+        }
+    }
+}
+
+impl StructureItemOptions {
+    /// Lays other options over these ones, every option set in `other` wins.
+    pub(crate) fn overlay(self, other: &StructureItemOptions) -> StructureItemOptions {
+        StructureItemOptions {
+            repair: other.repair.or(self.repair),
+            create_root: other.create_root.or(self.create_root),
+            verify_on_startup: other.verify_on_startup.or(self.verify_on_startup),
+            timeout: other.timeout.or(self.timeout),
+            strict: other.strict.or(self.strict),
+            acl: other.acl.or(self.acl),
+            tuning: other.tuning.clone().or(self.tuning),
+            max_children: other.max_children.or(self.max_children),
+            max_total_size: other.max_total_size.or(self.max_total_size),
+            max_entries: other.max_entries.or(self.max_entries),
+            prune_empty_dirs: other.prune_empty_dirs.or(self.prune_empty_dirs),
+            retention: other.retention.clone().or(self.retention),
+            quota_policy: other.quota_policy.or(self.quota_policy),
+            exclusive: other.exclusive.or(self.exclusive),
+            shared_entries: other.shared_entries.clone().or(self.shared_entries),
+            max_depth: other.max_depth.or(self.max_depth),
+            ignore: other.ignore.clone().or(self.ignore),
+            optional: other.optional.or(self.optional),
+            severity: other.severity.or(self.severity),
+            aggregate: other.aggregate.or(self.aggregate),
+            security_audit: other.security_audit.or(self.security_audit),
+            protected: other.protected.or(self.protected),
+            attributes: other.attributes.or(self.attributes),
+            owner: other.owner.clone().or(self.owner),
+            group: other.group.clone().or(self.group),
+            reparse_point: other.reparse_point.or(self.reparse_point),
+            unicode_normalization: other.unicode_normalization.or(self.unicode_normalization),
+            seed_from: other.seed_from.or(self.seed_from),
+            archive: other.archive.clone().or(self.archive),
+            moved_from: other.moved_from.clone().or(self.moved_from),
+            message: other.message.clone().or(self.message),
+            help_url: other.help_url.clone().or(self.help_url),
+        }
+    }
+}
+
+impl StructureFile {
+    /// Returns the severity of the violations of the file.
+    pub(crate) fn severity(&self) -> Severity {
+        self.options.severity.unwrap_or_default()
+    }
+
+    /// Returns the permissions expected on the file, with whether it has them.
+    pub(crate) fn permissions(&self) -> impl Iterator<Item = (Permission, bool)> {
+        [
+            (Permission::Readonly, self.options.readonly),
+            (Permission::Executable, self.options.executable),
+        ]
+        .into_iter()
+        .filter_map(|(permission, expected)| expected.map(|expected| (permission, expected)))
+    }
+
+    /// Returns the severity of the file missing: its `severity`, or a warning if it is `optional`.
+    pub(crate) fn missing_severity(&self) -> Severity {
+        match self.options.severity {
+            Some(severity) => severity,
+            None if self.options.optional == Some(true) => Severity::Warning,
+            None => Severity::Error,
+        }
+    }
+
+    /// Returns the `message` and `helpUrl` options of the file, carried by its violations.
+    pub(crate) fn explanation(&self) -> (Option<&str>, Option<&str>) {
+        (
+            self.options.message.as_deref(),
+            self.options.help_url.as_deref(),
+        )
+    }
+}
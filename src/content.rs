@@ -0,0 +1,45 @@
+use std::{fs::OpenOptions, io::Write, path::Path};
+
+use base64::Engine;
+
+use crate::{long_path::extended, models::FileContent, tuning::ResolvedIoTuning};
+
+impl FileContent {
+    /// Returns the bytes of the content, decoding base64 content.
+    pub fn to_bytes(&self) -> std::result::Result<Vec<u8>, String> {
+        match self {
+            FileContent::Text(text) => Ok(text.as_bytes().to_vec()),
+            FileContent::Base64 { base64 } => base64::engine::general_purpose::STANDARD
+                .decode(base64)
+                .map_err(|e| format!("Invalid base64 file content: {}", e)),
+        }
+    }
+}
+
+/// Creates a file that doesn't exist with the given content, or empty without content. Creating it is retried with
+/// the `retries` of the IO tuning.
+pub(crate) fn create_file(
+    path: &Path,
+    content: Option<&FileContent>,
+    tuning: &ResolvedIoTuning,
+) -> std::result::Result<(), String> {
+    let bytes = content.map(FileContent::to_bytes).transpose()?;
+    let mut file = tuning
+        .retry(|| {
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(extended(path))
+        })
+        .map_err(|e| format!("Failed to create file: {:?}, error: {:?}", path, e))?;
+    if let Some(bytes) = bytes {
+        if let Err(e) = file.write_all(&bytes) {
+            // A partly written file would pass for a valid one at the next verification.
+            drop(file);
+            let _ = std::fs::remove_file(extended(path));
+            return Err(format!("Failed to write file: {:?}, error: {:?}", path, e));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use std::path::Path;
+
+use tauri::{Manager, Runtime};
+
+use crate::{long_path::extended, models::*};
+
+/// Counts the entries declared by a structure item and how many of them exist under `path`, without repairing anything.
+///
+/// Directories that don't exist count as a single missing entry, their contents are not visited.
+///
+/// # Returns
+///
+/// Returns `(present, declared)`, or `Err(String)` if a `when` clause can't be evaluated.
+pub(crate) fn count_matches<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    path: &Path,
+    structure_item: &StructureItem,
+) -> std::result::Result<(usize, usize), String> {
+    let mut present = 0;
+    let mut declared = 0;
+
+    if let Some(files) = &structure_item.files {
+        for file in files {
+            declared += 1;
+            if extended(&path.join(&file.name)).is_file() {
+                present += 1;
+            }
+        }
+    }
+
+    if let Some(dirs) = &structure_item.dirs {
+        for (dir_name, dir) in dirs {
+            if !crate::conditions::is_active(manager, dir)? {
+                continue;
+            }
+
+            let dir_path = path.join(dir_name);
+            declared += 1;
+            if extended(&dir_path).is_dir() {
+                present += 1;
+                let (dir_present, dir_declared) = count_matches(manager, &dir_path, dir)?;
+                present += dir_present;
+                declared += dir_declared;
+            }
+        }
+    }
+
+    Ok((present, declared))
+}
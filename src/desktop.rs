@@ -1,7 +1,7 @@
 use serde::de::DeserializeOwned;
-use tauri::{plugin::PluginApi, AppHandle, Runtime};
+use tauri::{plugin::PluginApi, AppHandle, Manager, Runtime};
 
-use crate::models::*;
+use crate::{cache::ResultCache, conditions::Conditions, models::*};
 
 pub fn init<R: Runtime, C: DeserializeOwned>(
     app: &AppHandle<R>,
@@ -19,4 +19,12 @@ impl<R: Runtime> StructureManager<R> {
             value: payload.value,
         })
     }
+
+    /// Sets a named condition, evaluated by the structure items declaring `when: "<name>"` at verification time.
+    ///
+    /// Cached verification results are invalidated, as they may depend on the condition.
+    pub fn set_condition(&self, name: impl Into<String>, value: bool) {
+        self.0.state::<Conditions>().set(name.into(), value);
+        self.0.state::<ResultCache>().invalidate(None);
+    }
 }
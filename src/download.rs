@@ -0,0 +1,186 @@
+use std::path::Path;
+#[cfg(feature = "download")]
+use std::{
+    fs::OpenOptions,
+    io::{Read, Write},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "download")]
+use sha2::{Digest, Sha256};
+use tauri::{Manager, Runtime};
+
+#[cfg(feature = "download")]
+use crate::{events, hash::sha256_file, history, long_path::extended, models::*};
+
+/// The directory partial downloads are kept in until they are complete, relative to the plugin's own directory.
+#[cfg(feature = "download")]
+const PARTIAL_DIRECTORY: &str = "downloads";
+
+/// The minimum interval between two progress events of a download.
+#[cfg(feature = "download")]
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Downloads a missing file declared with a `url` to `path`.
+///
+/// The download is written to a partial file in `appLocalData/structure-manager/downloads`, so an interrupted
+/// download of the same URL resumes where it stopped (when the server supports range requests), and the file only
+/// appears at `path` once complete and matching `sha256`, if set. Progress is emitted as `download-progress` events.
+#[cfg(feature = "download")]
+pub(crate) fn download<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    path: &Path,
+    url: &str,
+    sha256: Option<&str>,
+) -> std::result::Result<(), String> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("download", url).entered();
+
+    let partial = partial_path(manager, url)?;
+    if let Some(parent) = partial.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", parent, e))?;
+    }
+    let offset = std::fs::metadata(&partial).map_or(0, |metadata| metadata.len());
+
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(30))
+        .timeout_read(Duration::from_secs(60))
+        .build();
+    let mut request = agent.get(url);
+    if offset > 0 {
+        request = request.set("Range", &format!("bytes={}-", offset));
+    }
+    let response = match request.call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(416, _)) if offset > 0 => {
+            // The partial download can't be resumed, e.g. the file changed on the server: it starts over.
+            std::fs::remove_file(&partial)
+                .map_err(|e| format!("Failed to delete {:?}, error: {:?}", partial, e))?;
+            return download(manager, path, url, sha256);
+        }
+        Err(e) => return Err(format!("Failed to download {}: {}", url, e)),
+    };
+
+    // A server ignoring the range sends the whole file again.
+    let resumed = response.status() == 206;
+    let mut downloaded = if resumed { offset } else { 0 };
+    let total = response
+        .header("Content-Length")
+        .and_then(|length| length.parse::<u64>().ok())
+        .map(|length| length + downloaded);
+    if let Some(total) = total {
+        crate::space::check(&partial, total - downloaded).map_err(|e| e.to_string())?;
+    }
+
+    let mut options = OpenOptions::new();
+    if resumed {
+        options.append(true);
+    } else {
+        options.write(true).create(true).truncate(true);
+    }
+    let mut file = options
+        .open(&partial)
+        .map_err(|e| format!("Failed to open {:?}, error: {:?}", partial, e))?;
+
+    let progress = |downloaded: u64| {
+        events::emit(
+            manager,
+            events::DOWNLOAD_PROGRESS,
+            DownloadProgress {
+                path: path.to_path_buf(),
+                url: url.to_string(),
+                downloaded,
+                total,
+            },
+        );
+    };
+    progress(downloaded);
+
+    let mut reader = response.into_reader();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut last_progress = Instant::now();
+    loop {
+        let read = reader
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])
+            .map_err(|e| format!("Failed to write {:?}, error: {:?}", partial, e))?;
+        downloaded += read as u64;
+
+        if last_progress.elapsed() >= PROGRESS_INTERVAL {
+            progress(downloaded);
+            last_progress = Instant::now();
+        }
+    }
+    file.flush()
+        .map_err(|e| format!("Failed to write {:?}, error: {:?}", partial, e))?;
+    drop(file);
+    progress(downloaded);
+
+    if let Some(expected) = sha256 {
+        let tuning = IoTuning::default().resolve(&partial);
+        let actual = sha256_file(&partial, &tuning)
+            .map_err(|e| format!("Failed to hash file: {:?}, error: {:?}", partial, e))?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            // Resuming a corrupted download would never match either.
+            let _ = std::fs::remove_file(&partial);
+            return Err(format!(
+                "Downloaded file doesn't match its hash: {}, expected: {}, actual: {}",
+                url, expected, actual
+            ));
+        }
+    }
+
+    // Partial downloads may be on another volume than the file, where it can't be renamed to.
+    if std::fs::rename(&partial, extended(path)).is_err() {
+        crate::space::check(path, downloaded).map_err(|e| e.to_string())?;
+        std::fs::copy(&partial, extended(path))
+            .map_err(|e| format!("Failed to copy {:?} to {:?}, error: {:?}", partial, path, e))?;
+        let _ = std::fs::remove_file(&partial);
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::info!(bytes = downloaded, resumed, "file downloaded");
+    Ok(())
+}
+
+/// Fails to download a file declared with a `url`, downloads require the `download` feature.
+#[cfg(not(feature = "download"))]
+pub(crate) fn download<R: Runtime, M: Manager<R>>(
+    _manager: &M,
+    _path: &Path,
+    url: &str,
+    _sha256: Option<&str>,
+) -> std::result::Result<(), String> {
+    Err(format!(
+        "Failed to download {}: the `download` feature of the plugin is disabled",
+        url
+    ))
+}
+
+/// Returns the partial file of the downloads of a URL.
+#[cfg(feature = "download")]
+fn partial_path<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    url: &str,
+) -> std::result::Result<PathBuf, String> {
+    let name: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    manager
+        .path()
+        .app_local_data_dir()
+        .map(|directory| {
+            directory
+                .join(history::DIRECTORY)
+                .join(PARTIAL_DIRECTORY)
+                .join(format!("{}.part", name))
+        })
+        .map_err(|e| format!("Failed to resolve the downloads directory: {}", e))
+}
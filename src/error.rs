@@ -1,4 +1,4 @@
-use serde::{ser::Serializer, Serialize};
+use serde::{ser::SerializeStruct, ser::Serializer, Serialize};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -6,16 +6,30 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Tauri(#[from] tauri::Error),
+    #[error("{0}")]
+    Verification(String),
+    #[error("Path not allowed by the structure-manager scope: {0:?}")]
+    PathNotAllowed(std::path::PathBuf),
+    #[error("Root not allowed by the structure-manager scope: {0}")]
+    RootNotAllowed(String),
+    #[error("Insufficient disk space: {required} bytes required, {available} bytes available")]
+    InsufficientSpace { required: u64, available: u64 },
     #[cfg(mobile)]
     #[error(transparent)]
     PluginInvoke(#[from] tauri::plugin::mobile::PluginInvokeError),
 }
 
+/// Serialized as `{ code, message }`, see [`Error::code`].
 impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        let mut error = serializer.serialize_struct("Error", 2)?;
+        error.serialize_field("code", self.code())?;
+        error.serialize_field("message", &self.to_string())?;
+        error.end()
     }
 }
@@ -0,0 +1,45 @@
+use log::warn;
+use serde::Serialize;
+use tauri::{Emitter, Manager, Runtime};
+
+/// Emitted when a verification starts, with a [`VerificationStarted`](crate::VerificationStarted) payload.
+pub const VERIFICATION_STARTED: &str = "structure-manager://verification-started";
+/// Emitted when a verification succeeds, with a [`VerificationSummary`](crate::VerificationSummary) payload.
+pub const VERIFICATION_FINISHED: &str = "structure-manager://verification-finished";
+/// Emitted when a verification fails, with a [`VerificationSummary`](crate::VerificationSummary) payload.
+pub const VERIFICATION_FAILED: &str = "structure-manager://verification-failed";
+
+/// Emitted when `verify_all` skips a root that can't be resolved on the platform, with a
+/// [`VerificationSkipped`](crate::VerificationSkipped) payload.
+pub const VERIFICATION_SKIPPED: &str = "structure-manager://verification-skipped";
+
+/// Emitted once the verification of the roots on startup is done, background verifications included, with a
+/// [`StartupVerification`](crate::StartupVerification) payload. Emitted at the end of the setup when no root is
+/// verified in the background, before any window listens: see `StructureManagerExt::wait_until_ready`.
+pub const READY: &str = "structure-manager://ready";
+
+/// Emitted when `verify_manifest` finds files of a protected root modified or removed, with a
+/// [`TamperDetected`](crate::TamperDetected) payload.
+pub const TAMPER_DETECTED: &str = "structure-manager://tamper-detected";
+
+/// Emitted when a root has been scaffolded, with a [`ScaffoldSummary`](crate::ScaffoldSummary) payload.
+pub const SCAFFOLD_FINISHED: &str = "structure-manager://scaffold-finished";
+
+/// Emitted while a file declared with a `url` is downloaded, with a [`DownloadProgress`](crate::DownloadProgress)
+/// payload.
+pub const DOWNLOAD_PROGRESS: &str = "structure-manager://download-progress";
+
+/// Emitted periodically while an operation runs longer than the heartbeat threshold, with an
+/// [`OperationHeartbeat`](crate::OperationHeartbeat) payload.
+pub const HEARTBEAT: &str = "structure-manager://heartbeat";
+
+/// Emits a plugin event to every window, a failure is only logged since it must not affect the verification.
+pub(crate) fn emit<R: Runtime, M: Manager<R>, S: Serialize + Clone>(
+    manager: &M,
+    event: &str,
+    payload: S,
+) {
+    if let Err(e) = manager.app_handle().emit(event, payload) {
+        warn!("Failed to emit `{}`: {}", event, e);
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use serde::{de::IgnoredAny, Deserialize};
+
+use crate::{long_path::extended, models::FileFormat};
+
+impl FileFormat {
+    /// Returns the name of the format, as in the configuration.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            FileFormat::Json => "json",
+            FileFormat::Toml => "toml",
+            FileFormat::Yaml => "yaml",
+            FileFormat::Ini => "ini",
+        }
+    }
+
+    /// Checks that a file parses in this format, its content is read but not kept.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the file parses, or `Err(String)` with the syntax error.
+    pub(crate) fn check(self, path: &Path) -> std::result::Result<(), String> {
+        let content = std::fs::read_to_string(extended(path))
+            .map_err(|e| format!("Failed to read file: {:?}", e))?;
+
+        match self {
+            FileFormat::Json => serde_json::from_str::<IgnoredAny>(&content)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            FileFormat::Toml => toml::from_str::<toml::Table>(&content)
+                .map(|_| ())
+                .map_err(|e| match e.span() {
+                    Some(span) => {
+                        let line = content[..span.start].matches('\n').count() + 1;
+                        format!("{} at line {}", e.message(), line)
+                    }
+                    None => e.message().to_string(),
+                }),
+            FileFormat::Yaml => serde_yaml::Deserializer::from_str(&content)
+                .try_for_each(|document| IgnoredAny::deserialize(document).map(|_| ()))
+                .map_err(|e| e.to_string()),
+            FileFormat::Ini => check_ini(&content),
+        }
+    }
+}
+
+fn check_ini(content: &str) -> std::result::Result<(), String> {
+    for (index, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        let valid = match line.strip_prefix('[') {
+            Some(section) => section
+                .strip_suffix(']')
+                .is_some_and(|name| !name.trim().is_empty()),
+            None => line
+                .split_once(['=', ':'])
+                .is_some_and(|(key, _)| !key.trim().is_empty()),
+        };
+        if !valid {
+            return Err(format!("invalid line {}: {:?}", index + 1, line));
+        }
+    }
+
+    Ok(())
+}
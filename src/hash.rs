@@ -0,0 +1,46 @@
+use std::{io::Read, path::Path, time::Duration};
+
+use sha2::{Digest, Sha256};
+
+use crate::{long_path::extended, models::StructureItem, tuning::ResolvedIoTuning};
+
+/// Computes the SHA-256 hash of a file as a lowercase hexadecimal string.
+///
+/// The file is read in chunks of `hash_chunk_size` bytes, and reads are spaced out to honor
+/// `io_throttle_bytes_per_sec` when it is set.
+pub(crate) fn sha256_file(path: &Path, tuning: &ResolvedIoTuning) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(extended(path))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; tuning.hash_chunk_size];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+
+        if let Some(throttle) = tuning
+            .io_throttle_bytes_per_sec
+            .filter(|throttle| *throttle > 0)
+        {
+            std::thread::sleep(Duration::from_secs_f64(read as f64 / throttle as f64));
+        }
+    }
+
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Computes the SHA-256 hash of a structure item as a lowercase hexadecimal string, so a verification can be told
+/// apart from one of a previous version of the structure, see `LastVerification::config_hash`.
+pub(crate) fn config_hash(structure_item: &StructureItem) -> String {
+    let json = serde_json::to_vec(structure_item).unwrap_or_default();
+    Sha256::digest(json)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
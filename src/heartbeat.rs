@@ -0,0 +1,105 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::{Duration, Instant},
+};
+
+use tauri::{Manager, Runtime};
+
+use crate::{events, models::*};
+
+static NEXT_OPERATION_ID: AtomicU64 = AtomicU64::new(1);
+
+/// When heartbeats are emitted, set with `Builder::heartbeat`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct HeartbeatSettings {
+    /// How long an operation runs before its first heartbeat.
+    pub(crate) threshold: Duration,
+    /// The time between two heartbeats.
+    pub(crate) interval: Duration,
+}
+
+impl Default for HeartbeatSettings {
+    fn default() -> Self {
+        Self {
+            threshold: Duration::from_secs(2),
+            interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// Emits heartbeats while a long-running operation is in progress, until it is dropped.
+pub(crate) struct Monitor {
+    id: u64,
+    current_path: Arc<Mutex<Option<PathBuf>>>,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Monitor {
+    /// Starts monitoring an operation on `root`, the first heartbeat is emitted once the threshold is exceeded.
+    pub(crate) fn start<R: Runtime, M: Manager<R>>(
+        manager: &M,
+        operation: OperationKind,
+        root: &str,
+    ) -> Self {
+        let id = NEXT_OPERATION_ID.fetch_add(1, Ordering::Relaxed);
+        let settings = *manager.state::<HeartbeatSettings>();
+        let current_path = Arc::new(Mutex::new(None::<PathBuf>));
+        let (stop, stopped) = mpsc::channel::<()>();
+
+        let app = manager.app_handle().clone();
+        let root = root.to_string();
+        let path = current_path.clone();
+        let thread = std::thread::spawn(move || {
+            let start = Instant::now();
+            let mut timeout = settings.threshold;
+            // The operation is over once the sender is dropped.
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(timeout) {
+                events::emit(
+                    &app,
+                    events::HEARTBEAT,
+                    OperationHeartbeat {
+                        operation_id: id,
+                        operation,
+                        root: root.clone(),
+                        elapsed_ms: start.elapsed().as_millis() as u64,
+                        current_path: path.lock().unwrap().clone(),
+                    },
+                );
+                timeout = settings.interval;
+            }
+        });
+
+        Self {
+            id,
+            current_path,
+            stop: Some(stop),
+            thread: Some(thread),
+        }
+    }
+
+    /// The identifier of the monitored operation, unique for the lifetime of the process.
+    pub(crate) fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Records the path the operation is working on, reported by the next heartbeat.
+    pub(crate) fn at(&self, path: &Path) {
+        *self.current_path.lock().unwrap() = Some(path.to_path_buf());
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use tauri::{Manager, Runtime};
+
+use crate::models::*;
+
+/// The directory of the plugin's own data, relative to `appLocalData`.
+pub(crate) const DIRECTORY: &str = "structure-manager";
+
+/// The file of the verification history, in [`DIRECTORY`].
+const FILE_NAME: &str = "history.jsonl";
+
+/// The rolling history of the verifications, see `StructureManagerExt::history`.
+pub(crate) struct History {
+    /// The maximum number of verifications kept, set with `Builder::history_limit`.
+    limit: usize,
+    /// Serializes the rewrites of the history file.
+    lock: Mutex<()>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+impl History {
+    /// Creates a history keeping at most `limit` verifications.
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Appends a finished verification to the history, dropping the oldest ones beyond the limit.
+    ///
+    /// A verification never fails because of its history, errors are only logged.
+    pub(crate) fn record<R: Runtime, M: Manager<R>>(
+        &self,
+        manager: &M,
+        summary: &VerificationSummary,
+    ) {
+        if self.limit == 0 {
+            return;
+        }
+
+        let record = VerificationRecord {
+            operation_id: summary.operation_id,
+            root: summary.root.clone(),
+            verified_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_millis() as u64),
+            duration_ms: summary.duration_ms,
+            success: summary.error.is_none(),
+            error: summary.error.clone(),
+            entries_checked: summary.counts.files + summary.counts.directories,
+            repaired: summary.counts.repaired,
+            warnings: summary.warnings.len(),
+        };
+
+        let _guard = self.lock.lock().unwrap();
+        if let Err(e) = self.append(manager, &record) {
+            log::warn!(
+                "Failed to record the verification of `{}` in its history: {}",
+                summary.root,
+                e
+            );
+        }
+    }
+
+    /// Returns the most recent verifications, newest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Only returns the verifications of this root, if set.
+    /// * `limit` - The maximum number of verifications returned.
+    pub(crate) fn query<R: Runtime, M: Manager<R>>(
+        &self,
+        manager: &M,
+        root: Option<&str>,
+        limit: usize,
+    ) -> std::result::Result<Vec<VerificationRecord>, String> {
+        let _guard = self.lock.lock().unwrap();
+        let mut records = read(&path(manager)?)?;
+        records.retain(|record| root.map_or(true, |root| record.root == root));
+        records.reverse();
+        records.truncate(limit);
+        Ok(records)
+    }
+
+    fn append<R: Runtime, M: Manager<R>>(
+        &self,
+        manager: &M,
+        record: &VerificationRecord,
+    ) -> std::result::Result<(), String> {
+        let path = path(manager)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", parent, e))?;
+        }
+        let line = serde_json::to_string(record).map_err(|e| e.to_string())?;
+
+        let mut records = read(&path)?;
+        if records.len() < self.limit {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| format!("Failed to open {:?}, error: {:?}", path, e))?;
+            return writeln!(file, "{}", line)
+                .map_err(|e| format!("Failed to write {:?}, error: {:?}", path, e));
+        }
+
+        // The history is full: it is rewritten without its oldest verifications, then swapped in.
+        records.drain(..=records.len() - self.limit);
+        let mut content = String::new();
+        for record in &records {
+            content.push_str(&serde_json::to_string(record).map_err(|e| e.to_string())?);
+            content.push('\n');
+        }
+        content.push_str(&line);
+        content.push('\n');
+
+        let temporary = path.with_extension("jsonl.tmp");
+        std::fs::write(&temporary, content)
+            .map_err(|e| format!("Failed to write {:?}, error: {:?}", temporary, e))?;
+        std::fs::rename(&temporary, &path)
+            .map_err(|e| format!("Failed to replace {:?}, error: {:?}", path, e))
+    }
+}
+
+/// Returns the path of the history file.
+fn path<R: Runtime, M: Manager<R>>(manager: &M) -> std::result::Result<PathBuf, String> {
+    manager
+        .path()
+        .app_local_data_dir()
+        .map(|directory| directory.join(DIRECTORY).join(FILE_NAME))
+        .map_err(|e| format!("Failed to resolve the history file: {}", e))
+}
+
+/// Reads the history file, skipping the lines that can't be parsed (e.g. one cut short by a crash).
+fn read(path: &Path) -> std::result::Result<Vec<VerificationRecord>, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {:?}, error: {:?}", path, e)),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
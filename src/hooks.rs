@@ -0,0 +1,53 @@
+use std::path::Path;
+
+use crate::{models::*, telemetry::TelemetrySink};
+
+/// A hook called for each declared entry found missing, before it is repaired (if repair is enabled).
+pub type MissingHook = Box<dyn Fn(&Path, EntryKind) + Send + Sync>;
+/// A hook called for each entry created or fixed by a repair.
+pub type RepairedHook = Box<dyn Fn(&Path) + Send + Sync>;
+/// A hook called for each violation that makes a verification fail.
+pub type ViolationHook = Box<dyn Fn(&Violation) + Send + Sync>;
+
+/// The lifecycle hooks registered with `Builder::on_missing`, `Builder::on_repaired` and `Builder::on_violation`,
+/// and the telemetry sinks registered with `Builder::telemetry`.
+#[derive(Default)]
+pub(crate) struct Hooks {
+    pub(crate) on_missing: Vec<MissingHook>,
+    pub(crate) on_repaired: Vec<RepairedHook>,
+    pub(crate) on_violation: Vec<ViolationHook>,
+    pub(crate) telemetry: Vec<Box<dyn TelemetrySink>>,
+}
+
+impl Hooks {
+    pub(crate) fn missing(&self, path: &Path, kind: EntryKind) {
+        for hook in &self.on_missing {
+            hook(path, kind);
+        }
+    }
+
+    pub(crate) fn repaired(&self, path: &Path) {
+        for hook in &self.on_repaired {
+            hook(path);
+        }
+        for sink in &self.telemetry {
+            sink.on_repair(path);
+        }
+    }
+
+    pub(crate) fn violation(&self, violation: &Violation) {
+        for hook in &self.on_violation {
+            hook(violation);
+        }
+    }
+
+    /// Forwards a finished verification to the telemetry sinks.
+    pub(crate) fn finished(&self, summary: &VerificationSummary) {
+        for sink in &self.telemetry {
+            match summary.error {
+                Some(_) => sink.on_failure(summary),
+                None => sink.on_verification_complete(summary),
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::path::Path;
+
+use glob::Pattern;
+
+use crate::{
+    long_path::extended,
+    manifest::{MANIFEST_FILE, SIGNATURE_FILE},
+    migrations::VERSION_MARKER,
+};
+
+/// The name of the file listing ignore patterns for the entries of its directory, one per line.
+pub const IGNORE_FILE: &str = ".structureignore";
+
+/// Returns whether an entry is one of the files the plugin keeps in the directories it manages: the `.structureignore`,
+/// `.structure-version` and manifest files, never reported, cleaned or deleted.
+pub(crate) fn is_marker(name: &str) -> bool {
+    [IGNORE_FILE, VERSION_MARKER, MANIFEST_FILE, SIGNATURE_FILE].contains(&name)
+}
+
+/// The glob patterns of the entry names ignored in a directory.
+pub(crate) struct IgnoreSet(Vec<Pattern>);
+
+impl IgnoreSet {
+    /// Collects the patterns ignored in a directory: the given ones and the ones of its `.structureignore` file.
+    ///
+    /// Lines of `.structureignore` are trimmed, empty lines and lines starting with `#` are skipped.
+    pub(crate) fn for_dir<'a>(
+        path: &Path,
+        patterns: impl IntoIterator<Item = &'a String>,
+    ) -> std::result::Result<Self, String> {
+        let file_patterns = match std::fs::read_to_string(extended(&path.join(IGNORE_FILE))) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(String::from)
+                .collect(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read: {:?}, error: {:?}",
+                    path.join(IGNORE_FILE),
+                    e
+                ))
+            }
+        };
+
+        let mut patterns: Vec<&str> = patterns.into_iter().map(String::as_str).collect();
+        patterns.extend(file_patterns.iter().map(String::as_str));
+        Self::from_patterns(patterns)
+    }
+
+    /// Compiles the given patterns, without reading any `.structureignore` file.
+    pub(crate) fn from_patterns(
+        patterns: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> std::result::Result<Self, String> {
+        let mut compiled = Vec::new();
+        for pattern in patterns {
+            let pattern = pattern.as_ref();
+            compiled.push(
+                Pattern::new(pattern)
+                    .map_err(|e| format!("Invalid ignore pattern: {:?}, error: {}", pattern, e))?,
+            );
+        }
+
+        Ok(IgnoreSet(compiled))
+    }
+
+    /// Returns whether an entry of the directory is ignored, the `.structureignore`, `.structure-version` and manifest
+    /// files always are.
+    pub(crate) fn is_ignored(&self, name: &str) -> bool {
+        is_marker(name) || self.0.iter().any(|pattern| pattern.matches(name))
+    }
+}
@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use jsonschema::JSONSchema;
+use tauri::{path::BaseDirectory, Manager, Runtime};
+
+use crate::{long_path::extended, models::*, roots};
+
+/// Compiles the `schema` option of a file, reading a bundled schema from `resource_dir()`.
+///
+/// # Returns
+///
+/// Returns the compiled schema, or `Err(String)` if the schema can't be read or isn't a valid JSON Schema.
+pub(crate) fn compile<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    schema: &FileSchema,
+) -> std::result::Result<JSONSchema, String> {
+    let resource;
+    let schema = match schema {
+        FileSchema::Inline(schema) => schema,
+        FileSchema::Resource(path) => {
+            let path = roots::resolve(manager, BaseDirectory::Resource)?.join(path);
+            resource = read_json(&path)
+                .map_err(|e| format!("Failed to read JSON Schema: {:?}, error: {}", path, e))?;
+            &resource
+        }
+    };
+
+    JSONSchema::compile(schema).map_err(|e| format!("Invalid JSON Schema: {}", e))
+}
+
+/// Validates a JSON file against a compiled schema.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the file parses and conforms to the schema, or `Err(String)` describing why it doesn't, with
+/// every schema violation and the location of each one in the document.
+pub(crate) fn validate(schema: &JSONSchema, path: &Path) -> std::result::Result<(), String> {
+    let instance = read_json(path)?;
+    if let Err(errors) = schema.validate(&instance) {
+        let errors: Vec<String> = errors
+            .map(|error| match error.instance_path.to_string() {
+                location if location.is_empty() => error.to_string(),
+                location => format!("{} (at {})", error, location),
+            })
+            .collect();
+        return Err(errors.join(", "));
+    }
+
+    Ok(())
+}
+
+fn read_json(path: &Path) -> std::result::Result<serde_json::Value, String> {
+    let content =
+        std::fs::read(extended(path)).map_err(|e| format!("Failed to read file: {:?}", e))?;
+    serde_json::from_slice(&content).map_err(|e| format!("Invalid JSON: {}", e))
+}
@@ -0,0 +1,26 @@
+use std::path::{Path, PathBuf};
+
+use crate::{long_path::extended, models::*, normalization};
+
+/// Returns the first legacy location of a missing directory (see its `movedFrom` option) that exists in `parent`,
+/// the directory the repair renames to its declared name instead of creating an empty one.
+pub(crate) fn locate(
+    parent: &Path,
+    structure_item: &StructureItem,
+    form: Option<UnicodeNormalization>,
+) -> Option<PathBuf> {
+    structure_item
+        .options
+        .as_ref()
+        .and_then(|options| options.moved_from.as_deref())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| normalization::locate(parent, name, form))
+        .find(|path| extended(path).is_dir())
+}
+
+/// Renames a legacy directory to the declared path of the directory.
+pub(crate) fn rename(from: &Path, to: &Path) -> std::result::Result<(), String> {
+    std::fs::rename(extended(from), extended(to))
+        .map_err(|e| format!("Failed to rename {:?} to {:?}, error: {:?}", from, to, e))
+}
@@ -1,8 +1,15 @@
-use log::{info, warn};
-use std::{path::PathBuf, sync::Mutex};
+use log::{error, info, warn};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
 use tauri::{
-    plugin::{Builder, TauriPlugin},
+    path::BaseDirectory,
+    plugin::{Builder as PluginBuilder, TauriPlugin},
+    utils::config::FsScope,
     Manager, Runtime,
 };
 
@@ -13,11 +20,87 @@ mod desktop;
 #[cfg(mobile)]
 mod mobile;
 
+mod acl;
+mod archive;
+mod attributes;
+mod audit;
+pub mod build;
+mod builder;
+mod cache;
+mod clean;
+mod codes;
+mod combinators;
 mod commands;
+mod conditions;
+mod config;
+mod content;
+mod contracts;
+mod download;
 mod error;
+pub mod events;
+mod format;
+mod hash;
+mod heartbeat;
+mod history;
+mod hooks;
+mod ignore;
+mod json_schema;
+mod legacy;
+mod long_path;
+mod manifest;
+mod messages;
+mod migrations;
 mod models;
-
+mod names;
+mod normalization;
+mod owner;
+mod ownership;
+mod plan;
+#[cfg(desktop)]
+mod preflight;
+mod refs;
+mod repair_scope;
+mod report;
+mod roots;
+mod scaffold;
+mod schema;
+mod scope;
+mod seed;
+mod snapshot;
+mod space;
+mod sqlite;
+mod startup;
+mod stats;
+#[cfg(feature = "store")]
+mod store;
+mod telemetry;
+mod timeout;
+mod tree;
+mod tuning;
+mod validators;
+mod verify;
+#[cfg(feature = "watch")]
+mod watch;
+mod workspaces;
+
+#[cfg(feature = "archive")]
+pub use archive::ArchiveRoot;
+pub use builder::{StructureConfigBuilder, StructureItemBuilder};
 pub use error::{Error, Result};
+pub use hooks::{MissingHook, RepairedHook, ViolationHook};
+pub use ignore::IGNORE_FILE;
+pub use manifest::{MANIFEST_FILE, SIGNATURE_FILE};
+pub use messages::Translator;
+pub use migrations::VERSION_MARKER;
+#[cfg(desktop)]
+pub use preflight::Preflight;
+pub use roots::{field_name, RootSelector, ROOTS};
+pub use schema::SCHEMA_VERSION;
+pub use scope::ScopeEntry;
+pub use snapshot::snapshot;
+pub use telemetry::TelemetrySink;
+pub use tuning::{detect_volume, ResolvedIoTuning, VolumeKind};
+pub use validators::Validator;
 
 #[cfg(desktop)]
 use desktop::StructureManager;
@@ -29,9 +112,118 @@ pub trait StructureManagerExt<R: Runtime> {
     fn structure_manager(&self) -> &StructureManager<R>;
     fn dfs_verify(
         &self,
-        path: PathBuf,
-        structure_item: &StructureItem,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<(), String>;
+    fn verify_path(
+        &self,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<(), String>;
+    #[cfg(feature = "archive")]
+    fn verify_archive(
+        &self,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<(), String>;
+    fn assert_external_contract(
+        &self,
+        path: impl AsRef<Path>,
+        contract_name: &str,
+    ) -> std::result::Result<(), String>;
+    fn detect_contract(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> std::result::Result<Vec<(String, f64)>, String>;
+    fn verify_custom(&self, name: &str) -> std::result::Result<(), String>;
+    fn register_structure(
+        &self,
+        name: impl Into<String>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<(), String>;
+    fn verify_named(&self, name: &str, path: impl AsRef<Path>) -> std::result::Result<(), String>;
+    fn add_workspace(
+        &self,
+        name: impl Into<String>,
+        path: impl Into<PathBuf>,
+        structure: impl Into<String>,
+    ) -> std::result::Result<(), String>;
+    fn remove_workspace(&self, name: &str) -> std::result::Result<bool, String>;
+    fn workspaces(&self) -> Vec<WorkspaceRoot>;
+    fn verify(&self, root: impl Into<RootSelector>) -> std::result::Result<(), String>;
+    fn verify_streamed(
+        &self,
+        root: impl Into<RootSelector>,
+        on_event: &dyn Fn(VerificationEvent),
+    ) -> std::result::Result<(), String>;
+    fn verify_all(&self) -> std::result::Result<(), String>;
+    fn scaffold(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<ScaffoldSummary, String>;
+    fn scaffold_all(&self) -> std::result::Result<Vec<ScaffoldSummary>, String>;
+    fn migrate(&self) -> std::result::Result<MigrationSummary, String>;
+    fn clean(
+        &self,
+        root: impl Into<RootSelector>,
+        dry_run: bool,
+    ) -> std::result::Result<CleanSummary, String>;
+    fn clean_cache(&self, dry_run: bool) -> std::result::Result<Vec<CleanSummary>, String>;
+    fn clean_temp(&self, dry_run: bool) -> std::result::Result<Option<CleanSummary>, String>;
+    fn structure_version(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<Option<u32>, String>;
+    fn generate_manifest(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<Manifest, String>;
+    fn verify_manifest(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<ManifestDiff, String>;
+    fn last_verification_stats(&self) -> BTreeMap<String, VerificationStats>;
+    fn wait_until_ready(&self) -> StartupVerification;
+    fn invalidate_verification_cache(&self, root: Option<RootSelector>);
+    fn history(
+        &self,
+        root: Option<&str>,
+        limit: usize,
+    ) -> std::result::Result<Vec<VerificationRecord>, String>;
+    #[cfg(feature = "store")]
+    fn last_verification(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<Option<LastVerification>, String>;
+    #[cfg(feature = "watch")]
+    fn watch(
+        &self,
+        root: impl Into<RootSelector>,
+        on_event: impl Fn(VerificationEvent) + Send + 'static,
     ) -> std::result::Result<(), String>;
+    #[cfg(feature = "watch")]
+    fn unwatch(&self, root: impl Into<RootSelector>) -> bool;
+    fn verification_report(&self, root: impl Into<RootSelector>) -> VerificationReport;
+    fn write_report(
+        &self,
+        root: impl Into<RootSelector>,
+        path: impl AsRef<Path>,
+        format: ReportFormat,
+    ) -> std::result::Result<VerificationReport, String>;
+    fn repair_plan(&self, root: impl Into<RootSelector>)
+        -> std::result::Result<RepairPlan, String>;
+    fn plan_path(
+        &self,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<RepairPlan, String>;
+    fn apply_repair(&self, plan: &RepairPlan, dry_run: bool) -> ReconciliationReport;
+    fn tree_json(
+        &self,
+        directory: BaseDirectory,
+        relative: Option<PathBuf>,
+        options: &TreeOptions,
+    ) -> std::result::Result<Vec<TreeNode>, String>;
     fn verify_app_cache(&self) -> std::result::Result<(), String>;
     fn verify_app_config(&self) -> std::result::Result<(), String>;
     fn verify_app_data(&self) -> std::result::Result<(), String>;
@@ -74,452 +266,1607 @@ impl<R: Runtime, T: Manager<R>> crate::StructureManagerExt<R> for T {
     /// Returns `Ok(())` if the directory structure is valid, or `Err(String)` with an error message if any issues are found.
     fn dfs_verify(
         &self,
-        path: PathBuf,
-        structure_item: &StructureItem,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
     ) -> std::result::Result<(), String> {
-        match &structure_item.files {
-            Some(files) => {
-                for file in files {
-                    let file_path = path.join(file);
-                    if !file_path.exists() {
-                        return Err(format!("File not found: {:?}", file_path));
-                    }
-                }
-            }
-            None => {}
-        }
-
-        match &structure_item.dirs {
-            Some(dirs) => {
-                for (dir_name, dir) in dirs {
-                    let dir_path = path.join(dir_name);
-                    if !dir_path.exists() {
-                        match &dir.options {
-                            Some(options) => {
-                                let mut repair = false;
-                                if let Some(value) = options.repair {
-                                    repair = value;
-                                }
-
-                                if repair {
-                                    std::fs::create_dir_all(&dir_path).map_err(|e| {
-                                        format!(
-                                            "Failed to create directory: {:?}, error: {:?}",
-                                            dir_path, e
-                                        )
-                                    })?;
-                                } else {
-                                    return Err(format!("Directory not found: {:?}.", dir_path));
-                                }
-
-                                // if let Some(value) = options.strict {} // TODO: Implement strict mode
-                            }
-                            None => {}
-                        }
-                    }
-                    self.dfs_verify(dir_path, dir)?;
-                }
-            }
-            None => {}
-        }
+        let path = path.as_ref().to_path_buf();
+        let structure_item = &structure_item.into();
+        let ignore = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            structure_config.ignore.clone().unwrap_or_default()
+        };
 
-        Ok(())
+        verify::Walker::new(self, &path, structure_item)
+            .with_ignore(ignore)
+            .run(&path.display().to_string(), |walker| {
+                walker.verify(path.clone(), structure_item)
+            })
     }
 
-    /// Verifies the structure of the `appCache` directory based on the provided structure configuration.
-    fn verify_app_cache(&self) -> std::result::Result<(), String> {
-        let path = match self.path().app_cache_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve app cache path: {:?}", e)),
+    /// Verifies any directory against a structure item provided at call time.
+    ///
+    /// Unlike `dfs_verify`, the `$ref`s of the item are resolved against the definitions of the structure configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory to be verified.
+    /// * `structure_item` - The expected structure of the directory.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the directory structure is valid, or `Err(String)` with an error message if any issues are found.
+    fn verify_path(
+        &self,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<(), String> {
+        let structure_item = structure_item.into();
+        let structure_item = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            structure_item.resolve(
+                structure_config
+                    .definitions
+                    .as_ref()
+                    .unwrap_or(&BTreeMap::new()),
+            )?
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.app_cache {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `appCache` not found".to_string()),
-        }
+        self.dfs_verify(path, structure_item)
     }
 
-    /// Verifies the structure of the `appConfig` directory based on the provided structure configuration.
-    fn verify_app_config(&self) -> std::result::Result<(), String> {
-        let path = match self.path().app_config_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve app config path: {:?}", e)),
+    /// Verifies the contents of a `.zip`, `.tar.gz` or `.tgz` archive against a structure item provided at call time,
+    /// reading its entries without extracting it, e.g. a project bundle saved by the user.
+    ///
+    /// The `$ref`s of the item are resolved like with `verify_path`. Only the presence of the declared entries (and the
+    /// absence of undeclared ones with `strict`) is verified, and nothing is repaired.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the archive to be verified.
+    /// * `structure_item` - The expected structure of the contents of the archive.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the archive contains the structure, or `Err(String)` with an error message if any issues are found.
+    #[cfg(feature = "archive")]
+    fn verify_archive(
+        &self,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<(), String> {
+        let structure_item = structure_item.into();
+        let (structure_item, ignore) = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            let structure_item = structure_item.resolve(
+                structure_config
+                    .definitions
+                    .as_ref()
+                    .unwrap_or(&BTreeMap::new()),
+            )?;
+            (
+                structure_item,
+                structure_config.ignore.clone().unwrap_or_default(),
+            )
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.app_config {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `appConfig` not found".to_string()),
-        }
+        let archive = ArchiveRoot::open(path)?;
+        archive::ArchiveVerifier::new(self, &archive, &structure_item)
+            .with_ignore(ignore)
+            .verify(&structure_item)
     }
 
-    /// Verifies the structure of the `app_data` directory based on the provided structure configuration.
-    fn verify_app_data(&self) -> std::result::Result<(), String> {
-        let path = match self.path().app_data_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve app data path: {:?}", e)),
+    /// Verifies that a folder produced by an external tool matches a contract from the structure configuration.
+    ///
+    /// Repair options of the contract structure are honored, so contracts describing third-party layouts should not enable them.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the folder to be verified, usually picked by the user.
+    /// * `contract_name` - The name of the contract in `StructureConfig::contracts`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the folder fulfills the contract, or `Err(String)` with a contract-specific message otherwise.
+    fn assert_external_contract(
+        &self,
+        path: impl AsRef<Path>,
+        contract_name: &str,
+    ) -> std::result::Result<(), String> {
+        let path = path.as_ref();
+        let contract = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+
+            match structure_config
+                .contracts
+                .as_ref()
+                .and_then(|contracts| contracts.get(contract_name))
+            {
+                Some(contract) => contract.clone(),
+                None => return Err(format!("Structure contract `{}` not found", contract_name)),
+            }
         };
+        let label = contract.label.as_deref().unwrap_or(contract_name);
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.app_data {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `appData` not found".to_string()),
+        if !path.is_dir() {
+            return Err(format!("{:?} is not a folder, expected a {}", path, label));
         }
+
+        self.dfs_verify(path, contract.structure)
+            .map_err(|e| format!("{:?} is not a valid {}: {}", path, label, e))
     }
 
-    /// Verifies the structure of the `app_local_data` directory based on the provided structure configuration.
-    fn verify_app_local_data(&self) -> std::result::Result<(), String> {
-        let path = match self.path().app_local_data_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve app local data path: {:?}", e)),
+    /// Scores a folder against every contract of the structure configuration, without repairing anything.
+    ///
+    /// The score of a contract is the share of its declared files and directories found in the folder, from `0.0` to `1.0`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the folder to be scored, usually picked by the user.
+    ///
+    /// # Returns
+    ///
+    /// Returns the contracts with a non-zero score as `(contract_name, score)`, best match first.
+    fn detect_contract(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> std::result::Result<Vec<(String, f64)>, String> {
+        let path = path.as_ref();
+        let contracts = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            structure_config.contracts.clone().unwrap_or_default()
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.app_local_data {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `appLocalData` not found".to_string()),
+        if !path.is_dir() {
+            return Err(format!("{:?} is not a folder", path));
         }
-    }
 
-    /// Verifies the structure of the `app_log` directory based on the provided structure configuration.
-    fn verify_app_log(&self) -> std::result::Result<(), String> {
-        let path = match self.path().app_log_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve app log path: {:?}", e)),
-        };
+        let mut scores = Vec::new();
+        for (name, contract) in contracts {
+            let (present, declared) = contracts::count_matches(self, path, &contract.structure)?;
+            if declared > 0 && present > 0 {
+                scores.push((name, present as f64 / declared as f64));
+            }
+        }
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
+        Ok(scores)
+    }
 
-        match &structure_config.app_log {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `appLog` not found".to_string()),
-        }
+    /// Verifies the structure of a custom root from the `custom` section of the structure configuration.
+    fn verify_custom(&self, name: &str) -> std::result::Result<(), String> {
+        self.verify(name)
     }
 
-    /// Verifies the structure of the `audio` directory based on the provided structure configuration.
-    fn verify_audio(&self) -> std::result::Result<(), String> {
-        let path = match self.path().audio_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve audio path: {:?}", e)),
-        };
+    /// Registers a named structure at runtime, like the `structures` section of the structure configuration,
+    /// replacing a structure with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name the structure is verified with by `verify_named`, e.g. `workspace`.
+    /// * `structure_item` - The expected structure of the directories verified with it.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the structure was registered, or `Err(String)` if an entry name isn't a single path
+    /// component.
+    fn register_structure(
+        &self,
+        name: impl Into<String>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<(), String> {
+        let structure_item = structure_item.into();
+        structure_item.check_entry_names()?;
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.audio {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `audio` not found".to_string()),
-        }
+        let mut structure_config = state_mutex.lock().unwrap();
+        structure_config
+            .structures
+            .get_or_insert_with(BTreeMap::new)
+            .insert(name.into(), structure_item);
+        Ok(())
     }
 
-    /// Verifies the structure of the `cache` directory based on the provided structure configuration.
-    fn verify_cache(&self) -> std::result::Result<(), String> {
-        let path = match self.path().cache_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve cache path: {:?}", e)),
+    /// Verifies any directory against a named structure of the structure configuration (or registered with
+    /// `register_structure`), e.g. each project folder picked by the user against the same `workspace` layout.
+    ///
+    /// The `$ref`s of the structure are resolved like with `verify_path`.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the structure in `StructureConfig::structures`.
+    /// * `path` - The path to the directory to be verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the directory structure is valid, or `Err(String)` with an error message if the structure
+    /// isn't declared or any issues are found.
+    fn verify_named(&self, name: &str, path: impl AsRef<Path>) -> std::result::Result<(), String> {
+        let structure_item = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            structure_config
+                .structures
+                .as_ref()
+                .and_then(|structures| structures.get(name))
+                .cloned()
+                .ok_or_else(|| format!("Structure `{}` not found", name))?
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.cache {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `cache` not found".to_string()),
-        }
+        self.verify_path(path, structure_item)
     }
 
-    /// Verifies the structure of the `config` directory based on the provided structure configuration.
-    fn verify_config(&self) -> std::result::Result<(), String> {
-        let path = match self.path().config_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve config path: {:?}", e)),
+    /// Adds a workspace root, e.g. a project folder the user picked with a dialog, verified against a named
+    /// structure. It becomes a custom root with the same guarantees as the configured ones: `verify_all` verifies
+    /// it, and it can be verified, scaffolded or cleaned by name. Workspace roots are persisted in
+    /// `appLocalData/structure-manager/workspaces.json` and restored on the next launch.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the root, replacing the workspace root with the same name.
+    /// * `path` - The absolute path of the root.
+    /// * `structure` - The name of its structure in `StructureConfig::structures`.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the root was added, or `Err(String)` if the path isn't absolute, the structure isn't
+    /// declared, the name is taken by a configured custom root or the workspace roots can't be persisted.
+    fn add_workspace(
+        &self,
+        name: impl Into<String>,
+        path: impl Into<PathBuf>,
+        structure: impl Into<String>,
+    ) -> std::result::Result<(), String> {
+        let workspace = WorkspaceRoot {
+            name: name.into(),
+            path: path.into(),
+            structure: structure.into(),
         };
+        let name = workspace.name.clone();
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
+        let mut structure_config = state_mutex.lock().unwrap();
+        self.state::<workspaces::Workspaces>()
+            .add(self, &mut structure_config, workspace)?;
+        self.state::<cache::ResultCache>().invalidate(Some(&name));
+        Ok(())
+    }
 
-        match &structure_config.config {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `config` not found".to_string()),
-        }
+    /// Removes a workspace root added with `add_workspace`. Its folder is left untouched.
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the workspace root existed, or `Err(String)` if the workspace roots can't be persisted.
+    fn remove_workspace(&self, name: &str) -> std::result::Result<bool, String> {
+        let state_mutex = self.state::<Mutex<StructureConfig>>();
+        let mut structure_config = state_mutex.lock().unwrap();
+        let removed =
+            self.state::<workspaces::Workspaces>()
+                .remove(self, &mut structure_config, name)?;
+        self.state::<cache::ResultCache>().invalidate(Some(name));
+        Ok(removed)
     }
 
-    /// Verifies the structure of the `data` directory based on the provided structure configuration.
-    fn verify_data(&self) -> std::result::Result<(), String> {
-        let path = match self.path().data_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve data path: {:?}", e)),
-        };
+    /// Returns the workspace roots added with `add_workspace`, in the order they were added.
+    fn workspaces(&self) -> Vec<WorkspaceRoot> {
+        self.state::<workspaces::Workspaces>().list()
+    }
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
+    /// Verifies the structure of a configured root based on the provided structure configuration.
+    ///
+    /// With `Builder::result_cache`, the result of a recent verification of the root with the same structure is
+    /// returned instead of walking it again.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be verified, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the directory structure is valid, or `Err(String)` with an error message if any issues are found.
+    fn verify(&self, root: impl Into<RootSelector>) -> std::result::Result<(), String> {
+        let root = root.into();
+        let cache = self.state::<cache::ResultCache>();
+        let config_hash = cache.config_hash(self, &root);
+        if let Some(config_hash) = &config_hash {
+            if let Some(result) = cache.get(self, root.name(), config_hash) {
+                log::debug!("Reusing the cached verification of `{}`", root.name());
+                return result;
+            }
+        }
 
-        match &structure_config.data {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `data` not found".to_string()),
+        let result = self.verify_streamed(root.clone(), &|_| {});
+        if let Some(config_hash) = config_hash {
+            cache.insert(root.name(), config_hash, &result);
         }
+        result
     }
 
-    /// Verifies the structure of the `desktop` directory based on the provided structure configuration.
-    fn verify_desktop(&self) -> std::result::Result<(), String> {
-        let path = match self.path().desktop_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve desktop path: {:?}", e)),
-        };
+    /// Verifies the structure of a configured root like `verify`, passing every result to a callback as it is produced.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be verified, a base directory or the name of a custom root.
+    /// * `on_event` - Called for each checked entry, and for the violation that makes the verification fail.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the directory structure is valid, or `Err(String)` with an error message if any issues are found.
+    fn verify_streamed(
+        &self,
+        root: impl Into<RootSelector>,
+        on_event: &dyn Fn(VerificationEvent),
+    ) -> std::result::Result<(), String> {
+        verify_root(self, &root.into(), on_event, true)
+    }
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
+    /// Verifies every configured root, built-in and custom (and, on iOS, the app groups), based on the provided
+    /// structure configuration.
+    ///
+    /// When a root is nested inside another one (e.g. `appData` inside `data`), the nested root owns its subtree:
+    /// it is only verified once, and the strict mode of the outer root doesn't flag it as unexpected.
+    ///
+    /// With the `skipUnresolvedRoots` option, base directories that can't be resolved on the platform are skipped.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if every root is valid, or `Err(String)` with the error message of the first invalid root.
+    fn verify_all(&self) -> std::result::Result<(), String> {
+        let (directories, custom, app_groups, skip_unresolved) = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+
+            let directories: Vec<_> = roots::ROOTS
+                .into_iter()
+                .filter(|directory| structure_config.root(*directory).is_some())
+                .collect();
+            let custom: Vec<_> = structure_config
+                .custom
+                .iter()
+                .flat_map(|custom| custom.keys().cloned())
+                .collect();
+            // App group containers only exist on iOS, elsewhere their structures are ignored.
+            let app_groups: Vec<_> = structure_config
+                .app_groups
+                .iter()
+                .filter(|_| cfg!(target_os = "ios"))
+                .flat_map(|app_groups| app_groups.keys().cloned())
+                .collect();
+            let skip_unresolved = structure_config.skip_unresolved_roots == Some(true);
+            (directories, custom, app_groups, skip_unresolved)
+        };
 
-        match &structure_config.desktop {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `desktop` not found".to_string()),
+        for directory in directories {
+            if skip_unresolved && roots::skip_unresolved(self, directory) {
+                continue;
+            }
+            self.verify(directory)?;
         }
+        for name in custom {
+            self.verify_custom(&name)?;
+        }
+        for group in app_groups {
+            self.verify(RootSelector::AppGroup(group))?;
+        }
+
+        Ok(())
     }
 
-    /// Verifies the structure of the `document` directory based on the provided structure configuration.
-    fn verify_document(&self) -> std::result::Result<(), String> {
-        let path = match self.path().document_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve document path: {:?}", e)),
-        };
+    /// Creates every declared directory and file of a configured root that doesn't exist, whatever their `repair`
+    /// option, e.g. on first launch. Files are created with their default `content` (empty without one) or downloaded
+    /// from their `url`, and items whose `when` condition doesn't hold are skipped.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be scaffolded, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns the created entries, or `Err(String)` with an error message if an entry can't be created.
+    fn scaffold(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<ScaffoldSummary, String> {
+        let root = root.into();
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.document {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `document` not found".to_string()),
-        }
+        let (path, structure_item) = roots::select(
+            &structure_config,
+            &root,
+            |directory| roots::resolve(self, directory),
+            |group| roots::resolve_app_group(self, group),
+        )?;
+        let nested_roots = roots::nested_in(self, &structure_config, &path);
+        let summary = scaffold::Scaffolder::new(self, &path, structure_item)
+            .with_nested_roots(nested_roots)
+            .scaffold(root.name(), &path, structure_item);
+        self.state::<cache::ResultCache>()
+            .invalidate(Some(root.name()));
+        let summary = summary?;
+
+        events::emit(self, events::SCAFFOLD_FINISHED, summary.clone());
+        Ok(summary)
     }
 
-    /// Verifies the structure of the `download` directory based on the provided structure configuration.
-    fn verify_download(&self) -> std::result::Result<(), String> {
-        let path = match self.path().download_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve download path: {:?}", e)),
+    /// Scaffolds every configured root like `scaffold`, built-in and custom (and, on iOS, the app groups).
+    ///
+    /// # Returns
+    ///
+    /// Returns the entries created in each root, or `Err(String)` with the error message of the first root that
+    /// can't be scaffolded.
+    fn scaffold_all(&self) -> std::result::Result<Vec<ScaffoldSummary>, String> {
+        let roots: Vec<RootSelector> = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+
+            let directories = roots::ROOTS
+                .into_iter()
+                .filter(|directory| structure_config.root(*directory).is_some())
+                .map(RootSelector::Base);
+            let custom = structure_config
+                .custom
+                .iter()
+                .flat_map(|custom| custom.keys().cloned())
+                .map(RootSelector::Custom);
+            let app_groups = structure_config
+                .app_groups
+                .iter()
+                .filter(|_| cfg!(target_os = "ios"))
+                .flat_map(|app_groups| app_groups.keys().cloned())
+                .map(RootSelector::AppGroup);
+            directories.chain(custom).chain(app_groups).collect()
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.download {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `download` not found".to_string()),
-        }
+        roots.into_iter().map(|root| self.scaffold(root)).collect()
     }
 
-    /// Verifies the structure of the `executable` directory based on the provided structure configuration.
-    fn verify_executable(&self) -> std::result::Result<(), String> {
-        let path = match self.path().executable_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve executable path: {:?}", e)),
-        };
+    /// Empties the declared structure of a configured root while keeping its declared directories, e.g. behind a
+    /// "Clear cache" button.
+    ///
+    /// Declared files and, below the declared directories, every entry are deleted. Undeclared entries at the top of
+    /// an app-scoped root are deleted too, while those of a shared root (e.g. `temp` or `document`) belong to other
+    /// software and are left alone. Nested roots are skipped and the repair scope applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be cleaned, a base directory or the name of a custom root.
+    /// * `dry_run` - If set, the entries to be deleted are only listed, nothing is deleted.
+    ///
+    /// # Returns
+    ///
+    /// Returns the deleted entries, or `Err(String)` with an error message if an entry can't be deleted.
+    fn clean(
+        &self,
+        root: impl Into<RootSelector>,
+        dry_run: bool,
+    ) -> std::result::Result<CleanSummary, String> {
+        let root = root.into();
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.executable {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `executable` not found".to_string()),
+        let (path, structure_item) = roots::select(
+            &structure_config,
+            &root,
+            |directory| roots::resolve(self, directory),
+            |group| roots::resolve_app_group(self, group),
+        )?;
+        let nested_roots = roots::nested_in(self, &structure_config, &path);
+        let owned =
+            matches!(root, RootSelector::Base(directory) if ownership::is_app_scoped(directory));
+
+        let repair_scope = self.state::<repair_scope::RepairScope>();
+        let summary = clean::Cleaner::new(&repair_scope, &path, structure_item)
+            .with_nested_roots(nested_roots)
+            .with_dry_run(dry_run)
+            .clean(root.name(), &path, structure_item, owned);
+        if !dry_run {
+            self.state::<cache::ResultCache>()
+                .invalidate(Some(root.name()));
         }
+        summary
     }
 
-    /// Verifies the structure of the `font` directory based on the provided structure configuration.
-    fn verify_font(&self) -> std::result::Result<(), String> {
-        let path = match self.path().font_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve font path: {:?}", e)),
+    /// Cleans the configured cache roots like `clean`: `appCache`, and the declared directories of `cache`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dry_run` - If set, the entries to be deleted are only listed, nothing is deleted.
+    ///
+    /// # Returns
+    ///
+    /// Returns the entries deleted in each root, or `Err(String)` with the error message of the first root that
+    /// can't be cleaned.
+    fn clean_cache(&self, dry_run: bool) -> std::result::Result<Vec<CleanSummary>, String> {
+        let directories = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            clean::cache_roots(&structure_config)
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.font {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `font` not found".to_string()),
-        }
+        directories
+            .into_iter()
+            .map(|directory| self.clean(directory, dry_run))
+            .collect()
     }
 
-    /// Verifies the structure of the `home` directory based on the provided structure configuration.
-    fn verify_home(&self) -> std::result::Result<(), String> {
-        let path = match self.path().home_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve home path: {:?}", e)),
+    /// Cleans the declared directories of the `temp` root like `clean`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dry_run` - If set, the entries to be deleted are only listed, nothing is deleted.
+    ///
+    /// # Returns
+    ///
+    /// Returns the deleted entries, `None` if `temp` isn't configured, or `Err(String)` with an error message if an
+    /// entry can't be deleted.
+    fn clean_temp(&self, dry_run: bool) -> std::result::Result<Option<CleanSummary>, String> {
+        let configured = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            structure_config.root(BaseDirectory::Temp).is_some()
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
-
-        match &structure_config.home {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `home` not found".to_string()),
+        match configured {
+            true => self.clean(BaseDirectory::Temp, dry_run).map(Some),
+            false => Ok(None),
         }
     }
 
-    /// Verifies the structure of the `local_data` directory based on the provided structure configuration.
-    fn verify_local_data(&self) -> std::result::Result<(), String> {
-        let path = match self.path().local_data_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve local data path: {:?}", e)),
+    /// Upgrades the layout on disk from the version recorded in `appLocalData/structure-manager/structure-version`
+    /// to the `version` of the structure configuration, applying its `migrations` in order, e.g. before
+    /// `verify_all` on every launch instead of failing the verification of an old layout.
+    ///
+    /// Without a recorded version, the layout is at version `0` if a migration from `0` is declared (an install
+    /// predating versioned structures), and at the current version otherwise (a new install). The version reached is
+    /// recorded after each migration, so an interrupted migration resumes where it stopped.
+    ///
+    /// # Returns
+    ///
+    /// Returns the versions before and after and the migrations applied, or `Err(String)` if there is no migration
+    /// path to the current version, the layout on disk is newer, or an action fails.
+    fn migrate(&self) -> std::result::Result<MigrationSummary, String> {
+        let structure_config = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            structure_config.clone()
         };
 
+        let summary = migrations::migrate(self, &structure_config);
+        self.state::<cache::ResultCache>().invalidate(None);
+        summary
+    }
+
+    /// Returns the version of the structure recorded in the `.structure-version` marker of a root, e.g. to check
+    /// whether it needs a migration before reading it.
+    ///
+    /// The plugin maintains markers in app-scoped roots, custom roots and app groups when the configuration has a
+    /// `version`: a root is marked when it first verifies without one, and every marker is updated by `migrate`.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns the recorded version, `None` if the root has no marker, or `Err(String)` if the root isn't configured
+    /// or its marker can't be read.
+    fn structure_version(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<Option<u32>, String> {
+        let root = root.into();
+
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.local_data {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `localData` not found".to_string()),
-        }
+        let (path, _) = roots::select(
+            &structure_config,
+            &root,
+            |directory| roots::resolve(self, directory),
+            |group| roots::resolve_app_group(self, group),
+        )?;
+        migrations::root_version(&path)
     }
 
-    /// Verifies the structure of the `picture` directory based on the provided structure configuration.
-    fn verify_picture(&self) -> std::result::Result<(), String> {
-        let path = match self.path().picture_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve picture path: {:?}", e)),
-        };
+    /// Generates the integrity manifest of a root, recording the relative path, size and SHA-256 hash of each of its
+    /// declared files that exists, and stores it in the root's `.structure-manifest.json` file, e.g. right after
+    /// installing or updating the app. A later manifest verification then detects corrupted or tampered files, and the
+    /// manifest helps support diagnose an install.
+    ///
+    /// The files of the other configured roots nested inside the root aren't recorded, and the repair scope applies
+    /// to the manifest file.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns the manifest, or `Err(String)` if a file can't be hashed or the manifest can't be written.
+    fn generate_manifest(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<Manifest, String> {
+        let root = root.into();
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.picture {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `picture` not found".to_string()),
-        }
+        let (path, structure_item) = roots::select(
+            &structure_config,
+            &root,
+            |directory| roots::resolve(self, directory),
+            |group| roots::resolve_app_group(self, group),
+        )?;
+        let nested_roots = roots::nested_in(self, &structure_config, &path);
+        manifest::generate(
+            &self.state::<repair_scope::RepairScope>(),
+            &self.state::<manifest::ManifestKeys>(),
+            root.name(),
+            &path,
+            structure_item,
+            &nested_roots,
+        )
     }
 
-    /// Verifies the structure of the `public` directory based on the provided structure configuration.
-    fn verify_public(&self) -> std::result::Result<(), String> {
-        let path = match self.path().public_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve public path: {:?}", e)),
-        };
+    /// Compares the declared files of a root with the manifest stored by `generate_manifest`, reporting the files
+    /// added, removed and modified since, e.g. to detect a corrupted or tampered install that existence checks miss.
+    ///
+    /// When files of a `protected` root (e.g. `resource`) were modified or removed, a
+    /// `structure-manager://tamper-detected` event is emitted, so the app can refuse to proceed or prompt for a
+    /// reinstall.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns the differences, empty if the files match the manifest, or `Err(String)` if the root has no manifest,
+    /// it can't be read or a file can't be hashed.
+    fn verify_manifest(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<ManifestDiff, String> {
+        let root = root.into();
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.public {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `public` not found".to_string()),
+        let (path, structure_item) = roots::select(
+            &structure_config,
+            &root,
+            |directory| roots::resolve(self, directory),
+            |group| roots::resolve_app_group(self, group),
+        )?;
+        let nested_roots = roots::nested_in(self, &structure_config, &path);
+        let diff = manifest::verify(
+            &self.state::<manifest::ManifestKeys>(),
+            root.name(),
+            &path,
+            structure_item,
+            &nested_roots,
+        )?;
+
+        let protected = structure_item
+            .options
+            .as_ref()
+            .and_then(|options| options.protected)
+            .unwrap_or(matches!(root, RootSelector::Base(BaseDirectory::Resource)));
+        if protected && (!diff.modified.is_empty() || !diff.removed.is_empty()) {
+            log::warn!(
+                "Files of the protected root {} were tampered with: {:?} modified, {:?} removed",
+                root.name(),
+                diff.modified,
+                diff.removed
+            );
+            events::emit(
+                self,
+                events::TAMPER_DETECTED,
+                TamperDetected {
+                    root: root.name().to_string(),
+                    path,
+                    modified: diff.modified.clone(),
+                    removed: diff.removed.clone(),
+                },
+            );
         }
+
+        Ok(diff)
     }
 
-    /// Verifies the structure of the `resource` directory based on the provided structure configuration.
-    fn verify_resource(&self) -> std::result::Result<(), String> {
-        let path = match self.path().resource_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve resource path: {:?}", e)),
-        };
+    /// Returns the statistics of the last verification of each root since the app started, e.g. to chart startup
+    /// health over time.
+    ///
+    /// Roots are keyed like the `root` of the verification events: the configuration field of a base directory,
+    /// the name of a custom root, or the verified path.
+    fn last_verification_stats(&self) -> BTreeMap<String, VerificationStats> {
+        self.state::<stats::Stats>().0.lock().unwrap().clone()
+    }
+
+    /// Waits until the verification of the roots on startup is done, background verifications included, e.g. to keep
+    /// a splash screen until the structure is confirmed. Returns at once if it is already done.
+    ///
+    /// # Returns
+    ///
+    /// Returns the roots verified on startup and the error message of the invalid ones, also sent with the
+    /// `structure-manager://ready` event.
+    fn wait_until_ready(&self) -> StartupVerification {
+        self.state::<startup::Readiness>().wait()
+    }
+
+    /// Forgets the cached verification results of a root, or of every root if `None`, so its next `verify` walks it
+    /// again, e.g. after the app changed files the structure covers. Repairs, scaffolding, cleaning and migrations
+    /// invalidate the results they affect themselves.
+    ///
+    /// Only relevant with `Builder::result_cache`.
+    fn invalidate_verification_cache(&self, root: Option<RootSelector>) {
+        self.state::<cache::ResultCache>()
+            .invalidate(root.as_ref().map(RootSelector::name));
+    }
+
+    /// Returns the most recent verifications of the rolling history, persisted in
+    /// `appLocalData/structure-manager/history.jsonl`, e.g. to analyze the drift of a structure over time.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - Only returns the verifications of this root, keyed like the `root` of the verification events.
+    ///   All roots if `None`.
+    /// * `limit` - The maximum number of verifications returned.
+    ///
+    /// # Returns
+    ///
+    /// Returns the verifications, newest first, or `Err(String)` if the history can't be read.
+    fn history(
+        &self,
+        root: Option<&str>,
+        limit: usize,
+    ) -> std::result::Result<Vec<VerificationRecord>, String> {
+        self.state::<history::History>().query(self, root, limit)
+    }
+
+    /// Returns the last verification of a root, persisted across restarts, e.g. to show "Structure last verified
+    /// 2 days ago — OK".
+    ///
+    /// Requires the `store` feature, and `tauri-plugin-store` to be registered by the app.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns the last verification, `None` if the root was never verified, or `Err(String)` if the store can't
+    /// be read.
+    #[cfg(feature = "store")]
+    fn last_verification(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<Option<LastVerification>, String> {
+        store::last(self, root.into().name())
+    }
+
+    /// Watches a root, verifying it right away and again whenever its contents change, and sending the violations,
+    /// warnings and info of each verification to `on_event`, e.g. so a settings page can display the health of a
+    /// folder live. Changes are debounced, those of the other configured roots nested inside it and those made by
+    /// the verification itself (e.g. its repairs) don't trigger a verification. A previous watch of the root is
+    /// replaced.
+    ///
+    /// Requires the `watch` feature.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root, a base directory or the name of a custom root.
+    /// * `on_event` - Called with the violations of each verification, from a background thread.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` once the root is watched, or `Err(String)` if it can't be resolved or watched.
+    #[cfg(feature = "watch")]
+    fn watch(
+        &self,
+        root: impl Into<RootSelector>,
+        on_event: impl Fn(VerificationEvent) + Send + 'static,
+    ) -> std::result::Result<(), String> {
+        let root = root.into();
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.resource {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `resource` not found".to_string()),
-        }
+        let (path, _) = roots::select(
+            &structure_config,
+            &root,
+            |directory| roots::resolve(self, directory),
+            |group| roots::resolve_app_group(self, group),
+        )?;
+        let nested_roots = roots::nested_in(self, &structure_config, &path);
+        drop(structure_config);
+
+        self.state::<watch::Watchers>().watch(
+            self.app_handle().clone(),
+            root,
+            &path,
+            nested_roots,
+            on_event,
+        )
     }
 
-    /// Verifies the structure of the `runtime` directory based on the provided structure configuration.
-    fn verify_runtime(&self) -> std::result::Result<(), String> {
-        let path = match self.path().runtime_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve runtime path: {:?}", e)),
+    /// Stops watching a root watched with `watch`.
+    ///
+    /// Requires the `watch` feature.
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the root was watched.
+    #[cfg(feature = "watch")]
+    fn unwatch(&self, root: impl Into<RootSelector>) -> bool {
+        self.state::<watch::Watchers>().unwatch(root.into().name())
+    }
+
+    /// Verifies the structure of a configured root like `verify`, collecting its results into a report.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be verified, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns the report, a failed verification is reported with its error message.
+    fn verification_report(&self, root: impl Into<RootSelector>) -> VerificationReport {
+        let root = root.into();
+        let path = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            roots::select(
+                &structure_config,
+                &root,
+                |directory| roots::resolve(self, directory),
+                |group| roots::resolve_app_group(self, group),
+            )
+            .map(|(path, _)| path)
+            .ok()
         };
 
+        let collector = report::Collector::default();
+        let result = self.verify_streamed(root.clone(), &|event| collector.record(event));
+        collector.finish(root.name(), path, result)
+    }
+
+    /// Verifies the structure of a configured root and writes the report to a file, e.g. to attach it to a support ticket.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be verified, a base directory or the name of a custom root.
+    /// * `path` - The path of the report file, replaced if it exists.
+    /// * `format` - The format of the report file.
+    ///
+    /// # Returns
+    ///
+    /// Returns the written report, or `Err(String)` if the file can't be written.
+    fn write_report(
+        &self,
+        root: impl Into<RootSelector>,
+        path: impl AsRef<Path>,
+        format: ReportFormat,
+    ) -> std::result::Result<VerificationReport, String> {
+        let report = self.verification_report(root);
+        report.write(path.as_ref(), format)?;
+        Ok(report)
+    }
+
+    /// Plans the repair of a configured root without touching the disk.
+    ///
+    /// The plan lists, in order, the directories and files a verification would create and the entries it would
+    /// delete, each with the precondition that must still hold when it is applied.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be planned, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns the repair plan, or `Err(String)` with an error message if the directory can't be read.
+    fn repair_plan(
+        &self,
+        root: impl Into<RootSelector>,
+    ) -> std::result::Result<RepairPlan, String> {
+        let root = root.into();
+
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.runtime {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `runtime` not found".to_string()),
-        }
+        let (path, structure_item) = roots::select(
+            &structure_config,
+            &root,
+            |directory| roots::resolve(self, directory),
+            |group| roots::resolve_app_group(self, group),
+        )?;
+        let nested_roots = roots::nested_in(self, &structure_config, &path);
+        let operations = plan::Planner::new(self, &path, structure_item)
+            .with_nested_roots(nested_roots)
+            .plan(root.name(), &path, structure_item)?;
+
+        Ok(RepairPlan {
+            schema_version: SCHEMA_VERSION,
+            root: root.name().to_string(),
+            operations,
+        })
     }
 
-    /// Verifies the structure of the `temp` directory based on the provided structure configuration.
-    fn verify_temp(&self) -> std::result::Result<(), String> {
-        let path = match self.path().temp_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve temp path: {:?}", e)),
+    /// Plans the repair of any directory against a structure item provided at call time, without touching the disk.
+    ///
+    /// This is the dry run of `verify_path`, the `$ref`s of the item are resolved the same way.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path to the directory to be planned.
+    /// * `structure_item` - The expected structure of the directory.
+    ///
+    /// # Returns
+    ///
+    /// Returns the repair plan, or `Err(String)` with an error message if the directory can't be read.
+    fn plan_path(
+        &self,
+        path: impl AsRef<Path>,
+        structure_item: impl Into<StructureItem>,
+    ) -> std::result::Result<RepairPlan, String> {
+        let path = path.as_ref();
+        let structure_item = structure_item.into();
+        let structure_item = {
+            let state_mutex = self.state::<Mutex<StructureConfig>>();
+            let structure_config = state_mutex.lock().unwrap();
+            structure_item.resolve(
+                structure_config
+                    .definitions
+                    .as_ref()
+                    .unwrap_or(&BTreeMap::new()),
+            )?
         };
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
+        let root = path.display().to_string();
+        let operations =
+            plan::Planner::new(self, path, &structure_item).plan(&root, path, &structure_item)?;
 
-        match &structure_config.temp {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `temp` not found".to_string()),
+        Ok(RepairPlan {
+            schema_version: SCHEMA_VERSION,
+            root,
+            operations,
+        })
+    }
+
+    /// Applies a repair plan computed by `repair_plan`, possibly stored or reviewed since.
+    ///
+    /// The precondition of each operation is checked right before applying it, as the disk may have changed since
+    /// the plan was computed. Operations whose target already is in the planned state are skipped, operations whose
+    /// precondition no longer holds fail, and neither stops the following operations.
+    ///
+    /// # Arguments
+    ///
+    /// * `plan` - The repair plan to apply.
+    /// * `dry_run` - If set, only the preconditions are checked and nothing is applied.
+    ///
+    /// # Returns
+    ///
+    /// Returns the reconciliation report, with the outcome of every operation of the plan.
+    fn apply_repair(&self, plan: &RepairPlan, dry_run: bool) -> ReconciliationReport {
+        let report = plan::apply(self, plan, dry_run);
+        if !dry_run {
+            self.state::<cache::ResultCache>().invalidate(None);
         }
+        report
     }
 
-    /// Verifies the structure of the `template` directory based on the provided structure configuration.
-    fn verify_template(&self) -> std::result::Result<(), String> {
-        let path = match self.path().template_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve template path: {:?}", e)),
-        };
+    /// Builds UI-ready tree nodes of a directory of a base directory, combining its contents with its declared structure.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - The base directory.
+    /// * `relative` - The path of the directory to expand, relative to the base directory. Defaults to the base directory itself.
+    /// * `options` - The depth to expand and whether to include statuses.
+    ///
+    /// # Returns
+    ///
+    /// Returns the nodes of the directory sorted by name, or `Err(String)` if the directory can't be read.
+    fn tree_json(
+        &self,
+        directory: BaseDirectory,
+        relative: Option<PathBuf>,
+        options: &TreeOptions,
+    ) -> std::result::Result<Vec<TreeNode>, String> {
+        let relative = relative.unwrap_or_default();
+        tree::check_relative(&relative)?;
+        let path = roots::resolve(self, directory)?.join(&relative);
 
         let state_mutex = self.state::<Mutex<StructureConfig>>();
         let structure_config = state_mutex.lock().unwrap();
 
-        match &structure_config.template {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `template` not found".to_string()),
-        }
+        let structure_item = structure_config
+            .root(directory)
+            .and_then(|root| tree::declared_at(root, &relative));
+
+        tree::build(&path, structure_item, options)
+    }
+
+    /// Verifies the structure of the `appCache` directory based on the provided structure configuration.
+    fn verify_app_cache(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::AppCache)
+    }
+
+    /// Verifies the structure of the `appConfig` directory based on the provided structure configuration.
+    fn verify_app_config(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::AppConfig)
+    }
+
+    /// Verifies the structure of the `app_data` directory based on the provided structure configuration.
+    fn verify_app_data(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::AppData)
+    }
+
+    /// Verifies the structure of the `app_local_data` directory based on the provided structure configuration.
+    fn verify_app_local_data(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::AppLocalData)
+    }
+
+    /// Verifies the structure of the `app_log` directory based on the provided structure configuration.
+    fn verify_app_log(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::AppLog)
+    }
+
+    /// Verifies the structure of the `audio` directory based on the provided structure configuration.
+    fn verify_audio(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Audio)
+    }
+
+    /// Verifies the structure of the `cache` directory based on the provided structure configuration.
+    fn verify_cache(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Cache)
+    }
+
+    /// Verifies the structure of the `config` directory based on the provided structure configuration.
+    fn verify_config(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Config)
+    }
+
+    /// Verifies the structure of the `data` directory based on the provided structure configuration.
+    fn verify_data(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Data)
+    }
+
+    /// Verifies the structure of the `desktop` directory based on the provided structure configuration.
+    fn verify_desktop(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Desktop)
+    }
+
+    /// Verifies the structure of the `document` directory based on the provided structure configuration.
+    fn verify_document(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Document)
+    }
+
+    /// Verifies the structure of the `download` directory based on the provided structure configuration.
+    fn verify_download(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Download)
+    }
+
+    /// Verifies the structure of the `executable` directory based on the provided structure configuration.
+    fn verify_executable(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Executable)
+    }
+
+    /// Verifies the structure of the `font` directory based on the provided structure configuration.
+    fn verify_font(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Font)
+    }
+
+    /// Verifies the structure of the `home` directory based on the provided structure configuration.
+    fn verify_home(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Home)
+    }
+
+    /// Verifies the structure of the `local_data` directory based on the provided structure configuration.
+    fn verify_local_data(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::LocalData)
+    }
+
+    /// Verifies the structure of the `picture` directory based on the provided structure configuration.
+    fn verify_picture(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Picture)
+    }
+
+    /// Verifies the structure of the `public` directory based on the provided structure configuration.
+    fn verify_public(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Public)
+    }
+
+    /// Verifies the structure of the `resource` directory based on the provided structure configuration.
+    fn verify_resource(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Resource)
+    }
+
+    /// Verifies the structure of the `runtime` directory based on the provided structure configuration.
+    fn verify_runtime(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Runtime)
+    }
+
+    /// Verifies the structure of the `temp` directory based on the provided structure configuration.
+    fn verify_temp(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Temp)
+    }
+
+    /// Verifies the structure of the `template` directory based on the provided structure configuration.
+    fn verify_template(&self) -> std::result::Result<(), String> {
+        self.verify(BaseDirectory::Template)
     }
 
     /// Verifies the structure of the `video` directory based on the provided structure configuration.
     fn verify_video(&self) -> std::result::Result<(), String> {
-        let path = match self.path().video_dir() {
-            Ok(path) => path,
-            Err(e) => return Err(format!("Failed to resolve video path: {:?}", e)),
-        };
+        self.verify(BaseDirectory::Video)
+    }
+}
 
-        let state_mutex = self.state::<Mutex<StructureConfig>>();
-        let structure_config = state_mutex.lock().unwrap();
+/// Verifies the structure of a configured root, see `StructureManagerExt::verify_streamed`.
+///
+/// # Arguments
+///
+/// * `repair` - Whether the `repair` options are honored. When they aren't, the root is only verified: it isn't
+///   created, and its version marker isn't written.
+fn verify_root<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    root: &RootSelector,
+    on_event: &dyn Fn(VerificationEvent),
+    repair: bool,
+) -> std::result::Result<(), String> {
+    let state_mutex = manager.state::<Mutex<StructureConfig>>();
+    let structure_config = state_mutex.lock().unwrap();
+
+    let (path, structure_item) = roots::select(
+        &structure_config,
+        root,
+        |directory| roots::resolve(manager, directory),
+        |group| roots::resolve_app_group(manager, group),
+    )?;
+    let nested_roots = roots::nested_in(manager, &structure_config, &path);
+    let exclusive = structure_item
+        .options
+        .as_ref()
+        .and_then(|options| options.exclusive)
+        .unwrap_or(false);
+    let app_scoped =
+        matches!(root, RootSelector::Base(directory) if ownership::is_app_scoped(*directory));
+    if exclusive && !app_scoped {
+        return Err(format!(
+            "Option `exclusive` of `{}` is only supported by app-scoped roots",
+            root.name()
+        ));
+    }
+
+    let options = structure_item.options.as_ref();
+    let walk = RootWalk {
+        nested_roots,
+        ignore: structure_config.ignore.clone().unwrap_or_default(),
+        version: structure_config
+            .version
+            .filter(|_| root.has_version_marker()),
+        create_root: repair
+            && options.and_then(|options| options.repair) == Some(true)
+            && options
+                .and_then(|options| options.create_root)
+                .or(structure_config.create_root)
+                == Some(true),
+        exclusive,
+        repair,
+        timeout: options
+            .and_then(|options| options.timeout)
+            .map(Duration::from_secs),
+    };
+    // The configuration isn't held while the root is walked, which may take long or hang.
+    let structure_item = structure_item.clone();
+    drop(structure_config);
+    let Some(timeout) = walk.timeout else {
+        return walk_root(
+            manager,
+            root.name(),
+            &path,
+            &structure_item,
+            &walk,
+            on_event,
+        );
+    };
+
+    let name = root.name().to_string();
+    let app = manager.app_handle().clone();
+    let worker_path = path.clone();
+    timeout::run(app, &path, timeout, on_event, move |app, on_event| {
+        walk_root(app, &name, &worker_path, &structure_item, &walk, on_event)
+    })
+}
+
+/// How a configured root is walked, resolved from the structure configuration by `verify_root`.
+struct RootWalk {
+    nested_roots: Vec<PathBuf>,
+    ignore: Vec<String>,
+    version: Option<u32>,
+    create_root: bool,
+    exclusive: bool,
+    repair: bool,
+    timeout: Option<Duration>,
+}
 
-        match &structure_config.video {
-            Some(structure_item) => self.dfs_verify(path, structure_item),
-            None => Err("Structure configuration field `video` not found".to_string()),
+/// Walks a configured root, at `path`, see `verify_root`.
+fn walk_root<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    root: &str,
+    path: &Path,
+    structure_item: &StructureItem,
+    walk: &RootWalk,
+    on_event: &dyn Fn(VerificationEvent),
+) -> std::result::Result<(), String> {
+    let mut walker = verify::Walker::new(manager, path, structure_item)
+        .with_nested_roots(walk.nested_roots.clone())
+        .with_ignore(walk.ignore.clone())
+        .with_events(on_event)
+        .with_repair(walk.repair);
+    if let Some(timeout) = walk.timeout {
+        walker = walker.with_timeout(timeout);
+    }
+    walker.run(root, |walker| {
+        if walk.create_root {
+            walker.create_root(path)?;
+        }
+        if let Some(version) = walk.version {
+            walker.verify_version(path, version)?;
         }
+        if walk.exclusive {
+            walker.verify_exclusive(path, structure_item)?;
+        }
+        walker.verify(path.to_path_buf(), structure_item)
+    })?;
+
+    // A root verified without a marker is taken to be at the current version, e.g. on first launch.
+    if let Some(version) = walk
+        .version
+        .filter(|_| walk.repair && long_path::extended(path).is_dir())
+    {
+        migrations::record_root_version(path, version, false);
     }
+    Ok(())
 }
 
-/// Initializes the plugin.
-pub fn init<R: Runtime>() -> TauriPlugin<R, Option<StructureConfig>> {
-    Builder::<R, Option<StructureConfig>>::new("structure-manager")
-        .setup(|app, api| {
-            match api.config() {
-                Some(structure_config) => {
-                    info!(
-                        "Using provided structure configuration\n{:?}",
+/// Builder for the structure-manager plugin.
+#[derive(Default)]
+pub struct Builder {
+    features: HashSet<String>,
+    contracts: BTreeMap<String, StructureContract>,
+    profile: Option<String>,
+    config: Option<StructureConfig>,
+    validators: HashMap<String, Validator>,
+    hooks: hooks::Hooks,
+    translation: messages::Translation,
+    heartbeat: heartbeat::HeartbeatSettings,
+    max_traversal_depth: verify::MaxTraversalDepth,
+    strict_config: bool,
+    repair_scope: Option<FsScope>,
+    history_limit: Option<usize>,
+    result_cache: Option<Duration>,
+    manifest_keys: manifest::ManifestKeys,
+}
+
+impl Builder {
+    /// Creates a new builder with no feature flags.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables a feature flag, structure items whose `when.features` require it will be verified.
+    pub fn feature(mut self, name: impl Into<String>) -> Self {
+        self.features.insert(name.into());
+        self
+    }
+
+    /// Registers a contract describing the layout of an external tool, in addition to the ones of the structure configuration.
+    ///
+    /// Contracts registered here take precedence over configured contracts with the same name.
+    pub fn contract(mut self, name: impl Into<String>, contract: StructureContract) -> Self {
+        self.contracts.insert(name.into(), contract);
+        self
+    }
+
+    /// Uses a structure configuration built in Rust instead of the one from `tauri.conf.json`.
+    pub fn config(mut self, config: StructureConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Selects a profile from `StructureConfig::profiles` to lay over the structure configuration,
+    /// e.g. `"dev"` in development builds. Setup fails if the profile is not declared.
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Registers a validator, run during verification on the files declaring it with `"validator": "<name>"`.
+    ///
+    /// ```
+    /// tauri_plugin_structure_manager::Builder::new().validator("db-check", |path| {
+    ///     let header = std::fs::read(path).map_err(|e| e.to_string())?;
+    ///     match header.starts_with(b"SQLite format 3\0") {
+    ///         true => Ok(()),
+    ///         false => Err("not a SQLite database".to_string()),
+    ///     }
+    /// });
+    /// ```
+    pub fn validator<F>(mut self, name: impl Into<String>, validator: F) -> Self
+    where
+        F: Fn(&Path) -> std::result::Result<(), String> + Send + Sync + 'static,
+    {
+        self.validators.insert(name.into(), Arc::new(validator));
+        self
+    }
+
+    /// Registers a hook called for each declared entry found missing, before it is repaired (if repair is enabled).
+    pub fn on_missing<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path, EntryKind) + Send + Sync + 'static,
+    {
+        self.hooks.on_missing.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a hook called for each entry created or fixed by a repair.
+    pub fn on_repaired<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Path) + Send + Sync + 'static,
+    {
+        self.hooks.on_repaired.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a hook called for each violation that makes a verification fail.
+    pub fn on_violation<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&Violation) + Send + Sync + 'static,
+    {
+        self.hooks.on_violation.push(Box::new(hook));
+        self
+    }
+
+    /// Registers a telemetry sink, receiving the summaries of verifications and the repaired entries, e.g. to forward
+    /// structure health data to an analytics backend.
+    pub fn telemetry(mut self, sink: impl TelemetrySink + 'static) -> Self {
+        self.hooks.telemetry.push(Box::new(sink));
+        self
+    }
+
+    /// Sets the translator of violation messages, called with the key and the parameters of each message.
+    ///
+    /// Messages the translator returns `None` for are kept in English.
+    pub fn translator<F>(mut self, translator: F) -> Self
+    where
+        F: Fn(&str, &BTreeMap<String, String>) -> Option<String> + Send + Sync + 'static,
+    {
+        self.translation = messages::Translation(Some(Box::new(translator)));
+        self
+    }
+
+    /// Sets when `structure-manager://heartbeat` events are emitted: every `interval` once an operation has been
+    /// running for `threshold`. Defaults to every second after two seconds.
+    pub fn heartbeat(mut self, threshold: Duration, interval: Duration) -> Self {
+        self.heartbeat = heartbeat::HeartbeatSettings {
+            threshold,
+            interval,
+        };
+        self
+    }
+
+    /// Sets the number of nested directory levels a verification descends into (256 by default), a hard limit
+    /// guarding against pathological structures: deeper declared directories fail the verification.
+    pub fn max_traversal_depth(mut self, levels: usize) -> Self {
+        self.max_traversal_depth = verify::MaxTraversalDepth(levels);
+        self
+    }
+
+    /// Signs the integrity manifests generated by `generate_manifest` with an Ed25519 secret key (its 32 bytes seed), so
+    /// `verify_manifest` can't be fooled by editing the manifest file. Unless `manifest_verifying_key` is set, the
+    /// signatures are verified with the matching public key.
+    #[cfg(feature = "signing")]
+    pub fn manifest_signing_key(mut self, secret: [u8; 32]) -> Self {
+        self.manifest_keys.signing = Some(secret);
+        self
+    }
+
+    /// Sets the Ed25519 public key `verify_manifest` verifies the signature of manifests with, e.g. when they are
+    /// signed by an installer holding the secret key rather than by the app. Unsigned manifests and invalid signatures
+    /// fail the verification.
+    #[cfg(feature = "signing")]
+    pub fn manifest_verifying_key(mut self, public: [u8; 32]) -> Self {
+        self.manifest_keys.verifying = Some(public);
+        self
+    }
+
+    /// Caches the result of `verify` for `ttl`, keyed by root and structure, so repeated verifications within the
+    /// session (e.g. from several windows) don't walk the same structure again. Disabled by default.
+    ///
+    /// Cached results are invalidated with `invalidate_verification_cache`, and by the plugin's own repairs,
+    /// scaffolding, cleaning and migrations. With the `store` feature, the last verification persisted by a previous
+    /// session is reused too while it is younger than `ttl`.
+    pub fn result_cache(mut self, ttl: Duration) -> Self {
+        self.result_cache = Some(ttl);
+        self
+    }
+
+    /// Rejects unknown keys in the `tauri.conf.json` structure configuration instead of ignoring them,
+    /// so a misspelled key fails the setup of the plugin rather than silently not being verified.
+    pub fn strict_config(mut self, strict: bool) -> Self {
+        self.strict_config = strict;
+        self
+    }
+
+    /// Sets the number of verifications kept in the history (1000 by default), `0` disables the history.
+    pub fn history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = Some(limit);
+        self
+    }
+
+    /// Restricts the locations repairs may create or delete entries in, e.g. with the scope given to
+    /// `tauri-plugin-fs`, so neither the configuration nor the frontend can use repairs to write elsewhere.
+    ///
+    /// The app-scoped base directories (`appData`, `appConfig`, …) are always allowed. Whatever the scope,
+    /// a verification never repairs outside of the verified root, even through a symbolic link or a junction.
+    pub fn repair_scope(mut self, scope: FsScope) -> Self {
+        self.repair_scope = Some(scope);
+        self
+    }
+
+    /// Builds the plugin.
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R, Option<StructureConfig>> {
+        PluginBuilder::<R, Option<StructureConfig>>::new("structure-manager")
+            .invoke_handler(tauri::generate_handler![
+                commands::assert_external_contract,
+                commands::detect_contract,
+                commands::verify_path,
+                commands::verify_named,
+                commands::add_workspace,
+                commands::remove_workspace,
+                commands::workspaces,
+                #[cfg(feature = "archive")]
+                commands::verify_archive,
+                commands::verify,
+                commands::verify_streamed,
+                commands::repair_plan,
+                commands::apply_repair,
+                commands::write_report,
+                commands::last_verification_stats,
+                commands::wait_until_ready,
+                commands::history,
+                commands::structure_version,
+                commands::generate_manifest,
+                commands::verify_manifest,
+                commands::clean_cache,
+                commands::clean_temp,
+                commands::invalidate_verification_cache,
+                #[cfg(feature = "store")]
+                commands::last_verification,
+                #[cfg(feature = "watch")]
+                commands::watch,
+                #[cfg(feature = "watch")]
+                commands::unwatch,
+                commands::tree_json
+            ])
+            .setup(move |app, api| {
+                if self.strict_config && self.config.is_none() {
+                    if let Some(value) = app
+                        .config()
+                        .plugins
+                        .0
+                        .get("structure-manager")
+                        .filter(|value| !value.is_null())
+                    {
+                        build::parse_strict(value.clone(), "plugins.structure-manager")?;
+                    }
+                }
+
+                let mut structure_config = match (self.config, api.config()) {
+                    (Some(structure_config), _) => {
+                        info!(
+                            "Using structure configuration provided at init\n{:?}",
+                            structure_config
+                        );
                         structure_config
+                    }
+                    (None, Some(structure_config)) => {
+                        info!(
+                            "Using provided structure configuration\n{:?}",
+                            structure_config
+                        );
+                        structure_config.clone()
+                    }
+                    (None, None) => {
+                        warn!("Using default structure configuration");
+                        StructureConfig::default()
+                    }
+                };
+                if !self.contracts.is_empty() {
+                    structure_config
+                        .contracts
+                        .get_or_insert_with(BTreeMap::new)
+                        .extend(self.contracts);
+                }
+                if let Some(profile) = &self.profile {
+                    info!("Using structure profile `{}`", profile);
+                    structure_config.apply_profile(profile)?;
+                }
+                structure_config.resolve_refs()?;
+                for diagnostic in structure_config.name_diagnostics() {
+                    warn!(
+                        "Structure entry name {:?} is problematic ({:?}) on: {}",
+                        diagnostic.location,
+                        diagnostic.issue,
+                        diagnostic.platforms.join(", ")
                     );
-                    app.manage(Mutex::new(structure_config.clone()))
                 }
-                None => {
-                    warn!("Using default structure configuration");
-                    app.manage(Mutex::new(StructureConfig::default()))
+                app.manage(workspaces::Workspaces::load(app, &mut structure_config));
+                app.manage(Mutex::new(structure_config));
+                app.manage(conditions::Features(self.features));
+                app.manage(conditions::Conditions::default());
+                app.manage(validators::Validators(self.validators));
+                app.manage(self.hooks);
+                app.manage(self.translation);
+                app.manage(self.heartbeat);
+                app.manage(self.max_traversal_depth);
+                app.manage(self.manifest_keys);
+                #[cfg(feature = "watch")]
+                app.manage(watch::Watchers::default());
+                app.manage(
+                    self.result_cache
+                        .map_or_else(cache::ResultCache::default, cache::ResultCache::new),
+                );
+                app.manage(stats::Stats::default());
+                app.manage(timeout::Abandoned::default());
+                app.manage(startup::Readiness::default());
+                app.manage(
+                    self.history_limit
+                        .map_or_else(history::History::default, history::History::new),
+                );
+                app.manage(repair_scope::RepairScope::new(
+                    app,
+                    self.repair_scope.as_ref(),
+                )?);
+
+                #[cfg(mobile)]
+                let structure_manager = mobile::init(app, api)?;
+                #[cfg(desktop)]
+                let structure_manager = desktop::init(app, api)?;
+                app.manage(structure_manager);
+
+                if let Err(e) = startup::verify(app) {
+                    error!("Aborting the launch, the structure is invalid: {}", e);
+                    return Err(format!(
+                        "The structure of the app's directories is invalid and couldn't be repaired: {}",
+                        e
+                    )
+                    .into());
                 }
-            };
 
-            #[cfg(mobile)]
-            let structure_manager = mobile::init(app, api)?;
-            #[cfg(desktop)]
-            let structure_manager = desktop::init(app, api)?;
-            app.manage(structure_manager);
+                Ok(())
+            })
+            .build()
+    }
+}
 
-            Ok(())
-        })
-        .build()
+/// Initializes the plugin.
+pub fn init<R: Runtime>() -> TauriPlugin<R, Option<StructureConfig>> {
+    Builder::new().build()
+}
+
+/// Initializes the plugin with a structure configuration built in Rust, `tauri.conf.json` is ignored.
+pub fn init_with_config<R: Runtime>(
+    config: StructureConfig,
+) -> TauriPlugin<R, Option<StructureConfig>> {
+    Builder::new().config(config).build()
 }
@@ -0,0 +1,66 @@
+use std::{borrow::Cow, path::Path};
+
+/// Returns the form of a path passed to the filesystem APIs.
+///
+/// On Windows, absolute paths are converted to their extended-length form (`\\?\C:\...` or `\\?\UNC\...`), which
+/// isn't limited to `MAX_PATH` (260 characters), so deep structures can be verified and repaired. Only the system
+/// calls see the converted path, messages and events report the path as configured.
+#[cfg(windows)]
+pub(crate) fn extended(path: &Path) -> Cow<'_, Path> {
+    use std::{
+        ffi::OsString,
+        path::{Component, Prefix},
+    };
+
+    let mut components = path.components();
+    let mut extended = match components.next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(_) => {
+                let mut extended = OsString::from(r"\\?\");
+                extended.push(prefix.as_os_str());
+                extended
+            }
+            Prefix::UNC(server, share) => {
+                let mut extended = OsString::from(r"\\?\UNC\");
+                extended.push(server);
+                extended.push(r"\");
+                extended.push(share);
+                extended
+            }
+            // Verbatim paths already are extended, device paths are left as is.
+            _ => return Cow::Borrowed(path),
+        },
+        _ => return Cow::Borrowed(path),
+    };
+    // `C:foo` is relative to the current directory of the drive.
+    if components.next() != Some(Component::RootDir) {
+        return Cow::Borrowed(path);
+    }
+
+    // Extended-length paths aren't normalized by Windows, `.` and `..` are resolved here.
+    let mut names = Vec::new();
+    for component in components {
+        match component {
+            Component::Normal(name) => names.push(name),
+            Component::ParentDir => {
+                names.pop();
+            }
+            _ => {}
+        }
+    }
+    extended.push(r"\");
+    for (index, name) in names.into_iter().enumerate() {
+        if index > 0 {
+            extended.push(r"\");
+        }
+        extended.push(name);
+    }
+
+    Cow::Owned(extended.into())
+}
+
+/// Returns the form of a path passed to the filesystem APIs, the path itself outside of Windows.
+#[cfg(not(windows))]
+pub(crate) fn extended(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
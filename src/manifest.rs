@@ -0,0 +1,298 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    hash::sha256_file, long_path::extended, models::*, normalization::locate,
+    repair_scope::RepairScope, tuning::ResolvedIoTuning,
+};
+
+/// The file recording the integrity manifest of a root, see `StructureManagerExt::generate_manifest`.
+pub const MANIFEST_FILE: &str = ".structure-manifest.json";
+
+/// The file recording the signature of the manifest of a root, see `Builder::manifest_signing_key`.
+pub const SIGNATURE_FILE: &str = ".structure-manifest.sig";
+
+/// The Ed25519 keys signing the generated manifests and verifying the signature of the stored ones, see
+/// `Builder::manifest_signing_key` and `Builder::manifest_verifying_key`.
+#[derive(Default)]
+pub(crate) struct ManifestKeys {
+    #[cfg(feature = "signing")]
+    pub(crate) signing: Option<[u8; 32]>,
+    #[cfg(feature = "signing")]
+    pub(crate) verifying: Option<[u8; 32]>,
+}
+
+impl ManifestKeys {
+    /// Returns the base64 signature of the contents of a manifest, or `None` without a signing key.
+    #[cfg(feature = "signing")]
+    fn sign(&self, json: &[u8]) -> Option<String> {
+        use base64::Engine;
+        use ed25519_dalek::{Signer, SigningKey};
+
+        let signing_key = SigningKey::from_bytes(self.signing.as_ref()?);
+        Some(base64::engine::general_purpose::STANDARD.encode(signing_key.sign(json).to_bytes()))
+    }
+
+    /// Returns the base64 signature of the contents of a manifest, or `None` without a signing key.
+    #[cfg(not(feature = "signing"))]
+    fn sign(&self, _json: &[u8]) -> Option<String> {
+        None
+    }
+
+    /// Returns the key verifying the signature of manifests: the configured verifying key, or else the one of the
+    /// signing key.
+    #[cfg(feature = "signing")]
+    fn verifying_key(&self) -> std::result::Result<Option<ed25519_dalek::VerifyingKey>, String> {
+        use ed25519_dalek::{SigningKey, VerifyingKey};
+
+        match (&self.verifying, &self.signing) {
+            (Some(verifying), _) => VerifyingKey::from_bytes(verifying)
+                .map(Some)
+                .map_err(|e| format!("Invalid manifest verifying key: {}", e)),
+            (None, Some(signing)) => Ok(Some(SigningKey::from_bytes(signing).verifying_key())),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Verifies the signature of the contents of a manifest, stored in the root at `path`, when a verifying key is
+    /// configured: an unsigned manifest or an invalid signature isn't trusted.
+    #[cfg(feature = "signing")]
+    fn check(&self, path: &Path, json: &[u8]) -> std::result::Result<(), String> {
+        use base64::Engine;
+        use ed25519_dalek::Signature;
+
+        let Some(verifying_key) = self.verifying_key()? else {
+            return Ok(());
+        };
+        let signature_path = path.join(SIGNATURE_FILE);
+        let encoded = match std::fs::read_to_string(extended(&signature_path)) {
+            Ok(encoded) => encoded,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(format!("Unsigned manifest: {:?}", path.join(MANIFEST_FILE)))
+            }
+            Err(e) => {
+                return Err(format!(
+                    "Failed to read {:?}, error: {:?}",
+                    signature_path, e
+                ))
+            }
+        };
+        let signature = base64::engine::general_purpose::STANDARD
+            .decode(encoded.trim())
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or_else(|| format!("Invalid manifest signature: {:?}", signature_path))?;
+        verifying_key
+            .verify_strict(json, &signature)
+            .map_err(|_| format!("Invalid manifest signature: {:?}", signature_path))
+    }
+
+    /// Verifies the signature of the contents of a manifest, stored in the root at `path`, when a verifying key is
+    /// configured: an unsigned manifest or an invalid signature isn't trusted.
+    #[cfg(not(feature = "signing"))]
+    fn check(&self, _path: &Path, _json: &[u8]) -> std::result::Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Generates the integrity manifest of a root, at `path`, and stores it in its manifest file.
+///
+/// Every declared file that exists is recorded, with the declared files of the declared directories below it,
+/// leaving out the other configured roots nested inside it. With a signing key, the manifest is signed in its
+/// signature file, otherwise a stale signature file is removed.
+///
+/// # Returns
+///
+/// Returns the manifest, or `Err(String)` if a file can't be hashed or the manifest can't be written.
+pub(crate) fn generate(
+    repair_scope: &RepairScope,
+    keys: &ManifestKeys,
+    root: &str,
+    path: &Path,
+    structure_item: &StructureItem,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<Manifest, String> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("generate_manifest", root).entered();
+
+    let tuning = tuning(path, structure_item);
+    let mut files = Vec::new();
+    for (relative, file_path) in declared_files(path, structure_item, nested_roots) {
+        let metadata = std::fs::metadata(extended(&file_path))
+            .map_err(|e| format!("Failed to read file: {:?}, error: {:?}", file_path, e))?;
+        let sha256 = sha256_file(&file_path, &tuning)
+            .map_err(|e| format!("Failed to hash file: {:?}, error: {:?}", file_path, e))?;
+        files.push(ManifestEntry {
+            path: relative,
+            size: metadata.len(),
+            sha256,
+        });
+    }
+
+    let manifest = Manifest {
+        root: root.to_string(),
+        generated_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64),
+        config_hash: crate::hash::config_hash(structure_item),
+        files,
+    };
+
+    let manifest_path = path.join(MANIFEST_FILE);
+    repair_scope.check(Some(path), &manifest_path)?;
+    let json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize the manifest of {}: {}", root, e))?;
+    std::fs::write(extended(&manifest_path), &json)
+        .map_err(|e| format!("Failed to write {:?}, error: {:?}", manifest_path, e))?;
+
+    let signature_path = path.join(SIGNATURE_FILE);
+    repair_scope.check(Some(path), &signature_path)?;
+    match keys.sign(&json) {
+        Some(signature) => std::fs::write(extended(&signature_path), signature)
+            .map_err(|e| format!("Failed to write {:?}, error: {:?}", signature_path, e))?,
+        None => match std::fs::remove_file(extended(&signature_path)) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                return Err(format!(
+                    "Failed to delete: {:?}, error: {:?}",
+                    signature_path, e
+                ))
+            }
+            _ => {}
+        },
+    }
+    log::info!(
+        "Generated the manifest of {}: {} files",
+        root,
+        manifest.files.len()
+    );
+
+    Ok(manifest)
+}
+
+/// Compares the declared files of a root, at `path`, with the manifest stored in it.
+///
+/// A file is modified when its size differs from the recorded one, or else its hash. With a verifying key, the
+/// signature of the manifest is verified before trusting it.
+///
+/// # Returns
+///
+/// Returns the differences, or `Err(String)` if the root has no manifest, it can't be read or its signature is
+/// missing or invalid, or a file can't be hashed.
+pub(crate) fn verify(
+    keys: &ManifestKeys,
+    root: &str,
+    path: &Path,
+    structure_item: &StructureItem,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<ManifestDiff, String> {
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("verify_manifest", root).entered();
+
+    let manifest_path = path.join(MANIFEST_FILE);
+    let json = match std::fs::read(extended(&manifest_path)) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(format!(
+                "No manifest generated for {}: {:?}",
+                root, manifest_path
+            ))
+        }
+        Err(e) => {
+            return Err(format!(
+                "Failed to read {:?}, error: {:?}",
+                manifest_path, e
+            ))
+        }
+    };
+    keys.check(path, &json)?;
+    let manifest: Manifest = serde_json::from_slice(&json)
+        .map_err(|e| format!("Invalid manifest: {:?}, error: {}", manifest_path, e))?;
+
+    let tuning = tuning(path, structure_item);
+    let mut recorded: BTreeMap<String, ManifestEntry> = manifest
+        .files
+        .into_iter()
+        .map(|entry| (entry.path.clone(), entry))
+        .collect();
+    let mut diff = ManifestDiff {
+        root: root.to_string(),
+        added: Vec::new(),
+        removed: Vec::new(),
+        modified: Vec::new(),
+    };
+    for (relative, file_path) in declared_files(path, structure_item, nested_roots) {
+        let Some(entry) = recorded.remove(&relative) else {
+            diff.added.push(relative);
+            continue;
+        };
+        let size = std::fs::metadata(extended(&file_path))
+            .map_err(|e| format!("Failed to read file: {:?}, error: {:?}", file_path, e))?
+            .len();
+        let modified = size != entry.size
+            || sha256_file(&file_path, &tuning)
+                .map_err(|e| format!("Failed to hash file: {:?}, error: {:?}", file_path, e))?
+                != entry.sha256;
+        if modified {
+            diff.modified.push(relative);
+        }
+    }
+    diff.removed = recorded.into_keys().collect();
+
+    if !diff.is_empty() {
+        log::warn!(
+            "The files of {} differ from its manifest: {} added, {} removed, {} modified",
+            root,
+            diff.added.len(),
+            diff.removed.len(),
+            diff.modified.len()
+        );
+    }
+
+    Ok(diff)
+}
+
+/// Returns the existing declared files of a structure, as their path relative to the root (separated by `/`, with
+/// their declared names) and their path on disk, sorted by relative path.
+pub(crate) fn declared_files(
+    path: &Path,
+    structure_item: &StructureItem,
+    nested_roots: &[PathBuf],
+) -> Vec<(String, PathBuf)> {
+    let normalization = structure_item.unicode_normalization();
+    let mut files = Vec::new();
+    let mut stack = vec![(String::new(), path.to_path_buf(), structure_item)];
+    while let Some((prefix, dir_path, item)) = stack.pop() {
+        for file in item.files.iter().flatten() {
+            if let Some(file_path) = locate(&dir_path, &file.name, normalization) {
+                if extended(&file_path).is_file() {
+                    files.push((format!("{}{}", prefix, file.name), file_path));
+                }
+            }
+        }
+        for (name, dir) in item.dirs.iter().flatten() {
+            let Some(sub_path) = locate(&dir_path, name, normalization) else {
+                continue;
+            };
+            if nested_roots.contains(&sub_path) || !extended(&sub_path).is_dir() {
+                continue;
+            }
+            stack.push((format!("{}{}/", prefix, name), sub_path, dir));
+        }
+    }
+    files.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    files
+}
+
+/// Resolves the `tuning` option of a root item for hashing its files, see `Walker::new`.
+fn tuning(path: &Path, structure_item: &StructureItem) -> ResolvedIoTuning {
+    structure_item
+        .options
+        .as_ref()
+        .and_then(|options| options.tuning.clone())
+        .unwrap_or_default()
+        .resolve(path)
+}
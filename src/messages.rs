@@ -0,0 +1,53 @@
+use std::collections::BTreeMap;
+
+/// Translates a violation message from its key and parameters, returning `None` to keep the English message.
+pub type Translator = Box<dyn Fn(&str, &BTreeMap<String, String>) -> Option<String> + Send + Sync>;
+
+/// The translator registered with `Builder::translator`, if any.
+#[derive(Default)]
+pub(crate) struct Translation(pub(crate) Option<Translator>);
+
+impl Translation {
+    /// Returns the message in the language of the translator, or in English.
+    pub(crate) fn translate(&self, message: &Message) -> String {
+        self.0
+            .as_ref()
+            .and_then(|translator| translator(message.key, &message.params))
+            .unwrap_or_else(|| message.text.clone())
+    }
+}
+
+/// The message of a violation: a stable key, its parameters and the English text, with the explanation and help link
+/// written by the author of the broken rule.
+pub(crate) struct Message {
+    pub(crate) key: &'static str,
+    pub(crate) params: BTreeMap<String, String>,
+    pub(crate) text: String,
+    pub(crate) explanation: Option<String>,
+    pub(crate) help_url: Option<String>,
+}
+
+impl Message {
+    pub(crate) fn new(key: &'static str, text: String) -> Self {
+        Self {
+            key,
+            params: BTreeMap::new(),
+            text,
+            explanation: None,
+            help_url: None,
+        }
+    }
+
+    /// Adds a parameter of the message.
+    pub(crate) fn param(mut self, name: &str, value: impl ToString) -> Self {
+        self.params.insert(name.to_string(), value.to_string());
+        self
+    }
+
+    /// Adds the `message` and `helpUrl` options of the entry whose rule is broken, as `(message, help_url)`.
+    pub(crate) fn explain(mut self, (explanation, help_url): (Option<&str>, Option<&str>)) -> Self {
+        self.explanation = explanation.map(str::to_string);
+        self.help_url = help_url.map(str::to_string);
+        self
+    }
+}
@@ -0,0 +1,308 @@
+use std::path::{Component, Path, PathBuf};
+
+use tauri::{path::BaseDirectory, Manager, Runtime};
+
+use crate::{
+    history,
+    long_path::extended,
+    models::*,
+    ownership,
+    repair_scope::RepairScope,
+    roots::{self, RootSelector},
+};
+
+/// The file recording the version of the layout on disk, in the plugin's own directory of `appLocalData`.
+const MARKER_FILE: &str = "structure-version";
+
+/// The file recording the version of the structure in each root managed by the app, see
+/// `StructureManagerExt::structure_version`.
+pub const VERSION_MARKER: &str = ".structure-version";
+
+impl RootSelector {
+    /// Returns whether the plugin maintains a version marker in the root: app-scoped base directories, custom roots
+    /// and app groups, never shared locations such as `document`.
+    pub(crate) fn has_version_marker(&self) -> bool {
+        match self {
+            RootSelector::Base(directory) => ownership::is_app_scoped(*directory),
+            RootSelector::Custom(_) | RootSelector::AppGroup(_) => true,
+        }
+    }
+}
+
+/// Returns the version recorded by the version marker of a root, or `None` if it has none.
+pub(crate) fn root_version(path: &Path) -> std::result::Result<Option<u32>, String> {
+    read_marker(&path.join(VERSION_MARKER))
+}
+
+/// Records the version of the structure in the version marker of a root, if `overwrite` is set or it has none.
+///
+/// Markers are maintained on a best effort basis, errors are only logged.
+pub(crate) fn record_root_version(path: &Path, version: u32, overwrite: bool) {
+    let marker = path.join(VERSION_MARKER);
+    let result = match overwrite {
+        true => Ok(true),
+        false => read_marker(&marker).map(|recorded| recorded.is_none()),
+    };
+    if let Err(e) = result.and_then(|write| match write {
+        true => write_marker(&marker, version),
+        false => Ok(()),
+    }) {
+        log::warn!(
+            "Failed to record the structure version in {:?}: {}",
+            path,
+            e
+        );
+    }
+}
+
+/// Returns the paths of the roots whose version marker the plugin maintains, leaving out the ones that can't be
+/// resolved or don't exist yet.
+fn marked_roots<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    structure_config: &StructureConfig,
+) -> Vec<PathBuf> {
+    let builtin = roots::ROOTS
+        .into_iter()
+        .filter(|directory| structure_config.root(*directory).is_some())
+        .map(RootSelector::Base);
+    let custom = structure_config
+        .custom
+        .iter()
+        .flat_map(|custom| custom.keys().cloned())
+        .map(RootSelector::Custom);
+    let app_groups = structure_config
+        .app_groups
+        .iter()
+        .flat_map(|groups| groups.keys().cloned())
+        .map(RootSelector::AppGroup);
+
+    builtin
+        .chain(custom)
+        .chain(app_groups)
+        .filter(RootSelector::has_version_marker)
+        .filter_map(|root| {
+            roots::select(
+                structure_config,
+                &root,
+                |directory| roots::resolve(manager, directory),
+                |group| roots::resolve_app_group(manager, group),
+            )
+            .ok()
+        })
+        .map(|(path, _)| path)
+        .filter(|path| extended(path).is_dir())
+        .collect()
+}
+
+impl MigrationAction {
+    /// Returns the root the paths of the action are relative to.
+    fn root(&self) -> &str {
+        match self {
+            MigrationAction::Move { root, .. }
+            | MigrationAction::Rename { root, .. }
+            | MigrationAction::Delete { root, .. }
+            | MigrationAction::CreateDir { root, .. } => root,
+        }
+    }
+
+    /// Verifies that the paths of the action are relative and can't reach outside of its root, and that a new name
+    /// is a single path component.
+    pub(crate) fn check_paths(&self) -> std::result::Result<(), String> {
+        let paths = match self {
+            MigrationAction::Move { from, to, .. } => vec![from, to],
+            MigrationAction::Rename { path, name, .. } => {
+                let mut components = Path::new(name).components();
+                if !matches!(
+                    (components.next(), components.next()),
+                    (Some(Component::Normal(_)), None)
+                ) {
+                    return Err(format!("Invalid migration entry name: {:?}", name));
+                }
+                vec![path]
+            }
+            MigrationAction::Delete { path, .. } | MigrationAction::CreateDir { path, .. } => {
+                vec![path]
+            }
+        };
+
+        for path in paths {
+            let valid = Path::new(path)
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+            if !valid || path.is_empty() {
+                return Err(format!("Invalid migration path: {:?}", path));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Upgrades the layout on disk to the `version` of the configuration, applying its migrations in order and recording
+/// the version reached after each one, see `StructureManagerExt::migrate`.
+pub(crate) fn migrate<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    structure_config: &StructureConfig,
+) -> std::result::Result<MigrationSummary, String> {
+    let migrations = structure_config.migrations.as_deref().unwrap_or_default();
+    let current = structure_config.version.unwrap_or(0);
+    let marker = marker_path(manager)?;
+    let recorded = read_marker(&marker)?;
+    // Without a recorded version, the layout predates versioned structures if they can be migrated, and is a new
+    // install otherwise.
+    let from = recorded.unwrap_or_else(|| match migrations.iter().any(|m| m.from == 0) {
+        true => 0,
+        false => current,
+    });
+    if from > current {
+        return Err(format!(
+            "The layout on disk (version {}) is newer than the structure (version {})",
+            from, current
+        ));
+    }
+
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("migration", from, to = current).entered();
+
+    let repair_scope = manager.state::<RepairScope>();
+    let mut version = from;
+    let mut applied = Vec::new();
+    while version < current {
+        let migration = migrations
+            .iter()
+            .find(|migration| {
+                migration.from == version && migration.to > version && migration.to <= current
+            })
+            .ok_or_else(|| {
+                format!(
+                    "No migration from version {} of the structure to version {}",
+                    version, current
+                )
+            })?;
+
+        for action in &migration.actions {
+            let root = roots::resolve_named(manager, structure_config, action.root())?;
+            apply(action, &root, &repair_scope).map_err(|e| {
+                format!(
+                    "Failed to migrate the structure from version {} to {}: {}",
+                    migration.from, migration.to, e
+                )
+            })?;
+        }
+        version = migration.to;
+        write_marker(&marker, version)?;
+        log::info!(
+            "Migrated the structure from version {} to {}",
+            migration.from,
+            migration.to
+        );
+        applied.push(migration.clone());
+    }
+    if recorded.is_none() {
+        write_marker(&marker, version)?;
+    }
+    if let Some(current) = structure_config.version {
+        for path in marked_roots(manager, structure_config) {
+            record_root_version(&path, current, true);
+        }
+    }
+
+    Ok(MigrationSummary {
+        from,
+        to: version,
+        applied,
+    })
+}
+
+/// Applies an action in `root`, an entry that is already in place is left as is.
+fn apply(
+    action: &MigrationAction,
+    root: &Path,
+    repair_scope: &RepairScope,
+) -> std::result::Result<(), String> {
+    match action {
+        MigrationAction::Move { from, to, .. } => {
+            move_entry(&root.join(from), &root.join(to), root, repair_scope)
+        }
+        MigrationAction::Rename { path, name, .. } => {
+            let from = root.join(path);
+            let to = from.with_file_name(name);
+            move_entry(&from, &to, root, repair_scope)
+        }
+        MigrationAction::Delete { path, .. } => {
+            let path = root.join(path);
+            let Ok(metadata) = std::fs::symlink_metadata(extended(&path)) else {
+                return Ok(());
+            };
+            repair_scope.check(Some(root), &path)?;
+            let result = if metadata.is_dir() {
+                std::fs::remove_dir_all(extended(&path))
+            } else {
+                std::fs::remove_file(extended(&path))
+            };
+            result.map_err(|e| format!("Failed to delete: {:?}, error: {:?}", path, e))
+        }
+        MigrationAction::CreateDir { path, .. } => {
+            let path = root.join(path);
+            if extended(&path).is_dir() {
+                return Ok(());
+            }
+            repair_scope.check(Some(root), &path)?;
+            std::fs::create_dir_all(extended(&path))
+                .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", path, e))
+        }
+    }
+}
+
+fn move_entry(
+    from: &Path,
+    to: &Path,
+    root: &Path,
+    repair_scope: &RepairScope,
+) -> std::result::Result<(), String> {
+    if std::fs::symlink_metadata(extended(from)).is_err() {
+        return Ok(());
+    }
+    if std::fs::symlink_metadata(extended(to)).is_ok() {
+        return Err(format!(
+            "Failed to move {:?} to {:?}: the target already exists",
+            from, to
+        ));
+    }
+    repair_scope.check(Some(root), from)?;
+    repair_scope.check(Some(root), to)?;
+
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(extended(parent))
+            .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", parent, e))?;
+    }
+    std::fs::rename(extended(from), extended(to))
+        .map_err(|e| format!("Failed to move {:?} to {:?}, error: {:?}", from, to, e))
+}
+
+/// Returns the path of the file recording the version of the layout on disk.
+fn marker_path<R: Runtime, M: Manager<R>>(manager: &M) -> std::result::Result<PathBuf, String> {
+    roots::resolve(manager, BaseDirectory::AppLocalData)
+        .map(|directory| directory.join(history::DIRECTORY).join(MARKER_FILE))
+}
+
+fn read_marker(path: &Path) -> std::result::Result<Option<u32>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => content
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|e| format!("Invalid structure version in {:?}: {}", path, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("Failed to read {:?}, error: {:?}", path, e)),
+    }
+}
+
+fn write_marker(path: &Path, version: u32) -> std::result::Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", parent, e))?;
+    }
+    std::fs::write(path, version.to_string())
+        .map_err(|e| format!("Failed to write {:?}, error: {:?}", path, e))
+}
@@ -1,36 +1,65 @@
 use serde::de::DeserializeOwned;
-use tauri::{
-    plugin::{PluginApi, PluginHandle},
-    AppHandle, Runtime,
-};
-
-use crate::models::*;
+#[cfg(target_os = "ios")]
+use tauri::plugin::PluginHandle;
+use tauri::{plugin::PluginApi, AppHandle, Manager, Runtime};
 
-#[cfg(target_os = "android")]
-const PLUGIN_IDENTIFIER: &str = "";
+use crate::{cache::ResultCache, conditions::Conditions, models::*};
 
 #[cfg(target_os = "ios")]
-tauri::ios_plugin_binding!(init_plugin_structure - manager);
+tauri::ios_plugin_binding!(init_plugin_structure_manager);
 
-// initializes the Kotlin or Swift plugin classes
+// initializes the Swift plugin class, Android has no plugin class: verifications run in Rust
+#[cfg_attr(target_os = "android", allow(unused_variables))]
 pub fn init<R: Runtime, C: DeserializeOwned>(
-    _app: &AppHandle<R>,
+    app: &AppHandle<R>,
     api: PluginApi<R, C>,
 ) -> crate::Result<StructureManager<R>> {
-    #[cfg(target_os = "android")]
-    let handle = api.register_android_plugin(PLUGIN_IDENTIFIER, "ExamplePlugin")?;
-    #[cfg(target_os = "ios")]
-    let handle = api.register_ios_plugin(init_plugin_structure - manager)?;
-    Ok(StructureManager(handle))
+    Ok(StructureManager {
+        app: app.clone(),
+        #[cfg(target_os = "ios")]
+        handle: api.register_ios_plugin(init_plugin_structure_manager)?,
+    })
 }
 
 /// Access to the structure-manager APIs.
-pub struct StructureManager<R: Runtime>(PluginHandle<R>);
+pub struct StructureManager<R: Runtime> {
+    app: AppHandle<R>,
+    #[cfg(target_os = "ios")]
+    handle: PluginHandle<R>,
+}
 
 impl<R: Runtime> StructureManager<R> {
+    #[cfg(target_os = "ios")]
     pub fn ping(&self, payload: PingRequest) -> crate::Result<PingResponse> {
-        self.0
+        self.handle
             .run_mobile_plugin("ping", payload)
             .map_err(Into::into)
     }
+
+    #[cfg(target_os = "android")]
+    pub fn ping(&self, payload: PingRequest) -> crate::Result<PingResponse> {
+        Ok(PingResponse {
+            value: payload.value,
+        })
+    }
+
+    /// Returns the shared container of an iOS app group, see `RootSelector::AppGroup`.
+    #[cfg(target_os = "ios")]
+    pub(crate) fn app_group_container(&self, group: &str) -> crate::Result<std::path::PathBuf> {
+        self.handle
+            .run_mobile_plugin::<AppGroupContainerResponse>(
+                "appGroupContainer",
+                AppGroupContainerRequest { group },
+            )
+            .map(|response| response.path)
+            .map_err(Into::into)
+    }
+
+    /// Sets a named condition, evaluated by the structure items declaring `when: "<name>"` at verification time.
+    ///
+    /// Cached verification results are invalidated, as they may depend on the condition.
+    pub fn set_condition(&self, name: impl Into<String>, value: bool) {
+        self.app.state::<Conditions>().set(name.into(), value);
+        self.app.state::<ResultCache>().invalidate(None);
+    }
 }
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
@@ -14,13 +14,152 @@ pub struct PingResponse {
     pub value: Option<String>,
 }
 
+#[cfg(target_os = "ios")]
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppGroupContainerRequest<'a> {
+    pub(crate) group: &'a str,
+}
+
+#[cfg(target_os = "ios")]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppGroupContainerResponse {
+    pub(crate) path: PathBuf,
+}
+
+/// Simplified access control intents that can be applied to a directory on Windows.
+///
+/// Inherited permissions on redirected folders sometimes make a data directory unreadable to the app,
+/// an ACL template replaces the inherited entries with an explicit set.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AclTemplate {
+    /// Only the current user (and the system account) can access the directory.
+    #[serde(alias = "owner_only")]
+    OwnerOnly,
+    /// The current user has full control, other local users can read.
+    #[serde(alias = "users_read")]
+    UsersRead,
+    /// The current user has full control, everyone can read.
+    #[serde(alias = "everyone_read")]
+    EveryoneRead,
+}
+
+/// The Windows attributes expected on an entry, an attribute that isn't set is not checked.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryAttributes {
+    /// Whether the entry is hidden from the default listings of Explorer and `dir`.
+    pub hidden: Option<bool>,
+    /// Whether the entry is read-only.
+    pub readonly: Option<bool>,
+    /// Whether the entry is marked as used by the operating system.
+    pub system: Option<bool>,
+}
+
+/// How long the undeclared entries of a directory are kept, e.g. old logs or cache files.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Retention {
+    /// The number of days since their last modification after which entries are deleted, or archived.
+    #[serde(alias = "max_age_days")]
+    pub max_age_days: u64,
+    /// A subdirectory of the directory expired entries are moved into instead of being deleted, e.g. `archive`.
+    /// It is created when needed and its own entries never expire.
+    #[serde(alias = "archive_to")]
+    pub archive_to: Option<String>,
+}
+
+/// Represents IO tuning options for the verification of a root.
+///
+/// Options left unset adapt to the detected volume type (local or network).
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct IoTuning {
+    /// The number of threads used to check the entries of a directory.
+    pub parallelism: Option<usize>,
+    /// The size, in bytes, of the chunks read when hashing file contents.
+    #[serde(alias = "hash_chunk_size")]
+    pub hash_chunk_size: Option<usize>,
+    /// The maximum number of bytes read per second when reading file contents.
+    #[serde(alias = "io_throttle_bytes_per_sec")]
+    pub io_throttle_bytes_per_sec: Option<u64>,
+    /// The number of times an IO operation of the verification or its repair is retried when it fails with a
+    /// transient error, e.g. a file briefly locked by an antivirus or a cloud-sync client. Defaults to 3, 0 disables
+    /// the retries.
+    pub retries: Option<u32>,
+    /// The delay, in milliseconds, before the first retry of an IO operation, doubled before each next one. Defaults
+    /// to 50.
+    #[serde(alias = "retry_backoff_ms")]
+    pub retry_backoff_ms: Option<u64>,
+}
+
+/// What verification does when a directory exceeds one of its quotas.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuotaPolicy {
+    /// The verification fails.
+    #[default]
+    Report,
+    /// The oldest undeclared entries (by modification time) are deleted until the quota is met.
+    #[serde(alias = "delete_oldest")]
+    DeleteOldest,
+}
+
+/// What verification does when a declared directory is a junction or another reparse point (Windows only), e.g.
+/// a folder redirected to OneDrive.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReparsePointPolicy {
+    /// The directory is verified and repaired like any other.
+    Follow,
+    /// The directory is reported as a warning, its contents are neither verified nor repaired.
+    #[default]
+    Report,
+    /// The directory is reported with its `severity`, failing the verification by default.
+    Reject,
+}
+
+/// Where the repair of a structure item copies the missing entries of its directory from.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SeedSource {
+    /// The resources bundled with the app (`resource_dir()`), at the same path relative to it as the directory is
+    /// relative to its root.
+    Resource,
+}
+
+/// A Unicode normalization form, entry names differing only by their normalization are considered the same.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UnicodeNormalization {
+    /// Canonical composition, e.g. `é` as a single character. Most configurations are written in this form.
+    Nfc,
+    /// Canonical decomposition, e.g. `é` as `e` and a combining accent, the form of names stored by macOS.
+    Nfd,
+}
+
 /// Represents the options for a structure item.
 ///
 /// By default, a None value is considered as false.
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
 pub struct StructureItemOptions {
     /// If set to true, the directory will be created if it does not exist.
     pub repair: Option<bool>,
+    /// If set to true and `repair` is enabled, the root itself is created before it is verified when it doesn't exist,
+    /// e.g. `appData` on a fresh machine. Overrides the top-level `createRoot`. Only read on the root item of a
+    /// verification.
+    #[serde(alias = "create_root")]
+    pub create_root: Option<bool>,
+    /// Whether and how the root is verified by the plugin during its setup. Overrides the top-level
+    /// `verifyOnStartup`. Only read on roots.
+    #[serde(alias = "verify_on_startup")]
+    pub verify_on_startup: Option<VerifyOnStartup>,
+    /// The number of seconds a verification of the root may take before it is aborted with a `timedOut` violation,
+    /// e.g. when the root lives on a hung network share. Only read on the root item of a verification.
+    pub timeout: Option<u64>,
     /// If set to true, the contents need to be exactly the same.
     ///
     /// By default, `StructureManager` will only verify if directories and files from the `StructureConfig` exist,
@@ -28,27 +167,510 @@ pub struct StructureItemOptions {
     ///
     /// If `strict` is set to true, the contents of the directory (`StructureItem`) need to be exactly the same.
     pub strict: Option<bool>,
+    /// The ACL template applied when the directory is created by a repair (Windows only, ignored elsewhere).
+    pub acl: Option<AclTemplate>,
+    /// IO tuning for the verification, only read on the root item of a verification.
+    pub tuning: Option<IoTuning>,
+    /// The maximum number of entries (files and directories) directly inside the directory.
+    #[serde(alias = "max_children")]
+    pub max_children: Option<usize>,
+    /// The maximum total size, in bytes, of the files inside the directory (recursively), e.g. `524288000` to cap a
+    /// cache at 500 MB. With the `deleteOldest` policy, the least recently modified undeclared files are deleted.
+    #[serde(alias = "max_total_size")]
+    pub max_total_size: Option<u64>,
+    /// The maximum number of entries (files and directories) inside the directory, recursively, e.g. to flag
+    /// thousands of leftover temporary exports. With the `deleteOldest` policy, the least recently modified undeclared
+    /// entries are deleted, an undeclared directory with its contents.
+    #[serde(alias = "max_entries")]
+    pub max_entries: Option<u64>,
+    /// If set to true, every verification removes the empty undeclared subdirectories of the directory (and those only
+    /// containing empty directories), e.g. left behind by older versions of the app, without enabling `strict`.
+    #[serde(alias = "prune_empty_dirs")]
+    pub prune_empty_dirs: Option<bool>,
+    /// How long the undeclared entries of the directory are kept: every verification deletes (or archives) the ones
+    /// older than `maxAgeDays`, e.g. on `appLog`.
+    pub retention: Option<Retention>,
+    /// What to do when a quota of the directory is exceeded, defaults to `report`.
+    #[serde(alias = "quota_policy")]
+    pub quota_policy: Option<QuotaPolicy>,
+    /// If set to true on the root of an app-scoped base directory (`appCache`, `appConfig`, `appData`, `appLocalData`
+    /// or `appLog`), entries clearly belonging to other software are reported, e.g. when two apps share a bundle identifier.
+    pub exclusive: Option<bool>,
+    /// Names of undeclared entries of an `exclusive` root known to be shared with other software, never reported.
+    #[serde(alias = "shared_entries")]
+    pub shared_entries: Option<Vec<String>>,
+    /// The number of levels of declared directories below the directory that are verified. Deeper directories are
+    /// only checked for existence (and created by `repair`), their contents are neither verified nor repaired.
+    #[serde(alias = "max_depth")]
+    pub max_depth: Option<usize>,
+    /// Glob patterns of entry names of the directory excluded from strict comparisons, e.g. `.DS_Store` or `*~`.
+    pub ignore: Option<Vec<String>>,
+    /// If set to true, a missing directory that isn't repaired is reported as a warning instead of failing
+    /// the verification, and its contents aren't verified.
+    pub optional: Option<bool>,
+    /// The severity of the violations of the directory: missing, unexpected entries and exceeded quotas.
+    pub severity: Option<Severity>,
+    /// If set to true, the verification keeps walking after the first error and fails with every error message,
+    /// one per line. Only read on the root item of a verification.
+    pub aggregate: Option<bool>,
+    /// If set to true, the verification also audits the permissions of every entry of the root on Unix, declared or
+    /// not, reporting world-writable directories, setuid files and entries owned by unexpected users as warnings.
+    /// Only read on the root item of a verification.
+    #[serde(alias = "security_audit")]
+    pub security_audit: Option<bool>,
+    /// If set to true, files of the root modified or removed since its manifest was generated are reported as
+    /// tampered with by `verify_manifest`. Defaults to true for `resource`. Only read on the root item of a
+    /// verification.
+    pub protected: Option<bool>,
+    /// The Windows attributes expected on the directory (ignored elsewhere), set by `repair` when they differ.
+    pub attributes: Option<EntryAttributes>,
+    /// The user expected to own the directory on Unix (ignored elsewhere), a name or a numeric id. It is set by
+    /// `repair` when it differs and the app has the privileges to change it.
+    pub owner: Option<String>,
+    /// The group expected on the directory on Unix (ignored elsewhere), a name or a numeric id, set like `owner`.
+    pub group: Option<String>,
+    /// What to do when the directory is a junction or another reparse point (Windows only), defaults to `report`.
+    #[serde(alias = "reparse_point")]
+    pub reparse_point: Option<ReparsePointPolicy>,
+    /// The Unicode normalization form entry names are compared in, and directories are created with by a repair.
+    /// Names are compared as is by default. Only read on the root item of a verification.
+    #[serde(alias = "unicode_normalization")]
+    pub unicode_normalization: Option<UnicodeNormalization>,
+    /// Where the repair copies the entries missing from the directory (recursively) from, e.g. to restore the
+    /// factory files of the app. Existing entries are never replaced.
+    #[serde(alias = "seed_from")]
+    pub seed_from: Option<SeedSource>,
+    /// A zip archive of the bundled resources (relative to `resource_dir()`) the repair extracts the directory from
+    /// when it is missing, instead of creating it empty, e.g. for presets made of thousands of files. Requires the
+    /// `archive` feature.
+    pub archive: Option<String>,
+    /// Previous names of the directory in its parent, e.g. `["OldCache"]`: when the directory is missing, the repair
+    /// renames the first of them that exists instead of creating an empty directory next to the user's data.
+    #[serde(alias = "moved_from")]
+    pub moved_from: Option<Vec<String>>,
+    /// An explanation of the violations of the directory written for the user, carried by them as `explanation`,
+    /// e.g. "Run File → Import to recreate your library".
+    pub message: Option<String>,
+    /// A link to help about the violations of the directory, carried by them as `helpUrl`.
+    #[serde(alias = "help_url")]
+    pub help_url: Option<String>,
+}
+
+/// The build profile an item is restricted to.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum BuildProfile {
+    Debug,
+    Release,
+}
+
+/// Represents the conditions under which a structure item is verified.
+///
+/// Every declared condition must match, an item without conditions is always verified.
+///
+/// In the configuration, a condition is either an object, or the name of a condition set at runtime:
+/// `"when": "hasGPU"` is the same as `"when": { "condition": "hasGPU" }`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(from = "StructureConditionRepr", into = "StructureConditionRepr")]
+pub struct StructureCondition {
+    /// The platforms the item applies to, as reported by `std::env::consts::OS` (`windows`, `macos`, `linux`, `android`, `ios`),
+    /// or `desktop` / `mobile`.
+    pub platform: Option<Vec<String>>,
+    /// A semver requirement matched against the app version, e.g. `">=2.0.0, <3.0.0"`.
+    pub version: Option<String>,
+    /// The build profile the item applies to.
+    pub build: Option<BuildProfile>,
+    /// Feature flags, supplied when building the plugin, that all need to be enabled.
+    pub features: Option<Vec<String>>,
+    /// The name of a condition set with `StructureManager::set_condition`, that needs to be true.
+    pub condition: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum StructureConditionRepr {
+    Name(String),
+    Detailed(StructureConditionFields),
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StructureConditionFields {
+    platform: Option<Vec<String>>,
+    version: Option<String>,
+    build: Option<BuildProfile>,
+    features: Option<Vec<String>>,
+    condition: Option<String>,
+}
+
+impl From<StructureConditionRepr> for StructureCondition {
+    fn from(repr: StructureConditionRepr) -> Self {
+        match repr {
+            StructureConditionRepr::Name(condition) => StructureCondition {
+                condition: Some(condition),
+                ..Default::default()
+            },
+            StructureConditionRepr::Detailed(fields) => StructureCondition {
+                platform: fields.platform,
+                version: fields.version,
+                build: fields.build,
+                features: fields.features,
+                condition: fields.condition,
+            },
+        }
+    }
+}
+
+impl From<StructureCondition> for StructureConditionRepr {
+    fn from(condition: StructureCondition) -> Self {
+        match condition {
+            // A condition only naming a runtime condition is written back as its name, as it is usually declared.
+            StructureCondition {
+                platform: None,
+                version: None,
+                build: None,
+                features: None,
+                condition: Some(name),
+            } => StructureConditionRepr::Name(name),
+            condition => StructureConditionRepr::Detailed(StructureConditionFields {
+                platform: condition.platform,
+                version: condition.version,
+                build: condition.build,
+                features: condition.features,
+                condition: condition.condition,
+            }),
+        }
+    }
+}
+
+/// Represents the options of a declared file.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StructureFileOptions {
+    /// The name of a validator registered with `Builder::validator`, run on the file when it exists.
+    pub validator: Option<String>,
+    /// If set to true, a missing file is reported as a warning instead of failing the verification.
+    pub optional: Option<bool>,
+    /// The severity of the violations of the file: missing, rejected by its validator or with other attributes.
+    pub severity: Option<Severity>,
+    /// The Windows attributes expected on the file (ignored elsewhere), set when the `repair` option of its directory
+    /// is enabled and they differ.
+    pub attributes: Option<EntryAttributes>,
+    /// Whether the file is expected to be read-only on every platform: the read-only attribute on Windows, no write
+    /// permission on Unix. It is set when the `repair` option of its directory is enabled and it differs, e.g. to
+    /// protect seeded reference files from accidental modification.
+    pub readonly: Option<bool>,
+    /// Whether the file is expected to be executable on Unix (ignored elsewhere), e.g. a helper binary whose execute
+    /// permission was lost when it was copied. It is set when the `repair` option of its directory is enabled and it
+    /// differs.
+    pub executable: Option<bool>,
+    /// The user expected to own the file on Unix (ignored elsewhere), a name or a numeric id. It is set when the
+    /// `repair` option of its directory is enabled, it differs and the app has the privileges to change it.
+    pub owner: Option<String>,
+    /// The group expected on the file on Unix (ignored elsewhere), a name or a numeric id, set like `owner`.
+    pub group: Option<String>,
+    /// The default content of the file, written when it is missing and the `repair` option of its directory is
+    /// enabled, and when it is scaffolded.
+    pub content: Option<FileContent>,
+    /// The URL the file is downloaded from when it is missing and the `repair` option of its directory is enabled,
+    /// and when it is scaffolded, e.g. for large assets that aren't bundled. Requires the `download` feature, and is
+    /// ignored when the file has a default `content`.
+    pub url: Option<String>,
+    /// The expected SHA-256 hash of the file downloaded from `url`, as a hexadecimal string: a download that doesn't
+    /// match it is discarded.
+    pub sha256: Option<String>,
+    /// The JSON Schema the file is validated against when it exists, the file must parse as JSON and conform to it.
+    pub schema: Option<FileSchema>,
+    /// The format the file must parse in when it exists, e.g. to catch a settings file cut short by a crash.
+    pub format: Option<FileFormat>,
+    /// If set to true, the file is a SQLite database checked with `PRAGMA integrity_check` when it exists, opened
+    /// read-only. Requires the `sqlite` feature.
+    #[serde(alias = "sqlite_integrity")]
+    pub sqlite_integrity: Option<bool>,
+    /// An explanation of the violations of the file written for the user, carried by them as `explanation`.
+    pub message: Option<String>,
+    /// A link to help about the violations of the file, carried by them as `helpUrl`.
+    #[serde(alias = "help_url")]
+    pub help_url: Option<String>,
+}
+
+/// A file format whose syntax is checked, see the `format` option of files.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum FileFormat {
+    Json,
+    Toml,
+    /// Every document of the file must parse.
+    Yaml,
+    /// Lines are sections (`[name]`), `key = value` or `key: value` pairs, comments (`;` or `#`) or empty.
+    Ini,
+}
+
+/// The JSON Schema a declared JSON file is validated against.
+///
+/// In the configuration, a bundled schema is its path relative to `resource_dir()`, and an inline schema an object:
+/// `"schema": "schemas/settings.json"` or `"schema": { "type": "object", "required": ["version"] }`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FileSchema {
+    Resource(String),
+    Inline(serde_json::Value),
+}
+
+/// The default content of a declared file.
+///
+/// In the configuration, text content is a string and binary content an object with its base64 encoding:
+/// `"content": "{}"` or `"content": { "base64": "iVBORw0KGgo=" }`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FileContent {
+    Text(String),
+    Base64 { base64: String },
+}
+
+/// Represents a file declared in a structure item.
+///
+/// In the configuration, a file is either its name, or an object with its `name` and options:
+/// `["settings.json", { "name": "library.db", "validator": "db-check" }]`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(from = "StructureFileRepr", into = "StructureFileRepr")]
+pub struct StructureFile {
+    /// The name of the file.
+    pub name: String,
+    /// The options of the file.
+    pub options: StructureFileOptions,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum StructureFileRepr {
+    Name(String),
+    Detailed {
+        name: String,
+        #[serde(flatten)]
+        options: Box<StructureFileOptions>,
+    },
+}
+
+impl From<StructureFileRepr> for StructureFile {
+    fn from(repr: StructureFileRepr) -> Self {
+        match repr {
+            StructureFileRepr::Name(name) => StructureFile {
+                name,
+                options: StructureFileOptions::default(),
+            },
+            StructureFileRepr::Detailed { name, options } => StructureFile {
+                name,
+                options: *options,
+            },
+        }
+    }
+}
+
+impl From<StructureFile> for StructureFileRepr {
+    fn from(file: StructureFile) -> Self {
+        // A file without options is written back as its name, as it is usually declared.
+        if file.options == StructureFileOptions::default() {
+            StructureFileRepr::Name(file.name)
+        } else {
+            StructureFileRepr::Detailed {
+                name: file.name,
+                options: Box::new(file.options),
+            }
+        }
+    }
+}
+
+impl From<&str> for StructureFile {
+    fn from(name: &str) -> Self {
+        name.to_string().into()
+    }
+}
+
+impl From<String> for StructureFile {
+    fn from(name: String) -> Self {
+        StructureFile {
+            name,
+            options: StructureFileOptions::default(),
+        }
+    }
 }
 
 /// Represents an item in the structure (a directory in the OS), which can contain options, files, and directories.
-#[derive(Deserialize, Clone, Debug)]
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
 pub struct StructureItem {
+    /// The name of a reusable structure declared in `StructureConfig::definitions`.
+    ///
+    /// The referenced structure is merged with the item: files and directories are combined,
+    /// and the item's own options and directories take precedence over the referenced ones.
+    #[serde(rename = "$ref")]
+    pub reference: Option<String>,
+    /// The conditions under which the item is verified, the item is ignored when they don't match.
+    pub when: Option<StructureCondition>,
     /// The options for the structure item.
     pub options: Option<StructureItemOptions>,
     /// The list of files in the structure item.
-    pub files: Option<Vec<String>>,
-    /// The list of directories in the structure item.
-    pub dirs: Option<HashMap<String, StructureItem>>,
+    pub files: Option<Vec<StructureFile>>,
+    /// The list of directories in the structure item, ordered by name so verification is deterministic.
+    pub dirs: Option<BTreeMap<String, StructureItem>>,
+}
+
+impl From<&StructureItem> for StructureItem {
+    fn from(item: &StructureItem) -> Self {
+        item.clone()
+    }
+}
+
+impl From<Vec<&str>> for StructureItem {
+    /// Creates an item declaring the given files, e.g. `vec!["settings.json", "library.db"].into()`.
+    fn from(files: Vec<&str>) -> Self {
+        StructureItem {
+            files: Some(files.into_iter().map(StructureFile::from).collect()),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Vec<String>> for StructureItem {
+    /// Creates an item declaring the given files.
+    fn from(files: Vec<String>) -> Self {
+        StructureItem {
+            files: Some(files.into_iter().map(StructureFile::from).collect()),
+            ..Default::default()
+        }
+    }
+}
+
+impl TryFrom<serde_json::Value> for StructureItem {
+    type Error = String;
+
+    /// Parses an item from its JSON form, as declared in the structure configuration.
+    fn try_from(value: serde_json::Value) -> std::result::Result<Self, Self::Error> {
+        serde_json::from_value(value).map_err(|e| format!("Invalid structure item: {}", e))
+    }
+}
+
+/// Represents a layout produced by an external tool (e.g. a DAW project or an OBS profile) that the app imports.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StructureContract {
+    /// The human-readable name of the layout used in messages, e.g. `"OBS profile"`. Defaults to the contract name.
+    pub label: Option<String>,
+    /// The expected structure of a folder fulfilling the contract.
+    pub structure: StructureItem,
+}
+
+/// Represents a structure rooted at an arbitrary absolute location, e.g. an external drive or a user-chosen workspace.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomRoot {
+    /// The absolute path of the root.
+    pub path: PathBuf,
+    /// The expected structure of the root.
+    pub structure: StructureItem,
+}
+
+/// A folder picked by the user, verified as a custom root against a named structure, see
+/// `StructureManagerExt::add_workspace`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceRoot {
+    /// The name of the root, like the name of a custom root.
+    pub name: String,
+    /// The absolute path of the root.
+    pub path: PathBuf,
+    /// The name of its structure in `StructureConfig::structures`.
+    pub structure: String,
+}
+
+/// Whether and how roots are verified by the plugin during its setup, see `StructureConfig::verify_on_startup`.
+///
+/// In the configuration, either a boolean or an object with the options:
+/// `"verifyOnStartup": true` or `"verifyOnStartup": { "repair": false, "async": true }`.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum VerifyOnStartup {
+    Enabled(bool),
+    Options(VerifyOnStartupOptions),
+}
+
+impl From<bool> for VerifyOnStartup {
+    fn from(enabled: bool) -> Self {
+        VerifyOnStartup::Enabled(enabled)
+    }
+}
+
+impl From<VerifyOnStartupOptions> for VerifyOnStartup {
+    fn from(options: VerifyOnStartupOptions) -> Self {
+        VerifyOnStartup::Options(options)
+    }
+}
+
+/// The options of the verification of roots during the setup of the plugin.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyOnStartupOptions {
+    /// If set to false, the roots are only verified, whatever their `repair` option. Defaults to true.
+    pub repair: Option<bool>,
+    /// If set to true, the roots are verified on a background thread instead of delaying the setup. Defaults to
+    /// false.
+    #[serde(rename = "async")]
+    pub in_background: Option<bool>,
 }
 
 /// Represents the structure configuration.
-#[derive(Deserialize, Clone, Default, Debug)]
+///
+/// Keys are camelCase, their snake_case spelling (e.g. `app_data`) is accepted as an alias.
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct StructureConfig {
+    /// The version of the structure, increased by each of its `migrations`.
+    pub version: Option<u32>,
+    /// The migrations upgrading the layout on disk of a previous version of the structure, see
+    /// `StructureManagerExt::migrate`.
+    pub migrations: Option<Vec<Migration>>,
+    /// Reusable structures that can be referenced by any item with `$ref`.
+    pub definitions: Option<BTreeMap<String, StructureItem>>,
+    /// Named layouts of external tools, used to validate user-picked folders.
+    pub contracts: Option<BTreeMap<String, StructureContract>>,
+    /// Named structures verified against any directory with `verify_named`, e.g. the layout of each project folder
+    /// picked by the user.
+    pub structures: Option<BTreeMap<String, StructureItem>>,
+    /// Named profiles laid over the configuration when selected with `Builder::profile`, e.g. a looser `dev` structure.
+    pub profiles: Option<BTreeMap<String, StructureConfig>>,
+    /// Structures rooted at arbitrary absolute locations, verified with `verify_custom`.
+    pub custom: Option<BTreeMap<String, CustomRoot>>,
+    /// Structures of the shared containers of iOS app groups, keyed by group identifier (e.g. `group.com.example`),
+    /// so an app and its extensions agree on the layout they share.
+    #[serde(alias = "app_groups")]
+    pub app_groups: Option<BTreeMap<String, StructureItem>>,
+    /// Glob patterns of entry names excluded from strict comparisons in every directory, e.g. `Thumbs.db`.
+    pub ignore: Option<Vec<String>>,
+    /// If set to true, every root whose `repair` option is enabled is created before it is verified when it doesn't
+    /// exist, unless the root sets its own `createRoot` option.
+    #[serde(alias = "create_root")]
+    pub create_root: Option<bool>,
+    /// If set to true, `verify_all` skips the base directories that can't be resolved on the platform (e.g. `runtime`
+    /// on macOS and Windows) instead of failing, emitting a `verification-skipped` event for each.
+    #[serde(alias = "skip_unresolved_roots")]
+    pub skip_unresolved_roots: Option<bool>,
+    /// If set to true, every root is verified during the setup of the plugin, and the setup fails when one of them is
+    /// invalid and can't be repaired, aborting the launch of the app.
+    #[serde(alias = "fail_on_startup")]
+    pub fail_on_startup: Option<bool>,
+    /// Whether and how every root is verified by the plugin during its setup, unless the root sets its own
+    /// `verifyOnStartup` option. Disabled by default.
+    #[serde(alias = "verify_on_startup")]
+    pub verify_on_startup: Option<VerifyOnStartup>,
+    #[serde(alias = "app_cache")]
     pub app_cache: Option<StructureItem>,
+    #[serde(alias = "app_config")]
     pub app_config: Option<StructureItem>,
+    #[serde(alias = "app_data")]
     pub app_data: Option<StructureItem>,
+    #[serde(alias = "app_local_data")]
     pub app_local_data: Option<StructureItem>,
+    #[serde(alias = "app_log")]
     pub app_log: Option<StructureItem>,
     pub audio: Option<StructureItem>,
     pub cache: Option<StructureItem>,
@@ -60,6 +682,7 @@ pub struct StructureConfig {
     pub executable: Option<StructureItem>,
     pub font: Option<StructureItem>,
     pub home: Option<StructureItem>,
+    #[serde(alias = "local_data")]
     pub local_data: Option<StructureItem>,
     pub picture: Option<StructureItem>,
     pub public: Option<StructureItem>,
@@ -69,3 +692,713 @@ pub struct StructureConfig {
     pub template: Option<StructureItem>,
     pub video: Option<StructureItem>,
 }
+
+/// Upgrades the layout on disk from a version of the structure to a later one.
+///
+/// In the configuration:
+/// `{ "from": 1, "to": 2, "actions": [{ "action": "move", "root": "appData", "from": "cache", "to": "state/cache" }] }`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Migration {
+    /// The version the migration upgrades from, `0` for a layout without a recorded version.
+    pub from: u32,
+    /// The version the migration upgrades to.
+    pub to: u32,
+    /// The actions of the migration, applied in order.
+    pub actions: Vec<MigrationAction>,
+}
+
+/// An action of a migration, on paths relative to a root: the configuration field of a base directory (e.g. `appData`)
+/// or the name of a custom root.
+///
+/// Actions are idempotent, so an interrupted migration can be run again: an entry that is already in place is left as
+/// is.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "camelCase")]
+pub enum MigrationAction {
+    /// Moves an entry to another path of its root, creating the missing parents of `to`. Skipped when `from` doesn't
+    /// exist, and fails when both exist.
+    Move {
+        root: String,
+        from: String,
+        to: String,
+    },
+    /// Renames an entry within its directory. Skipped when `path` doesn't exist, and fails when `name` already exists.
+    Rename {
+        root: String,
+        path: String,
+        name: String,
+    },
+    /// Deletes an entry, recursively for a directory. Skipped when it doesn't exist.
+    Delete { root: String, path: String },
+    /// Creates a directory and its missing parents. Skipped when it exists.
+    CreateDir { root: String, path: String },
+}
+
+/// The result of `StructureManagerExt::migrate`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MigrationSummary {
+    /// The version of the layout on disk before the migration.
+    pub from: u32,
+    /// The version of the layout on disk after the migration, the `version` of the configuration.
+    pub to: u32,
+    /// The migrations that were applied, in order.
+    pub applied: Vec<Migration>,
+}
+
+/// Represents the options of a snapshot of a directory.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct SnapshotOptions {
+    /// The maximum depth of directories described by the snapshot, `0` only lists the top-level entries.
+    pub max_depth: Option<usize>,
+    /// The maximum number of top-level entries (with their contents) per chunk, unlimited by default.
+    pub chunk_size: Option<usize>,
+    /// The continuation token of the previous chunk, to snapshot the next one.
+    pub continuation: Option<String>,
+    /// Glob patterns of entry names left out of the snapshot, in addition to the `.structureignore` files.
+    pub ignore: Option<Vec<String>>,
+}
+
+/// Represents a chunk of a snapshot, describing some of the top-level entries of a directory and their contents.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct SnapshotChunk {
+    /// The structure of the top-level entries of the chunk.
+    pub structure: StructureItem,
+    /// The token to pass as `SnapshotOptions::continuation` to get the next chunk, `None` for the last chunk.
+    pub continuation: Option<String>,
+}
+
+/// Represents the options of a tree visualization.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeOptions {
+    /// The number of directory levels to expand, `0` only lists the entries of the directory. Defaults to `0`.
+    pub depth: Option<usize>,
+    /// If set to true, each node carries its status against the structure configuration.
+    pub include_status: Option<bool>,
+}
+
+/// The status of a tree node against the structure configuration.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum TreeNodeStatus {
+    /// The entry is declared and exists.
+    Ok,
+    /// The entry is declared but doesn't exist.
+    Missing,
+    /// The entry exists but isn't declared.
+    Undeclared,
+}
+
+/// Represents a node of a tree visualization, ready to be rendered by a frontend.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TreeNode {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: EntryKind,
+    /// The status of the node, only set when `includeStatus` is requested.
+    pub status: Option<TreeNodeStatus>,
+    /// The size of the file in bytes, `None` for directories and missing files.
+    pub size: Option<u64>,
+    /// The children of a directory, `None` when it wasn't expanded.
+    pub children: Option<Vec<TreeNode>>,
+}
+
+/// The kind of an entry of a structure.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryKind {
+    File,
+    Directory,
+}
+
+/// The kind of a violation of a structure.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ViolationKind {
+    /// A declared file doesn't exist.
+    MissingFile,
+    /// A declared directory doesn't exist and wasn't repaired.
+    MissingDirectory,
+    /// An entry isn't declared in a strict directory.
+    UnexpectedEntry,
+    /// A directory exceeds one of its quotas.
+    QuotaExceeded,
+    /// A file was rejected by its validator.
+    ValidationFailed,
+    /// A file doesn't parse as JSON or doesn't conform to its JSON Schema.
+    SchemaViolation,
+    /// A file doesn't parse in its declared `format`.
+    InvalidFormat,
+    /// A repair action failed.
+    RepairFailed,
+    /// An entry of an `exclusive` root belongs to other software.
+    ForeignEntry,
+    /// The Windows attributes of an entry differ from the expected ones and weren't repaired.
+    AttributeMismatch,
+    /// The owner or group of an entry differ from the expected ones (Unix only) and weren't repaired.
+    OwnerMismatch,
+    /// The `securityAudit` option found a world-writable directory, a setuid file or an entry owned by an unexpected
+    /// user.
+    SecurityIssue,
+    /// A declared directory is a junction or another reparse point (Windows only).
+    ReparsePoint,
+    /// A declared directory is nested deeper than `Builder::max_traversal_depth`, its contents weren't verified.
+    DepthLimitExceeded,
+    /// The version marker of a root differs from the `version` of the structure, the root needs a migration.
+    VersionMismatch,
+    /// The verification of a root took longer than its `timeout`, it was aborted.
+    TimedOut,
+    /// The ACL of a directory differs from its `acl` template (Windows only) and wasn't repaired.
+    AclMismatch,
+}
+
+/// How much a violation of a structure rule matters, set per file or directory with `severity`.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// The violation fails the verification.
+    #[default]
+    Error,
+    /// The violation is logged as a warning and reported, without failing the verification.
+    Warning,
+    /// The violation is logged and reported, without failing the verification.
+    Info,
+}
+
+/// Represents an issue found while verifying a structure.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Violation {
+    pub kind: ViolationKind,
+    /// The stable code of the kind, e.g. `SM0101`, see [`ViolationKind::code`].
+    pub code: &'static str,
+    pub severity: Severity,
+    /// The path of the entry the violation is about.
+    pub path: PathBuf,
+    /// A human-readable description of the violation, translated by `Builder::translator` if set.
+    pub message: String,
+    /// The stable key of the message, e.g. `fileNotFound`, to translate it.
+    pub key: String,
+    /// The parameters of the message, e.g. `path`.
+    pub params: BTreeMap<String, String>,
+    /// The explanation written by the author of the broken rule, its `message` option, e.g. "Run File → Import to
+    /// recreate your library". Shown as is, it isn't translated.
+    pub explanation: Option<String>,
+    /// A link to help about the broken rule, its `helpUrl` option.
+    pub help_url: Option<String>,
+}
+
+/// Why a configured entry name is problematic on some platforms or filesystems.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum NameIssue {
+    /// The name contains a colon, shown as a slash by the macOS Finder and reserved on Windows.
+    Colon,
+    /// The name contains a character reserved on Windows (`<>"/\|?*`) or a control character.
+    ReservedCharacter,
+    /// The name is a reserved device name on Windows, e.g. `CON` or `nul.txt`.
+    ReservedName,
+    /// The name ends with a space or a dot, which Windows silently strips.
+    TrailingSpaceOrDot,
+    /// The name contains characters outside of the Basic Multilingual Plane (e.g. emoji),
+    /// mishandled by older NTFS tooling.
+    SupplementaryCharacter,
+    /// The name is longer than 255 bytes, the limit of most filesystems.
+    TooLong,
+    /// The name differs only by case from another entry of the same directory,
+    /// which collide on case-insensitive filesystems.
+    CaseConflict,
+}
+
+/// A configured entry name that is problematic on some platforms or filesystems.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct NameDiagnostic {
+    /// Where the entry is declared in the configuration, e.g. `appData/logs/app.log`.
+    pub location: String,
+    pub issue: NameIssue,
+    /// The affected platforms, named like the values of the `platform` condition.
+    pub platforms: Vec<String>,
+}
+
+/// The payload of the `structure-manager://verification-started` event.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationStarted {
+    /// The identifier of the verification, also carried by its heartbeats.
+    pub operation_id: u64,
+    /// The verified root: a base directory field (e.g. `appData`), a custom root name or a path.
+    pub root: String,
+}
+
+/// The payload of the `structure-manager://verification-skipped` event.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationSkipped {
+    /// The skipped root, a base directory field (e.g. `runtime`).
+    pub root: String,
+    /// Why the root was skipped, e.g. the error resolving it.
+    pub reason: String,
+}
+
+/// The payload of the `structure-manager://ready` event, and the result of `StructureManagerExt::wait_until_ready`:
+/// how the verification of the roots on startup went, see `verifyOnStartup`.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupVerification {
+    /// The roots verified on startup, valid or not, in the order they were verified.
+    pub verified: Vec<String>,
+    /// The error message of each root that failed its verification on startup.
+    pub failed: BTreeMap<String, String>,
+}
+
+/// The payload of the `structure-manager://tamper-detected` event, see `StructureManagerExt::verify_manifest`.
+///
+/// Paths are relative to the root, like [`ManifestEntry::path`].
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct TamperDetected {
+    /// The protected root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// The path of the root.
+    pub path: PathBuf,
+    /// The files whose size or contents changed since the manifest was generated.
+    pub modified: Vec<String>,
+    /// The files recorded by the manifest that no longer exist.
+    pub removed: Vec<String>,
+}
+
+/// What a verification went through.
+#[derive(Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationCounts {
+    /// The number of declared files checked.
+    pub files: usize,
+    /// The number of directories walked, the root included.
+    pub directories: usize,
+    /// The number of entries created or cleaned up by a repair.
+    pub repaired: usize,
+}
+
+/// The payload of the `structure-manager://verification-finished` and `structure-manager://verification-failed` events.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationSummary {
+    /// The identifier of the verification, see [`VerificationStarted::operation_id`].
+    pub operation_id: u64,
+    /// The verified root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// The duration of the verification, in milliseconds.
+    pub duration_ms: u64,
+    pub counts: VerificationCounts,
+    /// The error message of a failed verification.
+    pub error: Option<String>,
+    /// The violations with the `warning` severity, e.g. missing optional entries, which don't fail the verification.
+    pub warnings: Vec<Violation>,
+    /// The violations with the `info` severity, which don't fail the verification.
+    pub info: Vec<Violation>,
+}
+
+/// The statistics of the last verification of a root, see `StructureManagerExt::last_verification_stats`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationStats {
+    /// The identifier of the last verification, see [`VerificationStarted::operation_id`].
+    pub operation_id: u64,
+    /// The duration of the last verification, in milliseconds.
+    pub duration_ms: u64,
+    /// The number of declared files and directories checked by the last verification.
+    pub entries_checked: usize,
+    /// The number of entries created or cleaned up by the last verification.
+    pub repaired: usize,
+    /// The number of warnings of the last verification.
+    pub warnings: usize,
+    /// The error message of the last verification, if it failed.
+    pub error: Option<String>,
+    /// The number of verifications of the root since the app started.
+    pub verifications: u64,
+    /// The number of failed verifications of the root since the app started.
+    pub failures: u64,
+}
+
+/// The entries created by the scaffolding of a root, see `StructureManagerExt::scaffold_all`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ScaffoldSummary {
+    /// The scaffolded root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// The directories that were created, parents first.
+    pub created_directories: Vec<PathBuf>,
+    /// The files that were created.
+    pub created_files: Vec<PathBuf>,
+}
+
+/// The entries deleted by the cleaning of a root, see `StructureManagerExt::clean`.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanSummary {
+    /// The cleaned root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// Whether the entries were only listed, without deleting anything.
+    pub dry_run: bool,
+    /// The deleted entries, a directory with its contents.
+    pub deleted: Vec<PathBuf>,
+    /// The size of the deleted files, in bytes.
+    pub freed: u64,
+}
+
+/// The integrity manifest of a root, recording the size and hash of its declared files,
+/// see `StructureManagerExt::generate_manifest`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// The root of the manifest, see [`VerificationStarted::root`].
+    pub root: String,
+    /// When the manifest was generated, in milliseconds since the Unix epoch.
+    pub generated_at: u64,
+    /// The SHA-256 hash of the structure the manifest was generated for, see [`LastVerification::config_hash`].
+    pub config_hash: String,
+    /// The declared files that existed, sorted by path.
+    pub files: Vec<ManifestEntry>,
+}
+
+/// A file recorded by a [`Manifest`].
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestEntry {
+    /// The path of the file relative to the root, its declared names separated by `/`.
+    pub path: String,
+    /// The size of the file, in bytes.
+    pub size: u64,
+    /// The SHA-256 hash of the file, as a lowercase hexadecimal string.
+    pub sha256: String,
+}
+
+/// The differences between the declared files of a root and its manifest, see `StructureManagerExt::verify_manifest`.
+///
+/// Paths are relative to the root, like [`ManifestEntry::path`], and sorted.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestDiff {
+    /// The root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// The declared files that exist but weren't recorded by the manifest.
+    pub added: Vec<String>,
+    /// The files recorded by the manifest that no longer exist.
+    pub removed: Vec<String>,
+    /// The files whose size or contents changed since the manifest was generated.
+    pub modified: Vec<String>,
+}
+
+impl ManifestDiff {
+    /// Returns whether the files match the manifest.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// The payload of the `structure-manager://download-progress` event, emitted while a file declared with a `url` is
+/// downloaded.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    /// The path the file is downloaded to.
+    pub path: PathBuf,
+    pub url: String,
+    /// The number of bytes downloaded so far, including those of a resumed previous download.
+    pub downloaded: u64,
+    /// The size of the file in bytes, when the server announces it.
+    pub total: Option<u64>,
+}
+
+/// A verification of the history, see `StructureManagerExt::history`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationRecord {
+    /// The identifier of the verification, see [`VerificationStarted::operation_id`]. Identifiers restart with the app.
+    pub operation_id: u64,
+    /// The verified root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// When the verification finished, in milliseconds since the Unix epoch.
+    pub verified_at: u64,
+    /// The duration of the verification, in milliseconds.
+    pub duration_ms: u64,
+    /// Whether the structure was valid (or repaired).
+    pub success: bool,
+    /// The error message of the verification, if it failed.
+    pub error: Option<String>,
+    /// The number of declared files and directories checked.
+    pub entries_checked: usize,
+    /// The number of entries created or cleaned up.
+    pub repaired: usize,
+    /// The number of warnings.
+    pub warnings: usize,
+}
+
+/// The last verification of a root, persisted across restarts with the `store` feature,
+/// see `StructureManagerExt::last_verification`.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LastVerification {
+    /// The verified root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// When the verification finished, in milliseconds since the Unix epoch.
+    pub verified_at: u64,
+    /// Whether the structure was valid (or repaired).
+    pub success: bool,
+    /// The error message of the verification, if it failed.
+    pub error: Option<String>,
+    /// The SHA-256 hash of the verified structure, it changes with the configuration of the root.
+    pub config_hash: String,
+}
+
+/// The status of an entry checked by a verification.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum EntryStatus {
+    /// The entry exists.
+    Ok,
+    /// The entry doesn't exist.
+    Missing,
+    /// The entry was created or cleaned up by a repair.
+    Repaired,
+}
+
+/// A result streamed while a verification is running.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+pub enum VerificationEvent {
+    /// A declared entry was checked.
+    #[serde(rename_all = "camelCase")]
+    Entry {
+        path: PathBuf,
+        kind: EntryKind,
+        status: EntryStatus,
+    },
+    /// The violation that makes the verification fail.
+    Violation(Violation),
+    /// A violation with the `warning` severity, e.g. a missing optional entry, which doesn't fail the verification.
+    Warning(Violation),
+    /// A violation with the `info` severity, which doesn't fail the verification.
+    Info(Violation),
+}
+
+/// What must hold on disk for a repair operation to be applied.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "expect")]
+pub enum RepairPrecondition {
+    /// Nothing exists at the path.
+    Absent,
+    /// A directory exists at the path.
+    Directory,
+    /// A directory containing no files, only empty directories if any, exists at the path.
+    EmptyDirectory,
+    /// A file with this SHA-256 hash (lowercase hexadecimal) exists at the path.
+    #[serde(rename_all = "camelCase")]
+    Sha256 { hash: String },
+}
+
+/// An operation of a repair plan.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", tag = "op")]
+pub enum RepairOperation {
+    /// Creates a declared directory with the `repair` option, applying its ACL template if any.
+    #[serde(rename_all = "camelCase")]
+    CreateDir {
+        path: PathBuf,
+        acl: Option<AclTemplate>,
+        precondition: RepairPrecondition,
+    },
+    /// Renames a legacy directory (see the `movedFrom` option) to a missing declared directory with the `repair`
+    /// option, applying its ACL template if any.
+    #[serde(rename_all = "camelCase")]
+    MoveDir {
+        path: PathBuf,
+        from: PathBuf,
+        acl: Option<AclTemplate>,
+        precondition: RepairPrecondition,
+    },
+    /// Extracts a declared directory with the `repair` and `archive` options from its archive, applying its ACL
+    /// template if any.
+    #[serde(rename_all = "camelCase")]
+    ExtractArchive {
+        path: PathBuf,
+        archive: PathBuf,
+        acl: Option<AclTemplate>,
+        precondition: RepairPrecondition,
+    },
+    /// Copies a file missing from a directory with the `repair` and `seedFrom` options from its seed source.
+    #[serde(rename_all = "camelCase")]
+    CopyFile {
+        path: PathBuf,
+        source: PathBuf,
+        precondition: RepairPrecondition,
+    },
+    /// Creates a declared file with a default `content`, missing from a directory with the `repair` option.
+    #[serde(rename_all = "camelCase")]
+    CreateFile {
+        path: PathBuf,
+        content: FileContent,
+        precondition: RepairPrecondition,
+    },
+    /// Downloads a declared file with a `url`, missing from a directory with the `repair` option.
+    #[serde(rename_all = "camelCase")]
+    DownloadFile {
+        path: PathBuf,
+        url: String,
+        sha256: Option<String>,
+        precondition: RepairPrecondition,
+    },
+    /// Moves an undeclared entry older than the `retention` of its directory into its `archiveTo` directory.
+    #[serde(rename_all = "camelCase")]
+    ArchiveEntry {
+        path: PathBuf,
+        to: PathBuf,
+        precondition: RepairPrecondition,
+    },
+    /// Deletes an undeclared entry of a directory over its `maxChildren` quota with the `deleteOldest` policy, older
+    /// than its `retention`, or an empty directory pruned by `pruneEmptyDirs`.
+    #[serde(rename_all = "camelCase")]
+    Delete {
+        path: PathBuf,
+        precondition: RepairPrecondition,
+    },
+}
+
+/// The ordered operations a repair of a root would apply, to be reviewed or applied out-of-band.
+///
+/// Plans of previous schema versions are converted when deserialized, see [`SCHEMA_VERSION`](crate::SCHEMA_VERSION).
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(
+    rename_all = "camelCase",
+    try_from = "crate::schema::VersionedRepairPlan"
+)]
+pub struct RepairPlan {
+    /// The version of the format, always [`SCHEMA_VERSION`](crate::SCHEMA_VERSION) once deserialized.
+    pub schema_version: u32,
+    /// The planned root, see [`VerificationStarted::root`].
+    pub root: String,
+    pub operations: Vec<RepairOperation>,
+}
+
+/// What happened to an operation of an applied repair plan.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReconciliationStatus {
+    /// The precondition held and the operation was applied.
+    Applied,
+    /// The disk already is in the state the operation leads to, nothing was done.
+    Skipped,
+    /// The precondition no longer holds, or applying the operation failed.
+    Failed,
+}
+
+/// The outcome of an operation of an applied repair plan.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReconciledOperation {
+    pub operation: RepairOperation,
+    pub status: ReconciliationStatus,
+    /// Why the operation was skipped or failed.
+    pub message: Option<String>,
+}
+
+/// The outcome of every operation of an applied repair plan, in plan order.
+///
+/// Reports of previous schema versions are converted when deserialized, see [`SCHEMA_VERSION`](crate::SCHEMA_VERSION).
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(
+    rename_all = "camelCase",
+    try_from = "crate::schema::VersionedReconciliationReport"
+)]
+pub struct ReconciliationReport {
+    /// The version of the format, always [`SCHEMA_VERSION`](crate::SCHEMA_VERSION) once deserialized.
+    pub schema_version: u32,
+    /// The root of the applied plan.
+    pub root: String,
+    /// Whether the plan was only checked, without applying anything.
+    pub dry_run: bool,
+    pub operations: Vec<ReconciledOperation>,
+}
+
+impl ReconciliationReport {
+    /// Returns whether every operation was applied or skipped.
+    pub fn is_success(&self) -> bool {
+        self.operations
+            .iter()
+            .all(|operation| operation.status != ReconciliationStatus::Failed)
+    }
+}
+
+/// The format of a verification report written to a file.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReportFormat {
+    /// Pretty-printed JSON, see [`VerificationReport::to_json`].
+    #[default]
+    Json,
+    /// Plain text meant to be read by people, see [`VerificationReport::to_text`].
+    Text,
+}
+
+/// A declared entry checked by a verification.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportEntry {
+    pub path: PathBuf,
+    pub kind: EntryKind,
+    pub status: EntryStatus,
+}
+
+/// The results of a verification, e.g. to be saved and attached to a support ticket.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationReport {
+    /// The version of the format, see [`SCHEMA_VERSION`](crate::SCHEMA_VERSION).
+    pub schema_version: u32,
+    /// The verified root, see [`VerificationStarted::root`].
+    pub root: String,
+    /// The path of the verified root, unless it couldn't be resolved.
+    pub path: Option<PathBuf>,
+    /// The error message of a failed verification.
+    pub error: Option<String>,
+    /// The declared entries checked, in the order they were checked.
+    pub entries: Vec<ReportEntry>,
+    /// The violations found, whatever their severity.
+    pub violations: Vec<Violation>,
+}
+
+impl VerificationReport {
+    /// Returns whether the verification succeeded.
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// A long-running operation of the plugin.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum OperationKind {
+    Verification,
+    RepairPlan,
+    RepairApply,
+}
+
+/// The payload of the `structure-manager://heartbeat` event.
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OperationHeartbeat {
+    /// The identifier of the operation, unique for the lifetime of the app.
+    pub operation_id: u64,
+    pub operation: OperationKind,
+    /// The root of the operation, see [`VerificationStarted::root`].
+    pub root: String,
+    /// The time since the operation started, in milliseconds.
+    pub elapsed_ms: u64,
+    /// The path the operation was last working on, a path that stays the same across heartbeats hints at a stalled volume.
+    pub current_path: Option<PathBuf>,
+}
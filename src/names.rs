@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::{models::*, roots};
+
+/// Windows device names, reserved with or without an extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// The maximum length of an entry name on most filesystems, in bytes.
+const MAX_NAME_BYTES: usize = 255;
+
+impl StructureConfig {
+    /// Flags the entry names declared by the configuration that are problematic on some platforms or filesystems,
+    /// e.g. colons for the macOS Finder or trailing spaces on Windows.
+    ///
+    /// Every root, definition, contract, custom root and app group is checked, a name may be flagged for several issues.
+    pub fn name_diagnostics(&self) -> Vec<NameDiagnostic> {
+        let mut diagnostics = Vec::new();
+        for (directory, root) in roots::ROOTS.iter().zip(self.roots()) {
+            if let Some(root) = root {
+                root.name_diagnostics(roots::field_name(*directory), &mut diagnostics);
+            }
+        }
+        for (name, item) in self.definitions.iter().flatten() {
+            item.name_diagnostics(&format!("definitions/{}", name), &mut diagnostics);
+        }
+        for (name, contract) in self.contracts.iter().flatten() {
+            contract
+                .structure
+                .name_diagnostics(&format!("contracts/{}", name), &mut diagnostics);
+        }
+        for (name, item) in self.structures.iter().flatten() {
+            item.name_diagnostics(&format!("structures/{}", name), &mut diagnostics);
+        }
+        for (name, root) in self.custom.iter().flatten() {
+            root.structure
+                .name_diagnostics(&format!("custom/{}", name), &mut diagnostics);
+        }
+        for (group, item) in self.app_groups.iter().flatten() {
+            item.name_diagnostics(&format!("appGroups/{}", group), &mut diagnostics);
+        }
+
+        diagnostics
+    }
+}
+
+impl StructureItem {
+    /// Flags the problematic entry names of the item (recursively), see [`StructureConfig::name_diagnostics`].
+    fn name_diagnostics(&self, location: &str, diagnostics: &mut Vec<NameDiagnostic>) {
+        let names: Vec<&String> = self
+            .files
+            .iter()
+            .flatten()
+            .map(|file| &file.name)
+            .chain(self.dirs.iter().flat_map(|dirs| dirs.keys()))
+            .collect();
+
+        let mut lowercase: HashMap<String, &String> = HashMap::new();
+        for name in &names {
+            let entry = format!("{}/{}", location, name);
+            for issue in name_issues(name) {
+                diagnostics.push(diagnostic(&entry, issue));
+            }
+            // A file declared twice isn't a conflict, only names differing by case are.
+            match lowercase.insert(name.to_lowercase(), name) {
+                Some(other) if other != *name => {
+                    diagnostics.push(diagnostic(&entry, NameIssue::CaseConflict));
+                }
+                _ => {}
+            }
+        }
+
+        for (name, dir) in self.dirs.iter().flatten() {
+            dir.name_diagnostics(&format!("{}/{}", location, name), diagnostics);
+        }
+    }
+}
+
+/// Returns the issues of a single entry name, regardless of its siblings.
+fn name_issues(name: &str) -> Vec<NameIssue> {
+    let mut issues = Vec::new();
+    if name.contains(':') {
+        issues.push(NameIssue::Colon);
+    }
+    if name
+        .chars()
+        .any(|c| matches!(c, '<' | '>' | '"' | '/' | '\\' | '|' | '?' | '*') || c.is_control())
+    {
+        issues.push(NameIssue::ReservedCharacter);
+    }
+    let stem = name.split('.').next().unwrap_or_default().trim_end();
+    if RESERVED_NAMES
+        .iter()
+        .any(|reserved| reserved.eq_ignore_ascii_case(stem))
+    {
+        issues.push(NameIssue::ReservedName);
+    }
+    if name.ends_with(' ') || name.ends_with('.') {
+        issues.push(NameIssue::TrailingSpaceOrDot);
+    }
+    if name.chars().any(|c| c as u32 > 0xFFFF) {
+        issues.push(NameIssue::SupplementaryCharacter);
+    }
+    if name.len() > MAX_NAME_BYTES {
+        issues.push(NameIssue::TooLong);
+    }
+
+    issues
+}
+
+fn diagnostic(location: &str, issue: NameIssue) -> NameDiagnostic {
+    let platforms: &[&str] = match issue {
+        NameIssue::Colon => &["macos", "windows"],
+        NameIssue::ReservedCharacter
+        | NameIssue::ReservedName
+        | NameIssue::TrailingSpaceOrDot
+        | NameIssue::SupplementaryCharacter => &["windows"],
+        NameIssue::TooLong => &["windows", "macos", "linux", "android", "ios"],
+        NameIssue::CaseConflict => &["windows", "macos", "ios"],
+    };
+
+    NameDiagnostic {
+        location: location.to_string(),
+        issue,
+        platforms: platforms.iter().map(|p| p.to_string()).collect(),
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    borrow::Cow,
+    collections::HashSet,
+    ffi::{OsStr, OsString},
+    path::{Path, PathBuf},
+};
+
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization as _};
+
+use crate::{long_path::extended, models::*};
+
+impl UnicodeNormalization {
+    /// Returns a name in this normalization form.
+    pub(crate) fn apply(self, name: &str) -> Cow<'_, str> {
+        match self {
+            UnicodeNormalization::Nfc if is_nfc(name) => Cow::Borrowed(name),
+            UnicodeNormalization::Nfc => Cow::Owned(name.nfc().collect()),
+            UnicodeNormalization::Nfd if is_nfd(name) => Cow::Borrowed(name),
+            UnicodeNormalization::Nfd => Cow::Owned(name.nfd().collect()),
+        }
+    }
+}
+
+/// Returns a declared name in the normalization form entries are created with, the name itself without a form.
+pub(crate) fn normalize(name: &str, form: Option<UnicodeNormalization>) -> Cow<'_, str> {
+    match form {
+        Some(form) => form.apply(name),
+        None => Cow::Borrowed(name),
+    }
+}
+
+/// Returns whether two entry names are the same, ignoring their normalization when a form is set.
+pub(crate) fn same_name(a: &str, b: &str, form: Option<UnicodeNormalization>) -> bool {
+    match form {
+        Some(form) => a == b || form.apply(a) == form.apply(b),
+        None => a == b,
+    }
+}
+
+/// The names of the entries of a directory, read once so its declared entries are located without a system call
+/// each, see `locate_in`.
+pub(crate) struct Listing(HashSet<OsString>);
+
+impl Listing {
+    /// Lists a directory, returning the error if it can't be read, e.g. when it doesn't exist.
+    pub(crate) fn read(dir: &Path) -> std::io::Result<Self> {
+        std::fs::read_dir(extended(dir))?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<std::io::Result<_>>()
+            .map(Self)
+    }
+
+    /// Returns the names of the entries of the directory when it was listed.
+    pub(crate) fn names(&self) -> impl Iterator<Item = &OsString> {
+        self.0.iter()
+    }
+}
+
+/// Returns the path of a declared entry of a directory as it is stored on disk, like `locate`, matching its name
+/// against a listing of the directory when there is one.
+///
+/// A name missing from the listing is still looked up on disk, as the entry may have been created since the
+/// directory was listed, or be stored in another case on a case-insensitive filesystem.
+pub(crate) fn locate_in(
+    listing: Option<&Listing>,
+    dir: &Path,
+    name: &str,
+    form: Option<UnicodeNormalization>,
+) -> Option<PathBuf> {
+    let Some(listing) = listing else {
+        return locate(dir, name, form);
+    };
+    if listing.0.contains(OsStr::new(name)) {
+        return Some(dir.join(name));
+    }
+    if form.is_some() && !name.is_ascii() {
+        let stored = listing.names().find(|entry_name| {
+            entry_name
+                .to_str()
+                .is_some_and(|entry_name| same_name(entry_name, name, form))
+        });
+        if let Some(stored) = stored {
+            return Some(dir.join(stored));
+        }
+    }
+
+    let path = dir.join(name);
+    extended(&path).exists().then_some(path)
+}
+
+/// Returns the path of a declared entry of a directory as it is stored on disk, or `None` if it doesn't exist.
+///
+/// With a normalization form, an entry whose name only differs from `name` by its normalization is found too,
+/// e.g. `café` stored decomposed by macOS and declared precomposed in the configuration.
+pub(crate) fn locate(
+    dir: &Path,
+    name: &str,
+    form: Option<UnicodeNormalization>,
+) -> Option<PathBuf> {
+    let path = dir.join(name);
+    if extended(&path).exists() {
+        return Some(path);
+    }
+    // ASCII names are the same in every form, the directory isn't read for them.
+    if form.is_none() || name.is_ascii() {
+        return None;
+    }
+
+    std::fs::read_dir(extended(dir))
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.file_name())
+        .find(|entry_name| {
+            entry_name
+                .to_str()
+                .is_some_and(|entry_name| same_name(entry_name, name, form))
+        })
+        .map(|entry_name| dir.join(entry_name))
+}
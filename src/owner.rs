@@ -0,0 +1,129 @@
+use std::path::Path;
+
+/// Returns the owner and group of an existing entry that differ from the expected ones, as `(name, expected value)`,
+/// e.g. `("owner", "www-data")`.
+///
+/// On Unix, users and groups are either names, resolved with the user and group databases, or numeric ids. On other
+/// platforms nothing differs.
+#[cfg(unix)]
+pub(crate) fn mismatches<'a>(
+    path: &Path,
+    owner: Option<&'a str>,
+    group: Option<&'a str>,
+) -> std::result::Result<Vec<(&'static str, &'a str)>, String> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = std::fs::metadata(crate::long_path::extended(path))
+        .map_err(|e| format!("Failed to read the owner: {:?}, error: {:?}", path, e))?;
+
+    let mut mismatches = Vec::new();
+    if let Some(owner) = owner {
+        if user_id(owner)? != metadata.uid() {
+            mismatches.push(("owner", owner));
+        }
+    }
+    if let Some(group) = group {
+        if group_id(group)? != metadata.gid() {
+            mismatches.push(("group", group));
+        }
+    }
+
+    Ok(mismatches)
+}
+
+/// Returns the owner and group of an existing entry that differ from the expected ones, as `(name, expected value)`,
+/// e.g. `("owner", "www-data")`.
+///
+/// On Unix, users and groups are either names, resolved with the user and group databases, or numeric ids. On other
+/// platforms nothing differs.
+#[cfg(not(unix))]
+pub(crate) fn mismatches<'a>(
+    _path: &Path,
+    _owner: Option<&'a str>,
+    _group: Option<&'a str>,
+) -> std::result::Result<Vec<(&'static str, &'a str)>, String> {
+    Ok(Vec::new())
+}
+
+/// Sets the expected owner and group of an existing entry.
+///
+/// On Unix, the owner and group are set using `chown`, which needs adequate privileges: usually root to change the
+/// owner, and membership of the group to change the group. On other platforms this is a no-op.
+#[cfg(unix)]
+pub(crate) fn apply(
+    path: &Path,
+    owner: Option<&str>,
+    group: Option<&str>,
+) -> std::result::Result<(), String> {
+    use std::process::Command;
+
+    let spec = match (owner, group) {
+        (Some(owner), Some(group)) => format!("{}:{}", owner, group),
+        (Some(owner), None) => owner.to_string(),
+        (None, Some(group)) => format!(":{}", group),
+        (None, None) => return Ok(()),
+    };
+    let output = Command::new("chown")
+        .arg(spec)
+        .arg(path)
+        .output()
+        .map_err(|e| format!("Failed to run chown on {:?}, error: {:?}", path, e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "Failed to change the owner of {:?}: {}",
+            path,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    Ok(())
+}
+
+/// Sets the expected owner and group of an existing entry.
+///
+/// On Unix, the owner and group are set using `chown`, which needs adequate privileges: usually root to change the
+/// owner, and membership of the group to change the group. On other platforms this is a no-op.
+#[cfg(not(unix))]
+pub(crate) fn apply(
+    path: &Path,
+    owner: Option<&str>,
+    group: Option<&str>,
+) -> std::result::Result<(), String> {
+    log::debug!(
+        "Ignoring owner {:?} and group {:?} for {:?} on this platform",
+        owner,
+        group,
+        path
+    );
+    Ok(())
+}
+
+/// Returns the id of a user, its name or its numeric id.
+#[cfg(unix)]
+pub(crate) fn user_id(user: &str) -> std::result::Result<u32, String> {
+    user.parse()
+        .ok()
+        .or_else(|| uzers::get_user_by_name(user).map(|user| user.uid()))
+        .ok_or_else(|| format!("Unknown user: {:?}", user))
+}
+
+#[cfg(unix)]
+fn group_id(group: &str) -> std::result::Result<u32, String> {
+    group
+        .parse()
+        .ok()
+        .or_else(|| uzers::get_group_by_name(group).map(|group| group.gid()))
+        .ok_or_else(|| format!("Unknown group: {:?}", group))
+}
+
+/// Describes the expected owner and group of mismatched entries, e.g. `owned by www-data, in group staff`.
+pub(crate) fn describe(mismatches: &[(&str, &str)]) -> String {
+    mismatches
+        .iter()
+        .map(|(name, value)| match *name {
+            "owner" => format!("owned by {}", value),
+            _ => format!("in group {}", value),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
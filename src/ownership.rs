@@ -0,0 +1,48 @@
+use tauri::path::BaseDirectory;
+
+/// Entries left by Chromium-based software (e.g. Electron apps) at the root of their data directories.
+///
+/// The webview of a Tauri app keeps its own data in a dedicated subdirectory, so these entries are never ours.
+const CHROMIUM_ENTRIES: [&str; 8] = [
+    "Local State",
+    "Local Storage",
+    "Session Storage",
+    "IndexedDB",
+    "GPUCache",
+    "Code Cache",
+    "Crashpad",
+    "blob_storage",
+];
+
+/// Returns whether an `exclusive` root can be declared for a base directory, i.e. whether it is app-scoped.
+pub(crate) fn is_app_scoped(directory: BaseDirectory) -> bool {
+    matches!(
+        directory,
+        BaseDirectory::AppCache
+            | BaseDirectory::AppConfig
+            | BaseDirectory::AppData
+            | BaseDirectory::AppLocalData
+            | BaseDirectory::AppLog
+    )
+}
+
+/// Returns why an entry of an app-scoped root clearly belongs to other software, if it does.
+///
+/// # Arguments
+///
+/// * `name` - The name of an undeclared entry at the top of the root.
+/// * `package_name` - The package name of this app.
+pub(crate) fn foreign_reason(name: &str, package_name: &str) -> Option<String> {
+    if CHROMIUM_ENTRIES.contains(&name) {
+        return Some("left by a Chromium-based app".to_string());
+    }
+
+    // Log files are named after the product writing them.
+    if let Some(stem) = name.strip_suffix(".log") {
+        if !stem.eq_ignore_ascii_case(package_name) {
+            return Some(format!("log file of `{}`", stem));
+        }
+    }
+
+    None
+}
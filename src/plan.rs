@@ -0,0 +1,607 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{Manager, Runtime};
+
+use crate::{
+    conditions::is_active,
+    hash::sha256_file,
+    heartbeat::Monitor,
+    hooks::Hooks,
+    long_path::extended,
+    models::*,
+    normalization::{locate, normalize},
+    repair_scope::RepairScope,
+    seed,
+    tuning::ResolvedIoTuning,
+    verify::{
+        archive_entry, empty_dirs, entries_excess, expired_entries, is_empty_dir, quota_excess,
+        remove_empty_dir, size_excess,
+    },
+};
+
+/// Plans the repair of a structure item without touching the disk, in the order a verification would apply it.
+pub(crate) struct Planner<'a, R: Runtime, M: Manager<R>> {
+    manager: &'a M,
+    root: PathBuf,
+    tuning: ResolvedIoTuning,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: Vec<PathBuf>,
+    operations: Vec<RepairOperation>,
+    monitor: Option<Monitor>,
+    _runtime: std::marker::PhantomData<R>,
+}
+
+impl<'a, R: Runtime, M: Manager<R>> Planner<'a, R, M> {
+    /// Creates a planner for the root item of a plan, see `Walker::new`.
+    pub(crate) fn new(manager: &'a M, path: &Path, root: &StructureItem) -> Self {
+        let tuning = root
+            .options
+            .as_ref()
+            .and_then(|options| options.tuning.clone())
+            .unwrap_or_default()
+            .resolve(path);
+
+        Self {
+            manager,
+            root: path.to_path_buf(),
+            tuning,
+            normalization: root.unicode_normalization(),
+            nested_roots: Vec::new(),
+            operations: Vec::new(),
+            monitor: None,
+            _runtime: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the paths of the other configured roots nested inside the planned root, see `Walker::with_nested_roots`.
+    pub(crate) fn with_nested_roots(mut self, nested_roots: Vec<PathBuf>) -> Self {
+        self.nested_roots = nested_roots;
+        self
+    }
+
+    /// Plans the repair of `path` (the root `root`), returning the planned operations.
+    pub(crate) fn plan(
+        mut self,
+        root: &str,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<Vec<RepairOperation>, String> {
+        self.monitor = Some(Monitor::start(
+            self.manager,
+            OperationKind::RepairPlan,
+            root,
+        ));
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("repair_plan", root).entered();
+
+        self.plan_item(path, structure_item, extended(path).is_dir(), None)?;
+        #[cfg(feature = "tracing")]
+        tracing::info!(operations = self.operations.len(), "repair planned");
+        Ok(self.operations)
+    }
+
+    /// Adds an operation to the plan, unless an entry is already planned at its path, e.g. a declared directory
+    /// also copied from a seed source.
+    fn push(&mut self, operation: RepairOperation) {
+        if !self
+            .operations
+            .iter()
+            .any(|planned| planned.path() == operation.path())
+        {
+            self.operations.push(operation);
+        }
+    }
+
+    /// Plans the repair of an item, `exists` is false when the directory of the item is itself planned to be created.
+    ///
+    /// At most `depth` levels of directories are descended, see `Walker::verify`.
+    fn plan_item(
+        &mut self,
+        path: &Path,
+        structure_item: &StructureItem,
+        exists: bool,
+        depth: Option<usize>,
+    ) -> std::result::Result<(), String> {
+        if !is_active(self.manager, structure_item)? {
+            return Ok(());
+        }
+        if let Some(monitor) = &self.monitor {
+            monitor.at(path);
+        }
+
+        let depth = structure_item.depth_limit(depth);
+        // Below the depth limit, directories are only checked for existence.
+        let descend = depth != Some(0);
+        let below = depth.map(|depth| depth.saturating_sub(1));
+
+        let repair = structure_item
+            .options
+            .as_ref()
+            .and_then(|options| options.repair)
+            == Some(true);
+        if let (true, Some(seed_from)) = (repair, structure_item.seed_from()) {
+            let source = seed::source_of(self.manager, seed_from, &self.root, path)?;
+            // Below a planned directory, nothing exists yet and every entry of the source is planned.
+            let entries = seed::missing_entries(&source, path)?;
+            for entry in entries {
+                self.push(match entry.kind {
+                    EntryKind::Directory => RepairOperation::CreateDir {
+                        path: entry.target,
+                        acl: None,
+                        precondition: RepairPrecondition::Absent,
+                    },
+                    EntryKind::File => RepairOperation::CopyFile {
+                        path: entry.target,
+                        source: entry.source,
+                        precondition: RepairPrecondition::Absent,
+                    },
+                });
+            }
+        }
+        if repair {
+            for file in structure_item.files.iter().flatten() {
+                if exists && locate(path, &file.name, self.normalization).is_some() {
+                    continue;
+                }
+                let file_path = path.join(&*normalize(&file.name, self.normalization));
+                let options = &file.options;
+                self.push(match (&options.content, &options.url) {
+                    (Some(content), _) => RepairOperation::CreateFile {
+                        path: file_path,
+                        content: content.clone(),
+                        precondition: RepairPrecondition::Absent,
+                    },
+                    (None, Some(url)) => RepairOperation::DownloadFile {
+                        path: file_path,
+                        url: url.clone(),
+                        sha256: options.sha256.clone(),
+                        precondition: RepairPrecondition::Absent,
+                    },
+                    (None, None) => continue,
+                });
+            }
+        }
+
+        for (dir_name, dir) in structure_item.dirs.iter().flatten() {
+            if !is_active(self.manager, dir)? {
+                continue;
+            }
+
+            let dir_path = path.join(dir_name);
+            if self.nested_roots.contains(&dir_path) {
+                continue;
+            }
+
+            if let Some(dir_path) = exists
+                .then(|| locate(path, dir_name, self.normalization))
+                .flatten()
+            {
+                let redirected = dir.reparse_point_policy() != ReparsePointPolicy::Follow
+                    && crate::attributes::is_reparse_point(&dir_path);
+                if descend && !redirected {
+                    self.plan_item(&dir_path, dir, true, below)?;
+                }
+                continue;
+            }
+
+            // A missing directory without `repair` fails the verification, nothing below it can be repaired.
+            let options = dir.options.as_ref();
+            if options.and_then(|options| options.repair) == Some(true) {
+                let dir_path = path.join(&*normalize(dir_name, self.normalization));
+                let acl = options.and_then(|options| options.acl);
+                let legacy = crate::legacy::locate(path, dir, self.normalization);
+                let archive = options.and_then(|options| options.archive.as_deref());
+                self.push(match (legacy, archive) {
+                    (Some(from), _) => RepairOperation::MoveDir {
+                        path: dir_path.clone(),
+                        from,
+                        acl,
+                        precondition: RepairPrecondition::Absent,
+                    },
+                    (None, Some(archive)) => RepairOperation::ExtractArchive {
+                        path: dir_path.clone(),
+                        archive: crate::archive::source_of(self.manager, archive)?,
+                        acl,
+                        precondition: RepairPrecondition::Absent,
+                    },
+                    (None, None) => RepairOperation::CreateDir {
+                        path: dir_path.clone(),
+                        acl,
+                        precondition: RepairPrecondition::Absent,
+                    },
+                });
+                if descend {
+                    self.plan_item(&dir_path, dir, false, below)?;
+                }
+            }
+        }
+
+        if let Some(options) = &structure_item.options {
+            if let (true, Some(true)) = (exists, options.prune_empty_dirs) {
+                for dir_path in
+                    empty_dirs(path, structure_item, self.normalization, &self.nested_roots)?
+                {
+                    self.operations.push(RepairOperation::Delete {
+                        path: dir_path,
+                        precondition: RepairPrecondition::EmptyDirectory,
+                    });
+                }
+            }
+
+            if let (true, Some(retention)) = (exists, &options.retention) {
+                let expired = expired_entries(
+                    path,
+                    structure_item,
+                    retention,
+                    self.normalization,
+                    &self.nested_roots,
+                )?;
+                match &retention.archive_to {
+                    Some(archive_to) => {
+                        for entry_path in expired {
+                            let Some(name) = entry_path.file_name() else {
+                                continue;
+                            };
+                            let to = path.join(archive_to).join(name);
+                            let precondition = self.unchanged(&entry_path)?;
+                            self.operations.push(RepairOperation::ArchiveEntry {
+                                path: entry_path,
+                                to,
+                                precondition,
+                            });
+                        }
+                    }
+                    None => self.plan_deletes(&expired)?,
+                }
+            }
+
+            if let (true, Some(max_children), Some(QuotaPolicy::DeleteOldest)) =
+                (exists, options.max_children, options.quota_policy)
+            {
+                let excess = quota_excess(
+                    path,
+                    structure_item,
+                    max_children,
+                    self.normalization,
+                    &self.nested_roots,
+                )?;
+                if let Some(deleted) = excess.as_ref().and_then(|excess| excess.deletable()) {
+                    self.plan_deletes(deleted)?;
+                }
+            }
+
+            if let (true, Some(max_entries), Some(QuotaPolicy::DeleteOldest)) =
+                (exists, options.max_entries, options.quota_policy)
+            {
+                let excess = entries_excess(
+                    path,
+                    structure_item,
+                    max_entries,
+                    self.normalization,
+                    &self.nested_roots,
+                )?;
+                if let Some(deleted) = excess.and_then(|excess| excess.deletable()) {
+                    self.plan_deletes(&deleted)?;
+                }
+            }
+
+            if let (true, Some(max_total_size), Some(QuotaPolicy::DeleteOldest)) =
+                (exists, options.max_total_size, options.quota_policy)
+            {
+                let excess = size_excess(
+                    path,
+                    structure_item,
+                    max_total_size,
+                    self.normalization,
+                    &self.nested_roots,
+                )?;
+                if let Some(deleted) = excess.and_then(|excess| excess.deletable()) {
+                    self.plan_deletes(&deleted)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Plans the deletion of the entries selected by a quota policy, guarded by their current content.
+    fn plan_deletes(&mut self, deleted: &[PathBuf]) -> std::result::Result<(), String> {
+        for entry_path in deleted {
+            let precondition = self.unchanged(entry_path)?;
+            self.operations.push(RepairOperation::Delete {
+                path: entry_path.clone(),
+                precondition,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Returns the precondition that an existing entry is unchanged: a directory, or a file with the same content.
+    fn unchanged(&self, entry_path: &Path) -> std::result::Result<RepairPrecondition, String> {
+        if extended(entry_path).is_dir() {
+            return Ok(RepairPrecondition::Directory);
+        }
+        let hash = sha256_file(entry_path, &self.tuning)
+            .map_err(|e| format!("Failed to hash file: {:?}, error: {:?}", entry_path, e))?;
+        Ok(RepairPrecondition::Sha256 { hash })
+    }
+}
+
+/// Applies the operations of a plan in order, checking the precondition of each one first.
+///
+/// Operations are independent: a skipped or failed operation doesn't stop the following ones.
+/// With `dry_run`, only the preconditions are checked and `applied` means the operation would be applied.
+pub(crate) fn apply<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    plan: &RepairPlan,
+    dry_run: bool,
+) -> ReconciliationReport {
+    let hooks = manager.state::<Hooks>();
+    let repair_scope = manager.state::<RepairScope>();
+    let monitor = Monitor::start(manager, OperationKind::RepairApply, &plan.root);
+    #[cfg(feature = "tracing")]
+    let _span = tracing::info_span!("repair_apply", root = %plan.root, dry_run).entered();
+
+    // Nothing is applied when the volume can't hold the files the plan writes.
+    let insufficient_space = plan.operations.first().and_then(|operation| {
+        crate::space::check(
+            operation.path(),
+            crate::space::operations_size(&plan.operations),
+        )
+        .err()
+    });
+
+    let operations = plan
+        .operations
+        .iter()
+        .map(|operation| {
+            monitor.at(operation.path());
+            let result = match &insufficient_space {
+                Some(e) => Err(e.to_string()),
+                None => apply_operation(manager, operation, &repair_scope, dry_run),
+            };
+            let (status, message) = match result {
+                Ok(None) => {
+                    if !dry_run {
+                        hooks.repaired(operation.path());
+                    }
+                    (ReconciliationStatus::Applied, None)
+                }
+                Ok(Some(reason)) => (ReconciliationStatus::Skipped, Some(reason)),
+                Err(reason) => (ReconciliationStatus::Failed, Some(reason)),
+            };
+
+            ReconciledOperation {
+                operation: operation.clone(),
+                status,
+                message,
+            }
+        })
+        .collect();
+
+    ReconciliationReport {
+        schema_version: crate::SCHEMA_VERSION,
+        root: plan.root.clone(),
+        dry_run,
+        operations,
+    }
+}
+
+/// Applies an operation if its precondition holds and the repair scope allows it, unless `dry_run` is set.
+///
+/// # Returns
+///
+/// Returns `Ok(None)` if the operation was applied, `Ok(Some(reason))` if it was skipped as the disk already is in
+/// the expected state, or `Err(reason)` if the precondition no longer holds or applying the operation failed.
+fn apply_operation<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    operation: &RepairOperation,
+    repair_scope: &RepairScope,
+    dry_run: bool,
+) -> std::result::Result<Option<String>, String> {
+    repair_scope.check(None, operation.path())?;
+    if !dry_run {
+        // The volume may have filled up since the plan was checked as a whole, e.g. by the previous operations.
+        crate::space::check(operation.path(), crate::space::operation_size(operation))
+            .map_err(|e| e.to_string())?;
+    }
+    match operation {
+        RepairOperation::MoveDir {
+            path,
+            from,
+            acl,
+            precondition,
+        } => {
+            if extended(path).is_dir() {
+                return Ok(Some(format!("Directory already exists: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            repair_scope.check(None, from)?;
+            if !extended(from).is_dir() {
+                return Err(format!("Legacy directory not found: {:?}", from));
+            }
+            if dry_run {
+                return Ok(None);
+            }
+
+            crate::legacy::rename(from, path)?;
+            if let Some(template) = acl {
+                crate::acl::apply(path, *template)?;
+            }
+        }
+        RepairOperation::CreateDir {
+            path,
+            acl,
+            precondition,
+        } => {
+            if extended(path).is_dir() {
+                return Ok(Some(format!("Directory already exists: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            if dry_run {
+                return Ok(None);
+            }
+
+            IoTuning::default()
+                .resolve(path)
+                .retry(|| std::fs::create_dir_all(extended(path)))
+                .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", path, e))?;
+            if let Some(template) = acl {
+                crate::acl::apply(path, *template)?;
+            }
+        }
+        RepairOperation::ExtractArchive {
+            path,
+            archive,
+            acl,
+            precondition,
+        } => {
+            if extended(path).is_dir() {
+                return Ok(Some(format!("Directory already exists: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            if dry_run {
+                return Ok(None);
+            }
+
+            crate::archive::extract(archive, path)?;
+            if let Some(template) = acl {
+                crate::acl::apply(path, *template)?;
+            }
+        }
+        RepairOperation::CopyFile {
+            path,
+            source,
+            precondition,
+        } => {
+            if std::fs::symlink_metadata(extended(path)).is_ok() {
+                return Ok(Some(format!("File already exists: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            if dry_run {
+                return Ok(None);
+            }
+
+            seed::copy(source, path, EntryKind::File)?;
+        }
+        RepairOperation::CreateFile {
+            path,
+            content,
+            precondition,
+        } => {
+            if std::fs::symlink_metadata(extended(path)).is_ok() {
+                return Ok(Some(format!("File already exists: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            if dry_run {
+                return Ok(None);
+            }
+
+            crate::content::create_file(path, Some(content), &IoTuning::default().resolve(path))?;
+        }
+        RepairOperation::DownloadFile {
+            path,
+            url,
+            sha256,
+            precondition,
+        } => {
+            if std::fs::symlink_metadata(extended(path)).is_ok() {
+                return Ok(Some(format!("File already exists: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            if dry_run {
+                return Ok(None);
+            }
+
+            crate::download::download(manager, path, url, sha256.as_deref())?;
+        }
+        RepairOperation::ArchiveEntry {
+            path,
+            to,
+            precondition,
+        } => {
+            if std::fs::symlink_metadata(extended(path)).is_err() && extended(to).exists() {
+                return Ok(Some(format!("Entry already archived: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            repair_scope.check(None, to)?;
+            if dry_run {
+                return Ok(None);
+            }
+
+            archive_entry(path, to)?;
+        }
+        RepairOperation::Delete { path, precondition } => {
+            if std::fs::symlink_metadata(extended(path)).is_err() {
+                return Ok(Some(format!("Entry already deleted: {:?}", path)));
+            }
+            check_precondition(path, precondition)?;
+            if dry_run {
+                return Ok(None);
+            }
+
+            // Files added to a pruned directory since the plan was computed are never deleted with it.
+            let result = match precondition {
+                RepairPrecondition::EmptyDirectory => remove_empty_dir(path),
+                _ if extended(path).is_dir() => std::fs::remove_dir_all(extended(path)),
+                _ => std::fs::remove_file(extended(path)),
+            };
+            result.map_err(|e| format!("Failed to delete: {:?}, error: {:?}", path, e))?;
+        }
+    }
+
+    Ok(None)
+}
+
+/// Verifies that a precondition still holds, the disk may have changed since the plan was computed.
+fn check_precondition(
+    path: &Path,
+    precondition: &RepairPrecondition,
+) -> std::result::Result<(), String> {
+    match precondition {
+        RepairPrecondition::Absent if std::fs::symlink_metadata(extended(path)).is_ok() => {
+            Err(format!("Precondition failed, entry exists: {:?}", path))
+        }
+        RepairPrecondition::Directory if !extended(path).is_dir() => {
+            Err(format!("Precondition failed, not a directory: {:?}", path))
+        }
+        RepairPrecondition::EmptyDirectory if !is_empty_dir(path) => Err(format!(
+            "Precondition failed, not an empty directory: {:?}",
+            path
+        )),
+        RepairPrecondition::Sha256 { hash } => {
+            let tuning = IoTuning::default().resolve(path);
+            let actual = sha256_file(path, &tuning)
+                .map_err(|e| format!("Failed to hash file: {:?}, error: {:?}", path, e))?;
+            if actual != *hash {
+                return Err(format!("Precondition failed, file changed: {:?}", path));
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+impl RepairOperation {
+    /// Returns the path the operation applies to.
+    pub fn path(&self) -> &Path {
+        match self {
+            RepairOperation::MoveDir { path, .. } => path,
+            RepairOperation::CreateDir { path, .. } => path,
+            RepairOperation::ExtractArchive { path, .. } => path,
+            RepairOperation::CopyFile { path, .. } => path,
+            RepairOperation::CreateFile { path, .. } => path,
+            RepairOperation::DownloadFile { path, .. } => path,
+            RepairOperation::ArchiveEntry { path, .. } => path,
+            RepairOperation::Delete { path, .. } => path,
+        }
+    }
+}
+
+impl RepairPlan {
+    /// Serializes the plan as pretty-printed JSON.
+    pub fn to_json(&self) -> std::result::Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize plan: {}", e))
+    }
+}
@@ -0,0 +1,313 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use jsonschema::JSONSchema;
+use tauri::path::BaseDirectory;
+
+use crate::{
+    hooks::Hooks,
+    messages::Translation,
+    models::*,
+    repair_scope::RepairScope,
+    roots::{self, RootSelector},
+    validators::Validators,
+    verify::{Host, Walker},
+};
+
+/// A read-only verification that runs before the Tauri app is built, e.g. from a splash or bootstrap phase
+/// deciding whether to show a recovery screen instead of initializing the main window.
+///
+/// Base directories are resolved from the app identifier like Tauri does, except for `resource`, which needs the
+/// running app, and the `appDirectoriesOverride` setting, which isn't applied. Roots are walked like the plugin does,
+/// assuming its repairs: nothing is written to disk, and what `repair` fixes once the plugin is set up is accepted,
+/// e.g. missing directories, missing files with a default `content` and mismatched attributes, and `deleteOldest`
+/// quotas are only reported when they can't be enforced. Missing optional entries and violations with a `warning` or
+/// `info` severity are accepted too. Validators, JSON Schemas, hooks, events and `exclusive` checks are skipped, and
+/// named conditions are all unset.
+///
+/// ```ignore
+/// let context = tauri::generate_context!();
+/// let preflight = tauri_plugin_structure_manager::Preflight::from_config(context.config())?;
+/// if let Err(e) = preflight.verify_all() {
+///     // Show the recovery screen.
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Preflight {
+    identifier: String,
+    version: semver::Version,
+    structure_config: StructureConfig,
+    features: HashSet<String>,
+    profile: Option<String>,
+}
+
+impl Preflight {
+    /// Creates a read-only verification of a structure configuration for the app with the given identifier.
+    ///
+    /// The app version defaults to `0.0.0`, see [`Preflight::version`].
+    pub fn new(identifier: impl Into<String>, structure_config: StructureConfig) -> Self {
+        Self {
+            identifier: identifier.into(),
+            version: semver::Version::new(0, 0, 0),
+            structure_config,
+            features: HashSet::new(),
+            profile: None,
+        }
+    }
+
+    /// Creates a read-only verification from the Tauri configuration of the app, using its identifier, its version
+    /// and the `plugins.structure-manager` configuration.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Err(String)` if the structure configuration or the version can't be parsed.
+    pub fn from_config(config: &tauri::Config) -> std::result::Result<Self, String> {
+        let structure_config = match config
+            .plugins
+            .0
+            .get("structure-manager")
+            .filter(|value| !value.is_null())
+        {
+            Some(value) => serde_json::from_value(value.clone()).map_err(|e| {
+                format!(
+                    "Invalid structure configuration \"plugins.structure-manager\": {}",
+                    e
+                )
+            })?,
+            None => StructureConfig::default(),
+        };
+
+        let mut preflight = Self::new(config.identifier.clone(), structure_config);
+        if let Some(version) = &config.version {
+            preflight.version = semver::Version::parse(version)
+                .map_err(|e| format!("Invalid version: {:?}, error: {:?}", version, e))?;
+        }
+
+        Ok(preflight)
+    }
+
+    /// Sets the app version `when` clauses are evaluated against.
+    pub fn version(mut self, version: semver::Version) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Enables a feature flag, see `Builder::feature`.
+    pub fn feature(mut self, name: impl Into<String>) -> Self {
+        self.features.insert(name.into());
+        self
+    }
+
+    /// Applies a profile of the configuration, see `Builder::profile`.
+    pub fn profile(mut self, name: impl Into<String>) -> Self {
+        self.profile = Some(name.into());
+        self
+    }
+
+    /// Verifies the structure of a configured root without repairing it.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root to be verified, a base directory or the name of a custom root.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the directory structure is valid or would be repaired by the plugin, or `Err(String)`
+    /// with an error message otherwise.
+    pub fn verify(&self, root: impl Into<RootSelector>) -> std::result::Result<(), String> {
+        let structure_config = self.resolved()?;
+        let (path, structure_item) = roots::select(
+            &structure_config,
+            &root.into(),
+            |directory| self.resolve(directory),
+            |group| {
+                Err(format!(
+                    "App group `{}` can't be verified before setup: its container is resolved by the app",
+                    group
+                ))
+            },
+        )?;
+        self.check_root(&structure_config, &path, structure_item)
+    }
+
+    /// Verifies every configured root, built-in and custom, without repairing them.
+    ///
+    /// App groups are skipped, their containers can only be resolved by the running app.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if every root is valid or would be repaired by the plugin, or `Err(String)` with the error
+    /// message of the first invalid root.
+    pub fn verify_all(&self) -> std::result::Result<(), String> {
+        let structure_config = self.resolved()?;
+        for directory in roots::ROOTS {
+            if let Some(structure_item) = structure_config.root(directory) {
+                let path = self.resolve(directory)?;
+                self.check_root(&structure_config, &path, structure_item)?;
+            }
+        }
+        for name in structure_config
+            .custom
+            .iter()
+            .flat_map(|custom| custom.keys())
+        {
+            self.verify(name.as_str())?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the configuration with the profile applied and the `$ref`s resolved.
+    fn resolved(&self) -> std::result::Result<StructureConfig, String> {
+        let mut structure_config = self.structure_config.clone();
+        if let Some(profile) = &self.profile {
+            structure_config.apply_profile(profile)?;
+        }
+        structure_config.resolve_refs()?;
+        Ok(structure_config)
+    }
+
+    /// Resolves the path of a base directory without the app, see `roots::resolve`.
+    fn resolve(&self, directory: BaseDirectory) -> std::result::Result<PathBuf, String> {
+        let app_dir = |dir: Option<PathBuf>| dir.map(|dir| dir.join(&self.identifier));
+        let (path, label) = match directory {
+            BaseDirectory::AppCache => (app_dir(dirs::cache_dir()), "app cache"),
+            BaseDirectory::AppConfig => (app_dir(dirs::config_dir()), "app config"),
+            BaseDirectory::AppData => (app_dir(dirs::data_dir()), "app data"),
+            BaseDirectory::AppLocalData => (app_dir(dirs::data_local_dir()), "app local data"),
+            #[cfg(target_os = "macos")]
+            BaseDirectory::AppLog => (
+                app_dir(dirs::home_dir().map(|dir| dir.join("Library/Logs"))),
+                "app log",
+            ),
+            #[cfg(not(target_os = "macos"))]
+            BaseDirectory::AppLog => (
+                app_dir(dirs::data_local_dir()).map(|dir| dir.join("logs")),
+                "app log",
+            ),
+            BaseDirectory::Audio => (dirs::audio_dir(), "audio"),
+            BaseDirectory::Cache => (dirs::cache_dir(), "cache"),
+            BaseDirectory::Config => (dirs::config_dir(), "config"),
+            BaseDirectory::Data => (dirs::data_dir(), "data"),
+            BaseDirectory::Desktop => (dirs::desktop_dir(), "desktop"),
+            BaseDirectory::Document => (dirs::document_dir(), "document"),
+            BaseDirectory::Download => (dirs::download_dir(), "download"),
+            BaseDirectory::Executable => (dirs::executable_dir(), "executable"),
+            BaseDirectory::Font => (dirs::font_dir(), "font"),
+            BaseDirectory::Home => (dirs::home_dir(), "home"),
+            BaseDirectory::LocalData => (dirs::data_local_dir(), "local data"),
+            BaseDirectory::Picture => (dirs::picture_dir(), "picture"),
+            BaseDirectory::Public => (dirs::public_dir(), "public"),
+            BaseDirectory::Runtime => (dirs::runtime_dir(), "runtime"),
+            BaseDirectory::Temp => (Some(std::env::temp_dir()), "temp"),
+            BaseDirectory::Template => (dirs::template_dir(), "template"),
+            BaseDirectory::Video => (dirs::video_dir(), "video"),
+            _ => {
+                return Err(format!(
+                    "Base directory can't be resolved before setup: {:?}",
+                    directory
+                ))
+            }
+        };
+
+        path.ok_or_else(|| format!("Failed to resolve {} path", label))
+    }
+
+    /// Verifies a root, leaving out the other configured roots nested inside it.
+    fn check_root(
+        &self,
+        structure_config: &StructureConfig,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<(), String> {
+        let nested_roots =
+            roots::nested_within(structure_config, path, |directory| self.resolve(directory));
+        let host = Offline {
+            preflight: self,
+            hooks: Hooks::default(),
+            translation: Translation::default(),
+            repair_scope: RepairScope::default(),
+        };
+        Walker::with_host(host, path, structure_item)
+            .with_nested_roots(nested_roots)
+            .with_ignore(structure_config.ignore.clone().unwrap_or_default())
+            .with_dry_run(true)
+            .verify(path.to_path_buf(), structure_item)
+    }
+}
+
+/// The app before it is built, for a walker assuming the repairs, see `Walker::with_dry_run`.
+///
+/// Nothing is read from the app: named conditions are all unset, and files aren't validated nor checked against
+/// their JSON Schema.
+struct Offline<'a> {
+    preflight: &'a Preflight,
+    hooks: Hooks,
+    translation: Translation,
+    repair_scope: RepairScope,
+}
+
+impl Host for Offline<'_> {
+    fn is_active(&self, structure_item: &StructureItem) -> std::result::Result<bool, String> {
+        match &structure_item.when {
+            Some(condition) => condition.matches(
+                &self.preflight.version,
+                &self.preflight.features,
+                &HashMap::new(),
+            ),
+            None => Ok(true),
+        }
+    }
+
+    fn validators(&self) -> Option<&Validators> {
+        None
+    }
+
+    fn compile_schema(
+        &self,
+        _schema: &FileSchema,
+    ) -> std::result::Result<Option<JSONSchema>, String> {
+        Ok(None)
+    }
+
+    fn hooks(&self) -> &Hooks {
+        &self.hooks
+    }
+
+    fn translation(&self) -> &Translation {
+        &self.translation
+    }
+
+    fn repair_scope(&self) -> &RepairScope {
+        &self.repair_scope
+    }
+
+    fn archive_source(&self, archive: &str) -> std::result::Result<PathBuf, String> {
+        Err(needs_app(&format!("Archive {:?}", archive)))
+    }
+
+    fn seed_source(
+        &self,
+        _seed_from: SeedSource,
+        _root: &Path,
+        path: &Path,
+    ) -> std::result::Result<PathBuf, String> {
+        Err(needs_app(&format!("Seed source of {:?}", path)))
+    }
+
+    fn download(
+        &self,
+        path: &Path,
+        url: &str,
+        _sha256: Option<&str>,
+    ) -> std::result::Result<(), String> {
+        Err(needs_app(&format!("Download of {} to {:?}", url, path)))
+    }
+}
+
+/// Returns the error of a repair that needs the app, never made as the repairs are only assumed.
+fn needs_app(what: &str) -> String {
+    format!("{} can't be resolved before setup", what)
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+
+use crate::models::*;
+
+impl StructureItem {
+    /// Returns a copy of the structure item with every `$ref` (including nested ones) replaced by the referenced definition.
+    ///
+    /// # Arguments
+    ///
+    /// * `definitions` - The reusable structures that can be referenced.
+    ///
+    /// # Returns
+    ///
+    /// Returns the resolved item, or `Err(String)` if a reference is unknown or cyclic.
+    pub fn resolve(
+        &self,
+        definitions: &BTreeMap<String, StructureItem>,
+    ) -> std::result::Result<StructureItem, String> {
+        self.resolve_with_stack(definitions, &mut Vec::new())
+    }
+
+    fn resolve_with_stack(
+        &self,
+        definitions: &BTreeMap<String, StructureItem>,
+        stack: &mut Vec<String>,
+    ) -> std::result::Result<StructureItem, String> {
+        let mut item = match &self.reference {
+            Some(name) => {
+                if stack.contains(name) {
+                    stack.push(name.clone());
+                    return Err(format!(
+                        "Cyclic structure reference: {}",
+                        stack.join(" -> ")
+                    ));
+                }
+                let definition = definitions
+                    .get(name)
+                    .ok_or_else(|| format!("Structure definition not found: {:?}", name))?;
+
+                stack.push(name.clone());
+                let definition = definition.resolve_with_stack(definitions, stack)?;
+                stack.pop();
+
+                merge_reference(definition, self)
+            }
+            None => self.clone(),
+        };
+
+        if let Some(dirs) = &mut item.dirs {
+            for dir in dirs.values_mut() {
+                *dir = dir.resolve_with_stack(definitions, stack)?;
+            }
+        }
+
+        Ok(item)
+    }
+}
+
+/// Merges an item over the definition it references, the item's own fields take precedence.
+fn merge_reference(definition: StructureItem, item: &StructureItem) -> StructureItem {
+    let files = match (definition.files, &item.files) {
+        (Some(mut files), Some(own)) => {
+            for file in own {
+                match files.iter_mut().find(|f| f.name == file.name) {
+                    Some(f) => *f = file.clone(),
+                    None => files.push(file.clone()),
+                }
+            }
+            Some(files)
+        }
+        (files, None) => files,
+        (None, own) => own.clone(),
+    };
+
+    let dirs = match (definition.dirs, &item.dirs) {
+        (Some(mut dirs), Some(own)) => {
+            dirs.extend(own.iter().map(|(name, dir)| (name.clone(), dir.clone())));
+            Some(dirs)
+        }
+        (dirs, None) => dirs,
+        (None, own) => own.clone(),
+    };
+
+    StructureItem {
+        reference: None,
+        when: item.when.clone().or(definition.when),
+        options: item.options.clone().or(definition.options),
+        files,
+        dirs,
+    }
+}
+
+impl StructureConfig {
+    /// Resolves every `$ref` of the configured roots against `definitions`, in place.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if all references could be resolved, or `Err(String)` if a reference is unknown or cyclic.
+    pub fn resolve_refs(&mut self) -> std::result::Result<(), String> {
+        let definitions = self.definitions.clone().unwrap_or_default();
+
+        for root in self.roots_mut().into_iter().flatten() {
+            *root = root.resolve(&definitions)?;
+        }
+
+        if let Some(contracts) = &mut self.contracts {
+            for contract in contracts.values_mut() {
+                contract.structure = contract.structure.resolve(&definitions)?;
+            }
+        }
+
+        if let Some(structures) = &mut self.structures {
+            for item in structures.values_mut() {
+                *item = item.resolve(&definitions)?;
+            }
+        }
+
+        if let Some(custom) = &mut self.custom {
+            for root in custom.values_mut() {
+                root.structure = root.structure.resolve(&definitions)?;
+            }
+        }
+
+        if let Some(app_groups) = &mut self.app_groups {
+            for item in app_groups.values_mut() {
+                *item = item.resolve(&definitions)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{scope::fs::Scope, utils::config::FsScope, Manager, Runtime};
+
+use crate::{ownership, roots};
+
+/// Restricts the entries repairs may create or delete, see `Builder::repair_scope`.
+#[derive(Default)]
+pub(crate) struct RepairScope(Option<Scope>);
+
+impl RepairScope {
+    /// Builds the repair scope of the app from the patterns given to the builder, if any.
+    ///
+    /// The app-scoped base directories are always allowed, only shared locations need to be listed.
+    pub(crate) fn new<R: Runtime, M: Manager<R>>(
+        manager: &M,
+        fs_scope: Option<&FsScope>,
+    ) -> crate::Result<Self> {
+        let Some(fs_scope) = fs_scope else {
+            return Ok(Self(None));
+        };
+
+        let scope = Scope::new(manager, fs_scope)?;
+        for directory in roots::ROOTS
+            .into_iter()
+            .filter(|directory| ownership::is_app_scoped(*directory))
+        {
+            if let Ok(path) = roots::resolve(manager, directory) {
+                scope.allow_directory(path, true)?;
+            }
+        }
+
+        Ok(Self(Some(scope)))
+    }
+
+    /// Verifies that a repair may create or delete `target`.
+    ///
+    /// Symbolic links and junctions leading to the target are resolved first, so that a link planted in a verified
+    /// directory can't turn a repair into a write anywhere on the disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The verified root, the target must stay inside of it. `None` for repair plans, whose operations
+    ///   are checked against the permission scope of the caller instead.
+    /// * `target` - The entry to be created or deleted.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the repair is allowed, or `Err(String)` explaining why it isn't.
+    pub(crate) fn check(
+        &self,
+        root: Option<&Path>,
+        target: &Path,
+    ) -> std::result::Result<(), String> {
+        // The target itself isn't followed: deleting a link only removes the link.
+        let resolved = match (target.parent(), target.file_name()) {
+            (Some(parent), Some(name)) => resolve(parent).join(name),
+            _ => resolve(target),
+        };
+        if let Some(root) = root {
+            if !resolved.starts_with(resolve(root)) {
+                return Err(format!(
+                    "Repair target {:?} resolves outside of the verified root {:?}",
+                    target, root
+                ));
+            }
+        }
+        if let Some(scope) = &self.0 {
+            if !scope.is_allowed(&resolved) {
+                return Err(format!(
+                    "Repair target {:?} is not allowed by the repair scope",
+                    target
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolves a path that may not exist yet: its deepest existing ancestor is canonicalized and the missing
+/// components are appended to it.
+fn resolve(path: &Path) -> PathBuf {
+    let mut missing = Vec::new();
+    let mut current = path;
+    loop {
+        if let Ok(canonical) = current.canonicalize() {
+            return missing
+                .into_iter()
+                .rev()
+                .fold(canonical, |resolved, name| resolved.join(name));
+        }
+        match (current.parent(), current.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                current = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use crate::models::*;
+
+/// Collects the results of a verification into a report, see `StructureManagerExt::verification_report`.
+#[derive(Default)]
+pub(crate) struct Collector {
+    entries: RefCell<Vec<ReportEntry>>,
+    violations: RefCell<Vec<Violation>>,
+}
+
+impl Collector {
+    /// Records a result streamed by the verification.
+    pub(crate) fn record(&self, event: VerificationEvent) {
+        match event {
+            VerificationEvent::Entry { path, kind, status } => {
+                self.entries
+                    .borrow_mut()
+                    .push(ReportEntry { path, kind, status });
+            }
+            VerificationEvent::Violation(violation)
+            | VerificationEvent::Warning(violation)
+            | VerificationEvent::Info(violation) => self.violations.borrow_mut().push(violation),
+        }
+    }
+
+    /// Builds the report of the verification of `root` (at `path`), given its result.
+    pub(crate) fn finish(
+        self,
+        root: &str,
+        path: Option<PathBuf>,
+        result: std::result::Result<(), String>,
+    ) -> VerificationReport {
+        VerificationReport {
+            schema_version: crate::SCHEMA_VERSION,
+            root: root.to_string(),
+            path,
+            error: result.err(),
+            entries: self.entries.into_inner(),
+            violations: self.violations.into_inner(),
+        }
+    }
+}
+
+/// A line of the rendered tree.
+struct Line {
+    marker: char,
+    is_dir: bool,
+    notes: Vec<&'static str>,
+}
+
+impl VerificationReport {
+    /// Serializes the report as pretty-printed JSON.
+    pub fn to_json(&self) -> std::result::Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| format!("Failed to serialize report: {}", e))
+    }
+
+    /// Renders the report as an indented tree of the checked entries, for logs or a diagnostics dialog.
+    ///
+    /// Entries are marked with `✓` when they are fine (or were repaired), `✗` for errors, `!` for warnings and `i`
+    /// for info. The error message of a failed verification follows the tree.
+    ///
+    /// ```text
+    /// ✗ appData (/home/user/.local/share/com.example)
+    ///   ✓ settings.json
+    ///   ✓ db/ (repaired)
+    ///     ✗ index.json (missing file)
+    ///   ! plugins/ (missing directory)
+    /// Error: File not found: "/home/user/.local/share/com.example/db/index.json"
+    /// ```
+    pub fn render_text(&self) -> String {
+        let root_path = self.path.as_deref().unwrap_or(Path::new(""));
+        let relative = |path: &Path| path.strip_prefix(root_path).unwrap_or(path).to_path_buf();
+
+        // Later results about an entry override earlier ones, e.g. a missing directory that is then repaired.
+        let mut lines: BTreeMap<PathBuf, Line> = BTreeMap::new();
+        for entry in &self.entries {
+            let line = lines.entry(relative(&entry.path)).or_insert(Line {
+                marker: '✓',
+                is_dir: false,
+                notes: Vec::new(),
+            });
+            line.is_dir = entry.kind == EntryKind::Directory;
+            match entry.status {
+                EntryStatus::Ok => line.marker = '✓',
+                EntryStatus::Missing => line.marker = '✗',
+                EntryStatus::Repaired => {
+                    line.marker = '✓';
+                    line.notes.push("repaired");
+                }
+            }
+        }
+        for violation in &self.violations {
+            let line = lines.entry(relative(&violation.path)).or_insert(Line {
+                marker: '✗',
+                is_dir: false,
+                notes: Vec::new(),
+            });
+            line.marker = match violation.severity {
+                Severity::Error => '✗',
+                Severity::Warning => '!',
+                Severity::Info => 'i',
+            };
+            line.notes.push(kind_label(violation.kind));
+        }
+
+        let root_line = lines.remove(Path::new(""));
+        let root_marker = if self.is_success() { '✓' } else { '✗' };
+        let mut text = format!("{} {}", root_marker, self.root);
+        if let Some(path) = &self.path {
+            text.push_str(&format!(" ({})", path.display()));
+        }
+        if let Some(line) = root_line.filter(|line| !line.notes.is_empty()) {
+            text.push_str(&format!(" [{}]", line.notes.join(", ")));
+        }
+        text.push('\n');
+
+        for (path, line) in &lines {
+            let depth = path.components().count();
+            let name = path
+                .file_name()
+                .map(|name| name.to_string_lossy())
+                .unwrap_or_else(|| path.to_string_lossy());
+            text.push_str(&format!(
+                "{}{} {}{}",
+                "  ".repeat(depth),
+                line.marker,
+                name,
+                if line.is_dir { "/" } else { "" }
+            ));
+            if !line.notes.is_empty() {
+                text.push_str(&format!(" ({})", line.notes.join(", ")));
+            }
+            text.push('\n');
+        }
+
+        if let Some(error) = &self.error {
+            text.push_str(&format!("Error: {}\n", error));
+        }
+
+        text
+    }
+
+    /// Renders the report as plain text: the tree of [`render_text`](Self::render_text), then the code and message of
+    /// every violation, one per line.
+    pub fn to_text(&self) -> String {
+        let mut text = format!("Structure verification report\n\n{}", self.render_text());
+
+        if !self.violations.is_empty() {
+            text.push_str("\nViolations:\n");
+            for violation in &self.violations {
+                let severity = match violation.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::Info => "info",
+                };
+                text.push_str(&format!(
+                    "  [{}] {} {}\n",
+                    severity, violation.code, violation.message
+                ));
+                if let Some(explanation) = &violation.explanation {
+                    text.push_str(&format!("    {}\n", explanation));
+                }
+                if let Some(help_url) = &violation.help_url {
+                    text.push_str(&format!("    See {}\n", help_url));
+                }
+            }
+        }
+
+        text
+    }
+
+    /// Writes the report to a file in the given format, replacing the file if it exists.
+    pub fn write(&self, path: &Path, format: ReportFormat) -> std::result::Result<(), String> {
+        let content = match format {
+            ReportFormat::Json => self.to_json()?,
+            ReportFormat::Text => self.to_text(),
+        };
+
+        std::fs::write(path, content)
+            .map_err(|e| format!("Failed to write report: {:?}, error: {:?}", path, e))
+    }
+}
+
+impl fmt::Display for VerificationReport {
+    /// Formats the report as [`render_text`](VerificationReport::render_text) does.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render_text())
+    }
+}
+
+/// Returns how a kind of violation is described in the rendered tree.
+fn kind_label(kind: ViolationKind) -> &'static str {
+    match kind {
+        ViolationKind::MissingFile => "missing file",
+        ViolationKind::MissingDirectory => "missing directory",
+        ViolationKind::UnexpectedEntry => "unexpected entry",
+        ViolationKind::QuotaExceeded => "quota exceeded",
+        ViolationKind::ValidationFailed => "validation failed",
+        ViolationKind::SchemaViolation => "schema violation",
+        ViolationKind::InvalidFormat => "invalid format",
+        ViolationKind::RepairFailed => "repair failed",
+        ViolationKind::ForeignEntry => "foreign entry",
+        ViolationKind::AttributeMismatch => "attribute mismatch",
+        ViolationKind::OwnerMismatch => "owner mismatch",
+        ViolationKind::SecurityIssue => "security issue",
+        ViolationKind::DepthLimitExceeded => "depth limit exceeded",
+        ViolationKind::ReparsePoint => "reparse point",
+        ViolationKind::VersionMismatch => "version mismatch",
+        ViolationKind::TimedOut => "timed out",
+        ViolationKind::AclMismatch => "ACL mismatch",
+    }
+}
@@ -0,0 +1,353 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{path::BaseDirectory, Manager, Runtime};
+
+use crate::{events, history, models::*};
+
+/// Every base directory that can be configured in the structure configuration, in declaration order.
+pub const ROOTS: [BaseDirectory; 23] = [
+    BaseDirectory::AppCache,
+    BaseDirectory::AppConfig,
+    BaseDirectory::AppData,
+    BaseDirectory::AppLocalData,
+    BaseDirectory::AppLog,
+    BaseDirectory::Audio,
+    BaseDirectory::Cache,
+    BaseDirectory::Config,
+    BaseDirectory::Data,
+    BaseDirectory::Desktop,
+    BaseDirectory::Document,
+    BaseDirectory::Download,
+    BaseDirectory::Executable,
+    BaseDirectory::Font,
+    BaseDirectory::Home,
+    BaseDirectory::LocalData,
+    BaseDirectory::Picture,
+    BaseDirectory::Public,
+    BaseDirectory::Resource,
+    BaseDirectory::Runtime,
+    BaseDirectory::Temp,
+    BaseDirectory::Template,
+    BaseDirectory::Video,
+];
+
+/// Returns the name of the structure configuration field of a base directory, e.g. `appCache`.
+pub fn field_name(directory: BaseDirectory) -> &'static str {
+    match directory {
+        BaseDirectory::AppCache => "appCache",
+        BaseDirectory::AppConfig => "appConfig",
+        BaseDirectory::AppData => "appData",
+        BaseDirectory::AppLocalData => "appLocalData",
+        BaseDirectory::AppLog => "appLog",
+        BaseDirectory::Audio => "audio",
+        BaseDirectory::Cache => "cache",
+        BaseDirectory::Config => "config",
+        BaseDirectory::Data => "data",
+        BaseDirectory::Desktop => "desktop",
+        BaseDirectory::Document => "document",
+        BaseDirectory::Download => "download",
+        BaseDirectory::Executable => "executable",
+        BaseDirectory::Font => "font",
+        BaseDirectory::Home => "home",
+        BaseDirectory::LocalData => "localData",
+        BaseDirectory::Picture => "picture",
+        BaseDirectory::Public => "public",
+        BaseDirectory::Resource => "resource",
+        BaseDirectory::Runtime => "runtime",
+        BaseDirectory::Temp => "temp",
+        BaseDirectory::Template => "template",
+        BaseDirectory::Video => "video",
+        _ => "unknown",
+    }
+}
+
+/// A configured root: a base directory, a custom root of the `custom` section by name, or the shared container
+/// of an iOS app group of the `appGroups` section.
+///
+/// Base directories and custom root names convert into it, e.g. `app.verify(BaseDirectory::AppData)`
+/// or `app.verify("workspace")`. App groups are selected explicitly,
+/// e.g. `app.verify(RootSelector::AppGroup("group.com.example".into()))`.
+#[derive(Clone, Debug)]
+pub enum RootSelector {
+    Base(BaseDirectory),
+    Custom(String),
+    AppGroup(String),
+}
+
+impl RootSelector {
+    /// Returns the name of the root: the configuration field of a base directory (e.g. `appData`), the name of
+    /// a custom root or the identifier of an app group.
+    pub fn name(&self) -> &str {
+        match self {
+            RootSelector::Base(directory) => field_name(*directory),
+            RootSelector::Custom(name) => name,
+            RootSelector::AppGroup(group) => group,
+        }
+    }
+}
+
+impl From<BaseDirectory> for RootSelector {
+    fn from(directory: BaseDirectory) -> Self {
+        RootSelector::Base(directory)
+    }
+}
+
+impl From<&str> for RootSelector {
+    fn from(name: &str) -> Self {
+        RootSelector::Custom(name.to_string())
+    }
+}
+
+impl From<String> for RootSelector {
+    fn from(name: String) -> Self {
+        RootSelector::Custom(name)
+    }
+}
+
+/// Returns the path and the configured structure of a root, resolving base directories with `resolve` and the
+/// containers of app groups with `resolve_group`.
+pub(crate) fn select<'a>(
+    structure_config: &'a StructureConfig,
+    root: &RootSelector,
+    resolve: impl Fn(BaseDirectory) -> std::result::Result<PathBuf, String>,
+    resolve_group: impl Fn(&str) -> std::result::Result<PathBuf, String>,
+) -> std::result::Result<(PathBuf, &'a StructureItem), String> {
+    match root {
+        RootSelector::Base(directory) => match structure_config.root(*directory) {
+            Some(structure_item) => Ok((resolve(*directory)?, structure_item)),
+            None => Err(format!(
+                "Structure configuration field `{}` not found",
+                field_name(*directory)
+            )),
+        },
+        RootSelector::Custom(name) => match structure_config
+            .custom
+            .as_ref()
+            .and_then(|custom| custom.get(name))
+        {
+            Some(root) if !root.path.is_absolute() => Err(format!(
+                "Custom root `{}` path is not absolute: {:?}",
+                name, root.path
+            )),
+            Some(root) => Ok((root.path.clone(), &root.structure)),
+            None => Err(format!(
+                "Structure configuration custom root `{}` not found",
+                name
+            )),
+        },
+        RootSelector::AppGroup(group) => match structure_config
+            .app_groups
+            .as_ref()
+            .and_then(|app_groups| app_groups.get(group))
+        {
+            Some(structure_item) => Ok((resolve_group(group)?, structure_item)),
+            None => Err(format!(
+                "Structure configuration app group `{}` not found",
+                group
+            )),
+        },
+    }
+}
+
+/// Resolves the shared container of an iOS app group through the Swift side of the plugin.
+///
+/// The app (and each extension sharing the container) must declare the group in its App Groups entitlement,
+/// other platforms have no app group containers.
+pub(crate) fn resolve_app_group<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    group: &str,
+) -> std::result::Result<PathBuf, String> {
+    #[cfg(target_os = "ios")]
+    {
+        use crate::StructureManagerExt;
+
+        manager
+            .structure_manager()
+            .app_group_container(group)
+            .map_err(|e| format!("Failed to resolve app group `{}`: {}", group, e))
+    }
+    #[cfg(not(target_os = "ios"))]
+    {
+        let _ = manager;
+        Err(format!(
+            "App group `{}` can't be verified: app groups are only supported on iOS",
+            group
+        ))
+    }
+}
+
+/// Resolves the path of a base directory.
+pub(crate) fn resolve<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    directory: BaseDirectory,
+) -> std::result::Result<PathBuf, String> {
+    #[cfg(target_os = "android")]
+    if let Some(reason) = android_restriction(directory) {
+        return Err(format!(
+            "Base directory `{}` can't be verified on Android: {}",
+            field_name(directory),
+            reason
+        ));
+    }
+
+    let path = manager.path();
+    let (result, label) = match directory {
+        BaseDirectory::AppCache => (path.app_cache_dir(), "app cache"),
+        BaseDirectory::AppConfig => (path.app_config_dir(), "app config"),
+        BaseDirectory::AppData => (path.app_data_dir(), "app data"),
+        BaseDirectory::AppLocalData => (path.app_local_data_dir(), "app local data"),
+        BaseDirectory::AppLog => (path.app_log_dir(), "app log"),
+        BaseDirectory::Audio => (path.audio_dir(), "audio"),
+        BaseDirectory::Cache => (path.cache_dir(), "cache"),
+        BaseDirectory::Config => (path.config_dir(), "config"),
+        BaseDirectory::Data => (path.data_dir(), "data"),
+        BaseDirectory::Desktop => (path.desktop_dir(), "desktop"),
+        BaseDirectory::Document => (path.document_dir(), "document"),
+        BaseDirectory::Download => (path.download_dir(), "download"),
+        BaseDirectory::Executable => (path.executable_dir(), "executable"),
+        BaseDirectory::Font => (path.font_dir(), "font"),
+        BaseDirectory::Home => (path.home_dir(), "home"),
+        BaseDirectory::LocalData => (path.local_data_dir(), "local data"),
+        BaseDirectory::Picture => (path.picture_dir(), "picture"),
+        BaseDirectory::Public => (path.public_dir(), "public"),
+        BaseDirectory::Resource => (path.resource_dir(), "resource"),
+        BaseDirectory::Runtime => (path.runtime_dir(), "runtime"),
+        BaseDirectory::Temp => (path.temp_dir(), "temp"),
+        BaseDirectory::Template => (path.template_dir(), "template"),
+        BaseDirectory::Video => (path.video_dir(), "video"),
+        _ => return Err(format!("Unsupported base directory: {:?}", directory)),
+    };
+
+    result.map_err(|e| format!("Failed to resolve {} path: {:?}", label, e))
+}
+
+/// Returns whether a base directory can't be resolved on the platform, emitting a `verification-skipped` event for it
+/// if so. Used with the `skipUnresolvedRoots` option.
+pub(crate) fn skip_unresolved<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    directory: BaseDirectory,
+) -> bool {
+    let Err(reason) = resolve(manager, directory) else {
+        return false;
+    };
+
+    let root = field_name(directory);
+    log::info!("Skipping the verification of `{}`: {}", root, reason);
+    events::emit(
+        manager,
+        events::VERIFICATION_SKIPPED,
+        VerificationSkipped {
+            root: root.to_string(),
+            reason,
+        },
+    );
+    true
+}
+
+/// Resolves the path of a root by name: the configuration field of a base directory (e.g. `appData`), whether or not
+/// it has a configured structure, or the name of a custom root.
+pub(crate) fn resolve_named<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    structure_config: &StructureConfig,
+    name: &str,
+) -> std::result::Result<PathBuf, String> {
+    if let Some(directory) = ROOTS
+        .iter()
+        .find(|directory| field_name(**directory) == name)
+    {
+        return resolve(manager, *directory);
+    }
+
+    match structure_config
+        .custom
+        .as_ref()
+        .and_then(|custom| custom.get(name))
+    {
+        Some(root) => Ok(root.path.clone()),
+        None => Err(format!("Unknown root `{}`", name)),
+    }
+}
+
+/// Returns why a base directory can't be verified on Android, if it can't.
+///
+/// With scoped storage, the app only reaches its internal directories and its app-specific directories of the
+/// external storage, which is what `audio`, `document`, `download`, `picture`, `public` and `video` resolve to.
+/// Shared storage is only reachable through the MediaStore and Storage Access Framework APIs.
+#[cfg(target_os = "android")]
+fn android_restriction(directory: BaseDirectory) -> Option<&'static str> {
+    match directory {
+        BaseDirectory::Home => Some(
+            "it is the root of the shared storage, which scoped storage only exposes through the MediaStore and \
+             Storage Access Framework APIs",
+        ),
+        BaseDirectory::Resource => Some("resources are assets packaged in the APK, not a directory"),
+        BaseDirectory::Desktop
+        | BaseDirectory::Executable
+        | BaseDirectory::Font
+        | BaseDirectory::Runtime
+        | BaseDirectory::Template => Some("the directory doesn't exist on Android"),
+        _ => None,
+    }
+}
+
+/// Returns the paths of the configured roots (built-in and custom) strictly inside `path`, and of the plugin's own
+/// data directory, which isn't part of any declared structure.
+///
+/// Roots that can't be resolved are left out.
+pub(crate) fn nested_in<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    structure_config: &StructureConfig,
+    path: &Path,
+) -> Vec<PathBuf> {
+    nested_within(structure_config, path, |directory| {
+        resolve(manager, directory)
+    })
+}
+
+/// Returns the paths of the configured roots and of the plugin's data directory strictly inside `path`, resolving
+/// base directories with `resolve`.
+pub(crate) fn nested_within(
+    structure_config: &StructureConfig,
+    path: &Path,
+    resolve: impl Fn(BaseDirectory) -> std::result::Result<PathBuf, String>,
+) -> Vec<PathBuf> {
+    let builtin = ROOTS
+        .iter()
+        .filter(|directory| structure_config.root(**directory).is_some())
+        .filter_map(|directory| resolve(*directory).ok());
+    let custom = structure_config
+        .custom
+        .iter()
+        .flat_map(|custom| custom.values())
+        .map(|root| root.path.clone());
+    let own = resolve(BaseDirectory::AppLocalData)
+        .ok()
+        .map(|directory| directory.join(history::DIRECTORY));
+
+    builtin
+        .chain(custom)
+        .chain(own)
+        .filter(|root| root != path && root.starts_with(path))
+        .collect()
+}
+
+impl StructureConfig {
+    /// Returns the configured structure of a base directory, if any.
+    pub fn root(&self, directory: BaseDirectory) -> Option<&StructureItem> {
+        ROOTS
+            .iter()
+            .position(|root| field_name(*root) == field_name(directory))
+            .and_then(|index| self.roots()[index].as_ref())
+    }
+
+    /// Returns the configured structure of a base directory mutably.
+    pub(crate) fn root_mut(
+        &mut self,
+        directory: BaseDirectory,
+    ) -> Option<&mut Option<StructureItem>> {
+        let index = ROOTS
+            .iter()
+            .position(|root| field_name(*root) == field_name(directory))?;
+        self.roots_mut().into_iter().nth(index)
+    }
+}
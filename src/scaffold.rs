@@ -0,0 +1,239 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{Manager, Runtime};
+
+use crate::{
+    conditions::is_active,
+    long_path::extended,
+    models::*,
+    normalization::{locate, normalize},
+    repair_scope::RepairScope,
+    seed,
+    tuning::ResolvedIoTuning,
+};
+
+/// Creates every declared directory and file of a structure, whatever their `repair` option,
+/// see `StructureManagerExt::scaffold_all`.
+pub(crate) struct Scaffolder<'a, R: Runtime, M: Manager<R>> {
+    manager: &'a M,
+    repair_scope: tauri::State<'a, RepairScope>,
+    root: PathBuf,
+    tuning: ResolvedIoTuning,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: Vec<PathBuf>,
+    created_directories: Vec<PathBuf>,
+    created_files: Vec<PathBuf>,
+    _runtime: std::marker::PhantomData<R>,
+}
+
+impl<'a, R: Runtime, M: Manager<R>> Scaffolder<'a, R, M> {
+    /// Creates a scaffolder for the root item of a structure, see `Walker::new`.
+    pub(crate) fn new(manager: &'a M, path: &Path, root: &StructureItem) -> Self {
+        Self {
+            manager,
+            repair_scope: manager.state::<RepairScope>(),
+            root: path.to_path_buf(),
+            tuning: root
+                .options
+                .as_ref()
+                .and_then(|options| options.tuning.clone())
+                .unwrap_or_default()
+                .resolve(path),
+            normalization: root.unicode_normalization(),
+            nested_roots: Vec::new(),
+            created_directories: Vec::new(),
+            created_files: Vec::new(),
+            _runtime: std::marker::PhantomData,
+        }
+    }
+
+    /// Sets the paths of the other configured roots nested inside the scaffolded root, see `Walker::with_nested_roots`.
+    pub(crate) fn with_nested_roots(mut self, nested_roots: Vec<PathBuf>) -> Self {
+        self.nested_roots = nested_roots;
+        self
+    }
+
+    /// Scaffolds `root`, at `path`.
+    ///
+    /// # Returns
+    ///
+    /// Returns the entries that were created, or `Err(String)` if an entry can't be created.
+    pub(crate) fn scaffold(
+        mut self,
+        root: &str,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<ScaffoldSummary, String> {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!("scaffold", root).entered();
+
+        let required = self.required(path, structure_item)?;
+        crate::space::check(path, required).map_err(|e| e.to_string())?;
+        self.scaffold_item(path, structure_item)?;
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            directories = self.created_directories.len(),
+            files = self.created_files.len(),
+            "structure scaffolded"
+        );
+
+        Ok(ScaffoldSummary {
+            root: root.to_string(),
+            created_directories: self.created_directories,
+            created_files: self.created_files,
+        })
+    }
+
+    /// Returns the size of the files scaffolding a structure item writes: its archive, its missing seed entries and
+    /// the content of its missing files, recursively.
+    fn required(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<u64, String> {
+        if !is_active(self.manager, structure_item)? {
+            return Ok(0);
+        }
+
+        let exists = extended(path).is_dir();
+        if !exists {
+            // The contents of a directory extracted from its archive or renamed from a legacy location are in place.
+            let legacy = path.parent().and_then(|parent| {
+                crate::legacy::locate(parent, structure_item, self.normalization)
+            });
+            if legacy.is_some() {
+                return Ok(0);
+            }
+            if let Some(archive) = structure_item
+                .options
+                .as_ref()
+                .and_then(|o| o.archive.as_deref())
+            {
+                let archive = crate::archive::source_of(self.manager, archive)?;
+                return crate::archive::extracted_size(&archive);
+            }
+        }
+
+        let mut required = 0;
+        if let Some(seed_from) = structure_item.seed_from() {
+            let source = seed::source_of(self.manager, seed_from, &self.root, path)?;
+            required += crate::space::seed_size(&seed::missing_entries(&source, path)?);
+        }
+        for file in structure_item.files.iter().flatten() {
+            if exists && locate(path, &file.name, self.normalization).is_some() {
+                continue;
+            }
+            if let Some(content) = &file.options.content {
+                required += content.to_bytes()?.len() as u64;
+            }
+        }
+        for (dir_name, dir) in structure_item.dirs.iter().flatten() {
+            let dir_path = match exists
+                .then(|| locate(path, dir_name, self.normalization))
+                .flatten()
+            {
+                Some(dir_path) => dir_path,
+                None => path.join(&*normalize(dir_name, self.normalization)),
+            };
+            if !self.nested_roots.contains(&dir_path) {
+                required += self.required(&dir_path, dir)?;
+            }
+        }
+
+        Ok(required)
+    }
+
+    fn scaffold_item(
+        &mut self,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<(), String> {
+        if !is_active(self.manager, structure_item)? {
+            return Ok(());
+        }
+
+        if !extended(path).is_dir() {
+            self.repair_scope.check(Some(&self.root), path)?;
+            let legacy = path.parent().and_then(|parent| {
+                crate::legacy::locate(parent, structure_item, self.normalization)
+            });
+            let archive = structure_item
+                .options
+                .as_ref()
+                .and_then(|o| o.archive.as_deref());
+            match (legacy, archive) {
+                (Some(legacy), _) => {
+                    self.repair_scope.check(Some(&self.root), &legacy)?;
+                    crate::legacy::rename(&legacy, path)?;
+                }
+                (None, Some(archive)) => {
+                    let archive = crate::archive::source_of(self.manager, archive)?;
+                    crate::archive::extract(&archive, path)?;
+                }
+                (None, None) => self
+                    .tuning
+                    .retry(|| std::fs::create_dir_all(extended(path)))
+                    .map_err(|e| {
+                        format!("Failed to create directory: {:?}, error: {:?}", path, e)
+                    })?,
+            }
+            if let Some(template) = structure_item.options.as_ref().and_then(|o| o.acl) {
+                crate::acl::apply(path, template)?;
+            }
+            self.created_directories.push(path.to_path_buf());
+        }
+
+        if let Some(seed_from) = structure_item.seed_from() {
+            let source = seed::source_of(self.manager, seed_from, &self.root, path)?;
+            for entry in seed::missing_entries(&source, path)? {
+                self.repair_scope.check(Some(&self.root), &entry.target)?;
+                seed::copy(&entry.source, &entry.target, entry.kind)?;
+                match entry.kind {
+                    EntryKind::Directory => self.created_directories.push(entry.target),
+                    EntryKind::File => self.created_files.push(entry.target),
+                }
+            }
+        }
+
+        for file in structure_item.files.iter().flatten() {
+            if locate(path, &file.name, self.normalization).is_some() {
+                continue;
+            }
+
+            let file_path = path.join(&*normalize(&file.name, self.normalization));
+            self.repair_scope.check(Some(&self.root), &file_path)?;
+            match (&file.options.content, &file.options.url) {
+                (None, Some(url)) => crate::download::download(
+                    self.manager,
+                    &file_path,
+                    url,
+                    file.options.sha256.as_deref(),
+                )?,
+                (content, _) => {
+                    crate::content::create_file(&file_path, content.as_ref(), &self.tuning)?
+                }
+            }
+            self.created_files.push(file_path);
+        }
+
+        for (dir_name, dir) in structure_item.dirs.iter().flatten() {
+            let dir_path = match locate(path, dir_name, self.normalization) {
+                Some(dir_path) => dir_path,
+                None => path.join(&*normalize(dir_name, self.normalization)),
+            };
+            if self.nested_roots.contains(&dir_path) {
+                continue;
+            }
+            // Nothing is created through a link to another location.
+            if dir.reparse_point_policy() != ReparsePointPolicy::Follow
+                && crate::attributes::is_reparse_point(&dir_path)
+            {
+                continue;
+            }
+
+            self.scaffold_item(&dir_path, dir)?;
+        }
+
+        Ok(())
+    }
+}
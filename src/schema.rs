@@ -0,0 +1,78 @@
+use serde::Deserialize;
+
+use crate::models::*;
+
+/// The version of the serialized formats of repair plans and reconciliation reports, written as `schemaVersion`.
+///
+/// Formats of previous versions are still read and converted to the current one:
+/// - version 1 had no `schemaVersion` field, and reconciliation reports had no `dryRun` field.
+/// - version 2 had no `createFile`, `copyFile`, `downloadFile` or `extractArchive` operations.
+/// - version 3 had no `moveDir` operation.
+/// - version 4 had no `archiveEntry` operation.
+/// - version 5 had no `emptyDirectory` precondition.
+pub const SCHEMA_VERSION: u32 = 6;
+
+/// Returns the schema version of a serialized format, or an error if it is newer than this version of the plugin.
+fn check_version(schema_version: Option<u32>, format: &str) -> std::result::Result<u32, String> {
+    match schema_version.unwrap_or(1) {
+        version @ 1..=SCHEMA_VERSION => Ok(version),
+        version => Err(format!(
+            "Unsupported {} schema version {}, the latest supported version is {}",
+            format, version, SCHEMA_VERSION
+        )),
+    }
+}
+
+/// A repair plan of any supported schema version.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VersionedRepairPlan {
+    schema_version: Option<u32>,
+    root: String,
+    operations: Vec<RepairOperation>,
+}
+
+impl TryFrom<VersionedRepairPlan> for RepairPlan {
+    type Error = String;
+
+    fn try_from(plan: VersionedRepairPlan) -> std::result::Result<Self, Self::Error> {
+        check_version(plan.schema_version, "repair plan")?;
+
+        Ok(RepairPlan {
+            schema_version: SCHEMA_VERSION,
+            root: plan.root,
+            operations: plan.operations,
+        })
+    }
+}
+
+/// A reconciliation report of any supported schema version.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VersionedReconciliationReport {
+    schema_version: Option<u32>,
+    root: String,
+    dry_run: Option<bool>,
+    operations: Vec<ReconciledOperation>,
+}
+
+impl TryFrom<VersionedReconciliationReport> for ReconciliationReport {
+    type Error = String;
+
+    fn try_from(report: VersionedReconciliationReport) -> std::result::Result<Self, Self::Error> {
+        let dry_run = match check_version(report.schema_version, "reconciliation report")? {
+            // Plans couldn't be dry run before version 2.
+            1 => false,
+            _ => report
+                .dry_run
+                .ok_or("Missing field `dryRun` in reconciliation report")?,
+        };
+
+        Ok(ReconciliationReport {
+            schema_version: SCHEMA_VERSION,
+            root: report.root,
+            dry_run,
+            operations: report.operations,
+        })
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use tauri::{
+    ipc::{CommandScope, GlobalScope},
+    utils::config::FsScope,
+    AppHandle, Runtime,
+};
+
+/// An entry of the plugin's permission scopes: a path pattern, e.g. `{ "path": "$DOCUMENT/projects/**" }`, or a
+/// configured root, e.g. `{ "root": "appData" }`.
+///
+/// Commands accepting arbitrary paths only operate on paths allowed (and not denied) by the scopes
+/// of the permissions granted to the calling window, commands verifying a root only on allowed roots.
+#[derive(Debug, Deserialize)]
+pub struct ScopeEntry {
+    #[serde(default)]
+    pub path: PathBuf,
+    #[serde(default)]
+    pub root: Option<String>,
+}
+
+/// Returns whether `path` is allowed by the command and global scopes of the calling window.
+pub(crate) fn is_allowed<R: Runtime>(
+    app: &AppHandle<R>,
+    command_scope: &CommandScope<ScopeEntry>,
+    global_scope: &GlobalScope<ScopeEntry>,
+    path: &Path,
+) -> crate::Result<bool> {
+    let allow = command_scope
+        .allows()
+        .iter()
+        .chain(global_scope.allows())
+        .filter(|entry| entry.root.is_none())
+        .map(|entry| entry.path.clone())
+        .collect();
+    let deny = command_scope
+        .denies()
+        .iter()
+        .chain(global_scope.denies())
+        .filter(|entry| entry.root.is_none())
+        .map(|entry| entry.path.clone())
+        .collect();
+
+    let scope = tauri::scope::fs::Scope::new(
+        app,
+        &FsScope::Scope {
+            allow,
+            deny,
+            require_literal_leading_dot: None,
+        },
+    )?;
+
+    Ok(scope.is_allowed(path))
+}
+
+/// Returns whether the root named `root` (e.g. `appData`) is allowed by the command and global scopes of the
+/// calling window: an entry must allow it, and none deny it.
+pub(crate) fn is_root_allowed(
+    command_scope: &CommandScope<ScopeEntry>,
+    global_scope: &GlobalScope<ScopeEntry>,
+    root: &str,
+) -> bool {
+    let allowed = command_scope
+        .allows()
+        .iter()
+        .chain(global_scope.allows())
+        .any(|entry| entry.root.as_deref() == Some(root));
+    let denied = command_scope
+        .denies()
+        .iter()
+        .chain(global_scope.denies())
+        .any(|entry| entry.root.as_deref() == Some(root));
+
+    allowed && !denied
+}
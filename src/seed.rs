@@ -0,0 +1,112 @@
+use std::path::{Path, PathBuf};
+
+use tauri::{path::BaseDirectory, Manager, Runtime};
+
+use crate::{long_path::extended, models::*, roots};
+
+/// An entry of a seed source missing from the seeded directory.
+pub(crate) struct SeedEntry {
+    /// The entry in the seed source.
+    pub(crate) source: PathBuf,
+    /// Where the entry is copied to.
+    pub(crate) target: PathBuf,
+    pub(crate) kind: EntryKind,
+}
+
+/// Returns the directory mirrored into `path` by a `seedFrom` option, at the same path relative to its source as
+/// `path` is relative to the verified `root`.
+pub(crate) fn source_of<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    seed_from: SeedSource,
+    root: &Path,
+    path: &Path,
+) -> std::result::Result<PathBuf, String> {
+    let base = match seed_from {
+        SeedSource::Resource => roots::resolve(manager, BaseDirectory::Resource)?,
+    };
+    let relative = path.strip_prefix(root).unwrap_or(Path::new(""));
+    Ok(base.join(relative))
+}
+
+/// Returns the entries of `source` (recursively) missing from `target`, parents first.
+///
+/// Existing entries are never replaced, and symbolic links of the source are left out.
+pub(crate) fn missing_entries(
+    source: &Path,
+    target: &Path,
+) -> std::result::Result<Vec<SeedEntry>, String> {
+    let mut entries = Vec::new();
+    collect(source, target, target.exists(), &mut entries)?;
+    Ok(entries)
+}
+
+fn collect(
+    source: &Path,
+    target: &Path,
+    target_exists: bool,
+    entries: &mut Vec<SeedEntry>,
+) -> std::result::Result<(), String> {
+    let read_dir = match std::fs::read_dir(extended(source)) {
+        Ok(read_dir) => read_dir,
+        // Nothing to seed when the source has no such directory.
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(format!(
+                "Failed to read seed directory: {:?}, error: {:?}",
+                source, e
+            ))
+        }
+    };
+
+    let mut children: Vec<_> = read_dir
+        .filter_map(Result::ok)
+        .filter_map(|entry| Some((entry.file_name(), entry.file_type().ok()?)))
+        .collect();
+    children.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    for (name, file_type) in children {
+        let source = source.join(&name);
+        let target = target.join(&name);
+        let exists = target_exists && std::fs::symlink_metadata(extended(&target)).is_ok();
+        if file_type.is_dir() {
+            if !exists {
+                entries.push(SeedEntry {
+                    source: source.clone(),
+                    target: target.clone(),
+                    kind: EntryKind::Directory,
+                });
+            }
+            if !exists || extended(&target).is_dir() {
+                collect(&source, &target, exists, entries)?;
+            }
+        } else if file_type.is_file() && !exists {
+            entries.push(SeedEntry {
+                source,
+                target,
+                kind: EntryKind::File,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Copies a missing entry from its seed source, a directory is created empty.
+pub(crate) fn copy(
+    source: &Path,
+    target: &Path,
+    kind: EntryKind,
+) -> std::result::Result<(), String> {
+    match kind {
+        EntryKind::Directory => std::fs::create_dir_all(extended(target))
+            .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", target, e)),
+        EntryKind::File => std::fs::copy(extended(source), extended(target))
+            .map(|_| ())
+            .map_err(|e| {
+                format!(
+                    "Failed to copy {:?} to {:?}, error: {:?}",
+                    source, target, e
+                )
+            }),
+    }
+}
@@ -0,0 +1,107 @@
+use std::{collections::BTreeMap, fs::DirEntry, path::Path};
+
+use crate::{ignore::IgnoreSet, models::*};
+
+/// Takes a snapshot of a directory, describing its current contents as a structure item.
+///
+/// Entries are sorted by name (byte order, independent of the locale) so that snapshots, chunks
+/// and continuation tokens are stable between calls and machines. Entries matching `SnapshotOptions::ignore`
+/// or the `.structureignore` file of their directory are left out.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory.
+/// * `options` - The depth limit, chunk size and continuation token.
+///
+/// # Returns
+///
+/// Returns the chunk of the snapshot, or `Err(String)` if the directory can't be read.
+pub fn snapshot(
+    path: &Path,
+    options: &SnapshotOptions,
+) -> std::result::Result<SnapshotChunk, String> {
+    let mut entries = read_entries(path, options)?;
+    entries.sort_by_cached_key(|entry| entry.file_name().to_string_lossy().into_owned());
+
+    let start = match &options.continuation {
+        Some(token) => {
+            entries.partition_point(|entry| *entry.file_name().to_string_lossy() <= **token)
+        }
+        None => 0,
+    };
+    let end = match options.chunk_size {
+        Some(chunk_size) => (start + chunk_size.max(1)).min(entries.len()),
+        None => entries.len(),
+    };
+
+    let structure = describe(&entries[start..end], options.max_depth, options)?;
+    let continuation = if end < entries.len() {
+        entries[..end]
+            .last()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+    } else {
+        None
+    };
+
+    Ok(SnapshotChunk {
+        structure,
+        continuation,
+    })
+}
+
+/// Reads the entries of a directory, leaving out the ignored ones.
+fn read_entries(
+    path: &Path,
+    options: &SnapshotOptions,
+) -> std::result::Result<Vec<DirEntry>, String> {
+    let ignore = IgnoreSet::for_dir(path, options.ignore.iter().flatten())?;
+
+    let entries = std::fs::read_dir(path)
+        .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+    Ok(entries
+        .into_iter()
+        .filter(|entry| !ignore.is_ignored(&entry.file_name().to_string_lossy()))
+        .collect())
+}
+
+/// Describes entries as the files and directories of a structure item, descending `depth` more levels.
+fn describe(
+    entries: &[DirEntry],
+    depth: Option<usize>,
+    options: &SnapshotOptions,
+) -> std::result::Result<StructureItem, String> {
+    let mut files = Vec::new();
+    let mut dirs = BTreeMap::new();
+
+    for entry in entries {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let file_type = entry
+            .file_type()
+            .map_err(|e| format!("Failed to read entry: {:?}, error: {:?}", entry.path(), e))?;
+
+        if file_type.is_dir() {
+            let dir = match depth {
+                Some(0) => StructureItem::default(),
+                _ => describe(
+                    &read_entries(&entry.path(), options)?,
+                    depth.map(|d| d - 1),
+                    options,
+                )?,
+            };
+            dirs.insert(name, dir);
+        } else if file_type.is_symlink() && entry.path().is_dir() {
+            // Symbolic links to directories are described but not followed, to avoid cycles.
+            dirs.insert(name, StructureItem::default());
+        } else {
+            files.push(StructureFile::from(name));
+        }
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(StructureItem {
+        files: Some(files),
+        dirs: Some(dirs),
+        ..Default::default()
+    })
+}
@@ -0,0 +1,96 @@
+use std::path::Path;
+
+use crate::{error::Error, long_path::extended, models::*, seed::SeedEntry};
+
+/// The space a created directory or file takes besides its content, a block on most file systems, so a full volume
+/// is caught even when a repair writes no content.
+pub(crate) const ENTRY_SIZE: u64 = 4096;
+
+/// Verifies that the volume of `path` has `required` bytes available before a repair writes them, so it fails
+/// early instead of leaving a half-completed structure behind.
+///
+/// The path may not exist yet, the space is read on the volume of its closest existing ancestor. When it can't be
+/// read, the repair goes ahead.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the volume has room, or `Err(Error::InsufficientSpace)` otherwise.
+pub(crate) fn check(path: &Path, required: u64) -> std::result::Result<(), Error> {
+    if required == 0 {
+        return Ok(());
+    }
+
+    let Some(existing) = path
+        .ancestors()
+        .find(|ancestor| extended(ancestor).exists())
+    else {
+        return Ok(());
+    };
+    let available = match fs4::available_space(extended(existing)) {
+        Ok(available) => available,
+        Err(e) => {
+            log::warn!(
+                "Failed to read the available space of {:?}: {}",
+                existing,
+                e
+            );
+            return Ok(());
+        }
+    };
+
+    match required > available {
+        true => Err(Error::InsufficientSpace {
+            required,
+            available,
+        }),
+        false => Ok(()),
+    }
+}
+
+/// Returns the size of the files of seed entries.
+pub(crate) fn seed_size(entries: &[SeedEntry]) -> u64 {
+    entries
+        .iter()
+        .filter(|entry| entry.kind == EntryKind::File)
+        .filter_map(|entry| std::fs::metadata(extended(&entry.source)).ok())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Returns the space a file created with the given content, or empty without content, takes.
+pub(crate) fn content_size(content: Option<&FileContent>) -> u64 {
+    let length = content
+        .and_then(|content| content.to_bytes().ok())
+        .map_or(0, |bytes| bytes.len() as u64);
+    ENTRY_SIZE + length
+}
+
+/// Returns the space the operations of a repair plan take, see `operation_size`.
+pub(crate) fn operations_size(operations: &[RepairOperation]) -> u64 {
+    operations.iter().map(operation_size).sum()
+}
+
+/// Returns the space an operation of a repair plan takes, or nothing if its entry already exists. Downloads, whose
+/// size is only known once started, count for their entry only, and moves, archiving and deletions for nothing.
+pub(crate) fn operation_size(operation: &RepairOperation) -> u64 {
+    if std::fs::symlink_metadata(extended(operation.path())).is_ok() {
+        return 0;
+    }
+
+    match operation {
+        RepairOperation::ExtractArchive { archive, .. } => {
+            ENTRY_SIZE + crate::archive::extracted_size(archive).unwrap_or(0)
+        }
+        RepairOperation::CopyFile { source, .. } => {
+            ENTRY_SIZE
+                + std::fs::metadata(extended(source))
+                    .map(|metadata| metadata.len())
+                    .unwrap_or(0)
+        }
+        RepairOperation::CreateFile { content, .. } => content_size(Some(content)),
+        RepairOperation::CreateDir { .. } | RepairOperation::DownloadFile { .. } => ENTRY_SIZE,
+        RepairOperation::MoveDir { .. }
+        | RepairOperation::ArchiveEntry { .. }
+        | RepairOperation::Delete { .. } => 0,
+    }
+}
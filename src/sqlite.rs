@@ -0,0 +1,35 @@
+use std::path::Path;
+
+/// Runs `PRAGMA integrity_check` on a SQLite database, opened read-only so a verification never writes to it.
+///
+/// # Returns
+///
+/// Returns `Ok(())` if the database is intact, or `Err(String)` with the problems found, or why it can't be checked.
+#[cfg(feature = "sqlite")]
+pub(crate) fn integrity_check(path: &Path) -> std::result::Result<(), String> {
+    use rusqlite::{Connection, OpenFlags};
+
+    let connection = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("Failed to open database: {}", e))?;
+    let mut statement = connection
+        .prepare("PRAGMA integrity_check")
+        .map_err(|e| format!("Failed to check database: {}", e))?;
+    let problems = statement
+        .query_map([], |row| row.get::<_, String>(0))
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to check database: {}", e))?;
+
+    match problems.as_slice() {
+        [result] if result == "ok" => Ok(()),
+        problems => Err(problems.join(", ")),
+    }
+}
+
+/// Fails to check a SQLite database, integrity checks require the `sqlite` feature.
+#[cfg(not(feature = "sqlite"))]
+pub(crate) fn integrity_check(_path: &Path) -> std::result::Result<(), String> {
+    Err("the `sqlite` feature of the plugin is disabled".to_string())
+}
@@ -0,0 +1,211 @@
+use std::sync::{Condvar, Mutex};
+
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{
+    events,
+    models::*,
+    roots::{self, RootSelector},
+    StructureManagerExt,
+};
+
+/// How a root is verified during the setup of the plugin, see `verifyOnStartup`.
+#[derive(Clone, Copy, Debug)]
+struct Settings {
+    repair: bool,
+    in_background: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            repair: true,
+            in_background: false,
+        }
+    }
+}
+
+impl VerifyOnStartup {
+    /// Returns how the roots are verified, or `None` if they aren't.
+    fn settings(self) -> Option<Settings> {
+        match self {
+            VerifyOnStartup::Enabled(false) => None,
+            VerifyOnStartup::Enabled(true) => Some(Settings::default()),
+            VerifyOnStartup::Options(options) => Some(Settings {
+                repair: options.repair != Some(false),
+                in_background: options.in_background == Some(true),
+            }),
+        }
+    }
+}
+
+/// The outcome of the verification of the roots on startup, once it is done, see
+/// `StructureManagerExt::wait_until_ready`.
+#[derive(Default)]
+pub(crate) struct Readiness {
+    outcome: Mutex<Option<StartupVerification>>,
+    done: Condvar,
+}
+
+impl Readiness {
+    /// Records the outcome of the verification on startup, waking up the waiting callers, and emits the `ready` event.
+    fn finish<R: Runtime>(&self, app: &AppHandle<R>, outcome: StartupVerification) {
+        *self.outcome.lock().unwrap() = Some(outcome.clone());
+        self.done.notify_all();
+        log::info!(
+            "Structure ready, {} root(s) verified on startup, {} invalid",
+            outcome.verified.len(),
+            outcome.failed.len()
+        );
+        events::emit(app, events::READY, outcome);
+    }
+
+    /// Waits until the verification on startup is done, returning its outcome.
+    pub(crate) fn wait(&self) -> StartupVerification {
+        let mut outcome = self.outcome.lock().unwrap();
+        loop {
+            if let Some(outcome) = &*outcome {
+                return outcome.clone();
+            }
+            outcome = self.done.wait(outcome).unwrap();
+        }
+    }
+}
+
+/// Verifies the roots whose `verifyOnStartup` option is enabled (or the top-level one, unless they set their own), in
+/// the order of `verify_all`. The background verifications run one after the other on a thread of their own, the
+/// structure is ready once they are done (at once without any).
+///
+/// A failed verification is logged, and the setup goes on. With `failOnStartup`, every root is verified before the
+/// setup goes on, and the first invalid one fails it.
+///
+/// # Returns
+///
+/// Returns `Err(String)` with the error message of the first invalid root with `failOnStartup`, `Ok(())` otherwise.
+pub(crate) fn verify<R: Runtime>(app: &AppHandle<R>) -> std::result::Result<(), String> {
+    let (roots, fail_on_startup, skip_unresolved) = {
+        let state_mutex = app.state::<Mutex<StructureConfig>>();
+        let structure_config = state_mutex.lock().unwrap();
+        let fail_on_startup = structure_config.fail_on_startup == Some(true);
+        let default = structure_config
+            .verify_on_startup
+            .and_then(VerifyOnStartup::settings);
+
+        let mut items: Vec<(RootSelector, &StructureItem)> = roots::ROOTS
+            .into_iter()
+            .filter_map(|directory| {
+                let item = structure_config.root(directory)?;
+                Some((RootSelector::Base(directory), item))
+            })
+            .collect();
+        items.extend(
+            structure_config
+                .custom
+                .iter()
+                .flatten()
+                .map(|(name, custom)| (RootSelector::Custom(name.clone()), &custom.structure)),
+        );
+        // App group containers only exist on iOS, elsewhere their structures are ignored.
+        items.extend(
+            structure_config
+                .app_groups
+                .iter()
+                .filter(|_| cfg!(target_os = "ios"))
+                .flatten()
+                .map(|(group, item)| (RootSelector::AppGroup(group.clone()), item)),
+        );
+
+        let roots: Vec<(RootSelector, Settings)> = items
+            .into_iter()
+            .filter_map(|(root, item)| {
+                let settings = match item
+                    .options
+                    .as_ref()
+                    .and_then(|options| options.verify_on_startup)
+                {
+                    Some(verify_on_startup) => verify_on_startup.settings(),
+                    None => default,
+                };
+                match (settings, fail_on_startup) {
+                    (Some(settings), false) => Some((root, settings)),
+                    // An invalid root must fail the setup, so it can't be verified in the background.
+                    (Some(settings), true) => Some((
+                        root,
+                        Settings {
+                            in_background: false,
+                            ..settings
+                        },
+                    )),
+                    // A root that isn't meant to be verified on startup is only checked, not repaired.
+                    (None, true) => Some((
+                        root,
+                        Settings {
+                            repair: false,
+                            in_background: false,
+                        },
+                    )),
+                    (None, false) => None,
+                }
+            })
+            .collect();
+        let skip_unresolved = structure_config.skip_unresolved_roots == Some(true);
+        (roots, fail_on_startup, skip_unresolved)
+    };
+
+    let (background, foreground): (Vec<_>, Vec<_>) = roots
+        .into_iter()
+        .partition(|(_, settings)| settings.in_background);
+    let mut outcome = StartupVerification::default();
+    for (root, settings) in foreground {
+        if let Err(e) = verify_root(app, &root, settings, skip_unresolved, &mut outcome) {
+            if fail_on_startup {
+                return Err(e);
+            }
+        }
+    }
+    if background.is_empty() {
+        app.state::<Readiness>().finish(app, outcome);
+        return Ok(());
+    }
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        for (root, settings) in background {
+            let _ = verify_root(&app, &root, settings, skip_unresolved, &mut outcome);
+        }
+        app.state::<Readiness>().finish(&app, outcome);
+    });
+    Ok(())
+}
+
+/// Verifies a root on startup, recording how it went in `outcome`. A root skipped because it can't be resolved isn't
+/// recorded.
+fn verify_root<R: Runtime>(
+    app: &AppHandle<R>,
+    root: &RootSelector,
+    settings: Settings,
+    skip_unresolved: bool,
+    outcome: &mut StartupVerification,
+) -> std::result::Result<(), String> {
+    if let (true, RootSelector::Base(directory)) = (skip_unresolved, root) {
+        if roots::skip_unresolved(app, *directory) {
+            return Ok(());
+        }
+    }
+
+    log::info!("Verifying `{}` on startup", root.name());
+    let result = match settings.repair {
+        true => app.verify(root.clone()),
+        false => crate::verify_root(app, root, &|_| {}, false),
+    };
+    outcome.verified.push(root.name().to_string());
+    if let Err(e) = &result {
+        log::warn!(
+            "The verification of `{}` on startup failed: {}",
+            root.name(),
+            e
+        );
+        outcome.failed.insert(root.name().to_string(), e.clone());
+    }
+    result
+}
@@ -0,0 +1,31 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use crate::models::*;
+
+/// The statistics of the last verification of each root, see `StructureManagerExt::last_verification_stats`.
+#[derive(Default, Debug)]
+pub(crate) struct Stats(pub(crate) Mutex<BTreeMap<String, VerificationStats>>);
+
+impl Stats {
+    /// Records a finished verification, replacing the last one of its root.
+    pub(crate) fn record(&self, summary: &VerificationSummary) {
+        let mut stats = self.0.lock().unwrap();
+        let previous = stats.get(&summary.root);
+        let verifications = previous.map_or(0, |stats| stats.verifications) + 1;
+        let failures = previous.map_or(0, |stats| stats.failures) + summary.error.is_some() as u64;
+
+        stats.insert(
+            summary.root.clone(),
+            VerificationStats {
+                operation_id: summary.operation_id,
+                duration_ms: summary.duration_ms,
+                entries_checked: summary.counts.files + summary.counts.directories,
+                repaired: summary.counts.repaired,
+                warnings: summary.warnings.len(),
+                error: summary.error.clone(),
+                verifications,
+                failures,
+            },
+        );
+    }
+}
@@ -0,0 +1,65 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tauri::{Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use crate::models::*;
+
+/// The store file the last verification of each root is persisted to, see `StructureManagerExt::last_verification`.
+pub(crate) const STORE_PATH: &str = "structure-manager.json";
+
+/// Persists a finished verification as the last one of its root, replacing the previous one.
+///
+/// A verification never fails because of the store, errors are only logged.
+pub(crate) fn record<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    summary: &VerificationSummary,
+    config_hash: &str,
+) {
+    let last_verification = LastVerification {
+        root: summary.root.clone(),
+        verified_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64),
+        success: summary.error.is_none(),
+        error: summary.error.clone(),
+        config_hash: config_hash.to_string(),
+    };
+
+    let result = serde_json::to_value(&last_verification)
+        .map_err(|e| e.to_string())
+        .and_then(|value| {
+            let store = manager.store(STORE_PATH).map_err(|e| e.to_string())?;
+            store.set(summary.root.clone(), value);
+            store.save().map_err(|e| e.to_string())
+        });
+    if let Err(e) = result {
+        log::warn!(
+            "Failed to persist the last verification of `{}`: {}",
+            summary.root,
+            e
+        );
+    }
+}
+
+/// Returns the persisted last verification of a root, if it was ever verified.
+pub(crate) fn last<R: Runtime, M: Manager<R>>(
+    manager: &M,
+    root: &str,
+) -> std::result::Result<Option<LastVerification>, String> {
+    let store = manager
+        .store(STORE_PATH)
+        .map_err(|e| format!("Failed to open store {:?}, error: {}", STORE_PATH, e))?;
+
+    store
+        .get(root)
+        .map(|value| {
+            serde_json::from_value(value).map_err(|e| {
+                format!(
+                    "Invalid last verification of `{}` in store {:?}: {}",
+                    root, STORE_PATH, e
+                )
+            })
+        })
+        .transpose()
+}
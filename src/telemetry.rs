@@ -0,0 +1,18 @@
+use std::path::Path;
+
+use crate::models::*;
+
+/// Receives structure health data, registered with `Builder::telemetry` to forward it to an analytics backend.
+///
+/// Every method does nothing by default, so a sink only implements the data it forwards. Sinks are called on the
+/// thread running the operation, they should hand the data off rather than block.
+pub trait TelemetrySink: Send + Sync {
+    /// Called when a verification succeeds, with the payload of the `verification-finished` event.
+    fn on_verification_complete(&self, _summary: &VerificationSummary) {}
+
+    /// Called for each entry created or fixed by a repair, during a verification or when applying a repair plan.
+    fn on_repair(&self, _path: &Path) {}
+
+    /// Called when a verification fails, with the payload of the `verification-failed` event.
+    fn on_failure(&self, _summary: &VerificationSummary) {}
+}
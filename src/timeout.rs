@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{
+    hooks::Hooks,
+    messages::{Message, Translation},
+    models::*,
+};
+
+/// How long a timed out verification is waited for past its `timeout`, so one that is still walking gets to fail by
+/// itself, reporting its progress and lifecycle events.
+const GRACE: Duration = Duration::from_secs(1);
+
+/// The roots whose verification timed out and is still running in the background, keyed by path, with the flag their
+/// worker sets when it finishes.
+///
+/// A new verification of one of them is refused until it finishes, both would repair the same entries.
+#[derive(Default)]
+pub(crate) struct Abandoned(Mutex<HashMap<PathBuf, Arc<AtomicBool>>>);
+
+/// Marks the verification of a worker as finished when dropped, even by a panic, so its root can be verified again.
+struct Finished<R: Runtime> {
+    app: AppHandle<R>,
+    path: PathBuf,
+    finished: Arc<AtomicBool>,
+}
+
+impl<R: Runtime> Drop for Finished<R> {
+    fn drop(&mut self) {
+        let mut abandoned = self.app.state::<Abandoned>().inner().0.lock().unwrap();
+        self.finished.store(true, Ordering::Relaxed);
+        if abandoned
+            .get(&self.path)
+            .is_some_and(|finished| Arc::ptr_eq(finished, &self.finished))
+        {
+            abandoned.remove(&self.path);
+        }
+    }
+}
+
+enum Progress {
+    Event(VerificationEvent),
+    Done(std::result::Result<(), String>),
+}
+
+/// Returns the message of the `timedOut` violation of a verification aborted at `path`.
+pub(crate) fn timed_out(path: &Path, timeout: Duration) -> Message {
+    Message::new(
+        "verificationTimedOut",
+        format!(
+            "Verification timed out after {} seconds at {:?}",
+            timeout.as_secs(),
+            path
+        ),
+    )
+    .param("path", path.display())
+    .param("timeout", timeout.as_secs())
+}
+
+/// Runs the verification of a root, at `path`, on a thread of its own, forwarding its events to `on_event`, and
+/// stops waiting for it once it runs longer than `timeout`, e.g. stuck in a call to a hung network share.
+///
+/// A verification still walking the root fails by itself at its timeout, and doesn't repair anything past it. One that
+/// doesn't (within a grace period) is left to finish in the background, and a `timedOut` violation is reported in its
+/// place. Until it finishes, a new verification of the root is refused.
+///
+/// # Returns
+///
+/// Returns the result of the verification, or `Err(String)` with the message of the `timedOut` violation, or with an
+/// error message if a previous verification of the root is still running in the background.
+pub(crate) fn run<R: Runtime>(
+    app: AppHandle<R>,
+    path: &Path,
+    timeout: Duration,
+    on_event: &dyn Fn(VerificationEvent),
+    verification: impl FnOnce(&AppHandle<R>, &dyn Fn(VerificationEvent)) -> std::result::Result<(), String>
+        + Send
+        + 'static,
+) -> std::result::Result<(), String> {
+    if app
+        .state::<Abandoned>()
+        .0
+        .lock()
+        .unwrap()
+        .contains_key(path)
+    {
+        return Err(format!(
+            "A previous verification of {:?} timed out and is still running, it must finish first",
+            path
+        ));
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let worker = app.clone();
+    let finished = Arc::new(AtomicBool::new(false));
+    let guard = Finished {
+        app: app.clone(),
+        path: path.to_path_buf(),
+        finished: finished.clone(),
+    };
+    std::thread::spawn(move || {
+        let _guard = guard;
+        let events = sender.clone();
+        // Nobody listens to the events of a verification that timed out.
+        let forward = move |event| {
+            let _ = events.send(Progress::Event(event));
+        };
+        let result = verification(&worker, &forward);
+        let _ = sender.send(Progress::Done(result));
+    });
+
+    let deadline = Instant::now().checked_add(timeout.saturating_add(GRACE));
+    loop {
+        let remaining = deadline.map_or(Duration::MAX, |deadline| {
+            deadline.saturating_duration_since(Instant::now())
+        });
+        match receiver.recv_timeout(remaining) {
+            Ok(Progress::Event(event)) => on_event(event),
+            Ok(Progress::Done(result)) => return result,
+            Err(mpsc::RecvTimeoutError::Timeout) => break,
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                return Err(format!("The verification of {:?} panicked", path))
+            }
+        }
+    }
+
+    {
+        let mut abandoned = app.state::<Abandoned>().inner().0.lock().unwrap();
+        // The worker may have finished since, it is then no longer running.
+        if !finished.load(Ordering::Relaxed) {
+            abandoned.insert(path.to_path_buf(), finished);
+        }
+    }
+    log::warn!(
+        "The verification of {:?} didn't finish within {} seconds, it is left running",
+        path,
+        timeout.as_secs()
+    );
+    let message = timed_out(path, timeout);
+    let violation = Violation {
+        kind: ViolationKind::TimedOut,
+        code: ViolationKind::TimedOut.code(),
+        severity: Severity::Error,
+        path: path.to_path_buf(),
+        message: app.state::<Translation>().translate(&message),
+        key: message.key.to_string(),
+        params: message.params,
+        explanation: None,
+        help_url: None,
+    };
+    app.state::<Hooks>().violation(&violation);
+    let text = violation.message.clone();
+    on_event(VerificationEvent::Violation(violation));
+    Err(text)
+}
@@ -0,0 +1,138 @@
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path},
+};
+
+use crate::{long_path::extended, models::*};
+
+/// Builds the tree of a directory, combining its contents on disk with its declared structure.
+///
+/// # Arguments
+///
+/// * `path` - The path to the directory.
+/// * `structure_item` - The declared structure of the directory, if any.
+/// * `options` - The depth to expand and whether to include statuses.
+///
+/// # Returns
+///
+/// Returns the nodes of the directory sorted by name, or `Err(String)` if the directory can't be read.
+pub(crate) fn build(
+    path: &Path,
+    structure_item: Option<&StructureItem>,
+    options: &TreeOptions,
+) -> std::result::Result<Vec<TreeNode>, String> {
+    nodes(
+        path,
+        structure_item,
+        options.depth.unwrap_or(0),
+        options.include_status.unwrap_or(false),
+    )
+}
+
+/// Verifies that a path is relative and stays inside the directory it is relative to.
+pub(crate) fn check_relative(relative: &Path) -> std::result::Result<(), String> {
+    if relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+    {
+        Ok(())
+    } else {
+        Err(format!("Invalid relative path: {:?}", relative))
+    }
+}
+
+/// Returns the declared structure of a directory below a root item, if it is declared.
+pub(crate) fn declared_at<'a>(
+    root: &'a StructureItem,
+    relative: &Path,
+) -> Option<&'a StructureItem> {
+    let mut item = root;
+    for component in relative.components() {
+        if let Component::Normal(name) = component {
+            item = item.dirs.as_ref()?.get(name.to_string_lossy().as_ref())?;
+        }
+    }
+    Some(item)
+}
+
+fn nodes(
+    path: &Path,
+    structure_item: Option<&StructureItem>,
+    depth: usize,
+    include_status: bool,
+) -> std::result::Result<Vec<TreeNode>, String> {
+    // Entries found on disk, then declared entries, keyed by name so the output is sorted.
+    let mut entries: BTreeMap<String, (EntryKind, Option<u64>, bool)> = BTreeMap::new();
+
+    if extended(path).is_dir() {
+        let read_dir = std::fs::read_dir(extended(path))
+            .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        for entry in read_dir {
+            let entry = entry
+                .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+            let metadata = entry.metadata().map_err(|e| {
+                format!(
+                    "Failed to read entry: {:?}, error: {:?}",
+                    path.join(entry.file_name()),
+                    e
+                )
+            })?;
+            let (kind, size) = if metadata.is_dir() {
+                (EntryKind::Directory, None)
+            } else {
+                (EntryKind::File, Some(metadata.len()))
+            };
+            entries.insert(
+                entry.file_name().to_string_lossy().into_owned(),
+                (kind, size, true),
+            );
+        }
+    }
+
+    if let Some(structure_item) = structure_item {
+        for file in structure_item.files.iter().flatten() {
+            entries
+                .entry(file.name.clone())
+                .or_insert((EntryKind::File, None, false));
+        }
+        for dir in structure_item.dirs.iter().flat_map(|dirs| dirs.keys()) {
+            entries
+                .entry(dir.clone())
+                .or_insert((EntryKind::Directory, None, false));
+        }
+    }
+
+    let mut result = Vec::with_capacity(entries.len());
+    for (name, (kind, size, exists)) in entries {
+        let declared_dir = structure_item
+            .and_then(|item| item.dirs.as_ref())
+            .and_then(|dirs| dirs.get(&name));
+        let declared =
+            declared_dir.is_some() || structure_item.is_some_and(|item| item.declares(&name, None));
+
+        let status = include_status.then_some(match (declared, exists) {
+            (true, true) => TreeNodeStatus::Ok,
+            (true, false) => TreeNodeStatus::Missing,
+            (false, _) => TreeNodeStatus::Undeclared,
+        });
+        let children = match kind {
+            EntryKind::Directory if depth > 0 => Some(nodes(
+                &path.join(&name),
+                declared_dir,
+                depth - 1,
+                include_status,
+            )?),
+            _ => None,
+        };
+
+        result.push(TreeNode {
+            name,
+            kind,
+            status,
+            size,
+            children,
+        });
+    }
+
+    Ok(result)
+}
@@ -0,0 +1,183 @@
+use std::{io, path::Path, time::Duration};
+
+use crate::models::IoTuning;
+
+/// The kind of volume a path lives on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VolumeKind {
+    Local,
+    Network,
+}
+
+/// IO tuning with every option resolved for a given path.
+#[derive(Clone, Debug)]
+pub struct ResolvedIoTuning {
+    /// The kind of volume the path lives on.
+    pub volume: VolumeKind,
+    /// The number of threads used to check the entries of a directory.
+    pub parallelism: usize,
+    /// The size, in bytes, of the chunks read when hashing file contents.
+    pub hash_chunk_size: usize,
+    /// The maximum number of bytes read per second when reading file contents, if any.
+    pub io_throttle_bytes_per_sec: Option<u64>,
+    /// The number of times an IO operation failing with a transient error is retried.
+    pub retries: u32,
+    /// The delay before the first retry of an IO operation, doubled before each next one.
+    pub retry_backoff: Duration,
+}
+
+impl IoTuning {
+    /// Resolves the tuning for a path, options left unset adapt to the volume the path lives on.
+    ///
+    /// Local volumes check entries sequentially, as metadata calls are cheap, while network volumes
+    /// spread them over several threads and read larger chunks to hide latency.
+    pub fn resolve(&self, path: &Path) -> ResolvedIoTuning {
+        let volume = detect_volume(path);
+        let (parallelism, hash_chunk_size) = match volume {
+            VolumeKind::Local => (1, 64 * 1024),
+            VolumeKind::Network => (8, 1024 * 1024),
+        };
+
+        ResolvedIoTuning {
+            volume,
+            parallelism: self.parallelism.unwrap_or(parallelism).max(1),
+            hash_chunk_size: self.hash_chunk_size.unwrap_or(hash_chunk_size).max(1),
+            io_throttle_bytes_per_sec: self.io_throttle_bytes_per_sec,
+            retries: self.retries.unwrap_or(3),
+            retry_backoff: Duration::from_millis(self.retry_backoff_ms.unwrap_or(50)),
+        }
+    }
+}
+
+impl ResolvedIoTuning {
+    /// Runs an IO operation, retrying it with an exponential backoff while it fails with a transient error, see
+    /// [`is_transient`]. Other errors, e.g. a missing entry, are returned at once.
+    pub(crate) fn retry<T>(&self, mut operation: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut backoff = self.retry_backoff;
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Err(e) if attempt < self.retries && is_transient(&e) => {
+                    attempt += 1;
+                    log::debug!(
+                        "Retrying an IO operation in {:?} ({}/{}): {}",
+                        backoff,
+                        attempt,
+                        self.retries,
+                        e
+                    );
+                    std::thread::sleep(backoff);
+                    backoff = backoff.saturating_mul(2);
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Returns whether an IO error is likely transient, e.g. a file held open by an antivirus scanning it or a
+/// cloud-sync client uploading it: an interrupted or timed out call, a busy resource, and on Windows an access
+/// denied, sharing or lock violation, which is how a file locked by another process is reported.
+fn is_transient(error: &io::Error) -> bool {
+    match error.kind() {
+        io::ErrorKind::Interrupted | io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => true,
+        // ERROR_ACCESS_DENIED, ERROR_SHARING_VIOLATION and ERROR_LOCK_VIOLATION.
+        _ if cfg!(windows) => matches!(error.raw_os_error(), Some(5 | 32 | 33)),
+        // EBUSY.
+        _ => cfg!(unix) && error.raw_os_error() == Some(16),
+    }
+}
+
+/// Detects the kind of volume a path lives on, unknown volumes are considered local.
+pub fn detect_volume(path: &Path) -> VolumeKind {
+    // The path may not exist yet, so use its closest existing ancestor.
+    let path = match path
+        .ancestors()
+        .find_map(|ancestor| std::fs::canonicalize(ancestor).ok())
+    {
+        Some(path) => path,
+        None => return VolumeKind::Local,
+    };
+
+    detect_volume_of(&path)
+}
+
+#[cfg(target_os = "linux")]
+fn detect_volume_of(path: &Path) -> VolumeKind {
+    const NETWORK_FILESYSTEMS: &[&str] = &[
+        "nfs",
+        "nfs4",
+        "cifs",
+        "smb3",
+        "smbfs",
+        "afs",
+        "9p",
+        "ceph",
+        "glusterfs",
+        "davfs",
+        "fuse.sshfs",
+        "fuse.rclone",
+    ];
+
+    let mountinfo = match std::fs::read_to_string("/proc/self/mountinfo") {
+        Ok(mountinfo) => mountinfo,
+        Err(_) => return VolumeKind::Local,
+    };
+
+    // The mount point is the 5th field, the filesystem type is the first field after the ` - ` separator.
+    let mut best: Option<(usize, &str)> = None;
+    for line in mountinfo.lines() {
+        let (mount, fs_type) = match line.split_once(" - ") {
+            Some((left, right)) => match (left.split(' ').nth(4), right.split(' ').next()) {
+                (Some(mount), Some(fs_type)) => (unescape_mount_point(mount), fs_type),
+                _ => continue,
+            },
+            None => continue,
+        };
+        if path.starts_with(&mount) && best.map_or(true, |(len, _)| mount.len() >= len) {
+            best = Some((mount.len(), fs_type));
+        }
+    }
+
+    match best {
+        Some((_, fs_type)) if NETWORK_FILESYSTEMS.contains(&fs_type) => VolumeKind::Network,
+        _ => VolumeKind::Local,
+    }
+}
+
+/// Decodes the octal escapes (`\040` for a space) used in `/proc/self/mountinfo`.
+#[cfg(target_os = "linux")]
+fn unescape_mount_point(mount: &str) -> String {
+    let mut result = String::with_capacity(mount.len());
+    let mut chars = mount.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            let code: String = chars.by_ref().take(3).collect();
+            match u8::from_str_radix(&code, 8) {
+                Ok(byte) => result.push(byte as char),
+                Err(_) => {
+                    result.push(c);
+                    result.push_str(&code);
+                }
+            }
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+#[cfg(windows)]
+fn detect_volume_of(path: &Path) -> VolumeKind {
+    // Canonical paths of shares are UNC paths, `\\?\UNC\server\share\...`.
+    if path.to_string_lossy().starts_with(r"\\?\UNC\") {
+        VolumeKind::Network
+    } else {
+        VolumeKind::Local
+    }
+}
+
+#[cfg(not(any(target_os = "linux", windows)))]
+fn detect_volume_of(_path: &Path) -> VolumeKind {
+    VolumeKind::Local
+}
@@ -0,0 +1,8 @@
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+/// A domain-specific check run on a declared file, returning an error message when the file is invalid.
+pub type Validator = Arc<dyn Fn(&Path) -> std::result::Result<(), String> + Send + Sync>;
+
+/// The validators registered with `Builder::validator`.
+#[derive(Default)]
+pub(crate) struct Validators(pub(crate) HashMap<String, Validator>);
@@ -0,0 +1,2496 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, HashSet},
+    ffi::OsString,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use jsonschema::JSONSchema;
+use tauri::{Manager, Runtime};
+
+use crate::{
+    attributes::Permission,
+    audit::Finding,
+    events,
+    heartbeat::Monitor,
+    history::History,
+    hooks::Hooks,
+    ignore::{is_marker, IgnoreSet},
+    long_path::extended,
+    messages::{Message, Translation},
+    models::*,
+    normalization::{locate_in, normalize, same_name, Listing},
+    repair_scope::RepairScope,
+    seed,
+    stats::Stats,
+    tuning::ResolvedIoTuning,
+    validators::Validators,
+};
+
+/// The number of nested directory levels a verification descends into before failing, see
+/// `Builder::max_traversal_depth`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct MaxTraversalDepth(pub(crate) usize);
+
+impl Default for MaxTraversalDepth {
+    fn default() -> Self {
+        Self(256)
+    }
+}
+
+/// A directory whose declared subdirectories are being verified, see `Walker::verify_within`.
+struct Frame<'s> {
+    path: PathBuf,
+    structure_item: &'s StructureItem,
+    depth: Option<usize>,
+    dirs: std::iter::Flatten<std::option::Iter<'s, BTreeMap<String, StructureItem>>>,
+    /// The entries of the directory, listed once when it is entered.
+    listing: Option<Listing>,
+}
+
+/// What a walker needs from the app whose structure it verifies: the running app, see [`App`], or the app before it is
+/// built, see `Preflight`.
+pub(crate) trait Host {
+    /// Returns whether the `when` clause of a structure item holds, see `conditions::is_active`.
+    fn is_active(&self, structure_item: &StructureItem) -> std::result::Result<bool, String>;
+
+    /// Returns the validators registered with `Builder::validator`, or `None` if files aren't validated.
+    fn validators(&self) -> Option<&Validators>;
+
+    /// Compiles the `schema` option of a file, or returns `None` if files aren't checked against their schema.
+    fn compile_schema(
+        &self,
+        schema: &FileSchema,
+    ) -> std::result::Result<Option<JSONSchema>, String>;
+
+    /// Returns the hooks notified of the missing entries, repairs and violations.
+    fn hooks(&self) -> &Hooks;
+
+    /// Returns the translator of the violation messages, see `Builder::translator`.
+    fn translation(&self) -> &Translation;
+
+    /// Returns the entries repairs may create or delete, see `Builder::repair_scope`.
+    fn repair_scope(&self) -> &RepairScope;
+
+    /// Returns the path of the bundled archive a directory is extracted from, see `archive::source_of`.
+    fn archive_source(&self, archive: &str) -> std::result::Result<PathBuf, String>;
+
+    /// Returns the seed source of a directory, see `seed::source_of`.
+    fn seed_source(
+        &self,
+        seed_from: SeedSource,
+        root: &Path,
+        path: &Path,
+    ) -> std::result::Result<PathBuf, String>;
+
+    /// Downloads a missing file, see `download::download`.
+    fn download(
+        &self,
+        path: &Path,
+        url: &str,
+        sha256: Option<&str>,
+    ) -> std::result::Result<(), String>;
+}
+
+/// The running app, with the states of the plugin a walker uses.
+pub(crate) struct App<'a, R: Runtime, M: Manager<R>> {
+    manager: &'a M,
+    validators: &'a Validators,
+    hooks: &'a Hooks,
+    translation: &'a Translation,
+    repair_scope: &'a RepairScope,
+    _runtime: PhantomData<R>,
+}
+
+impl<R: Runtime, M: Manager<R>> Host for App<'_, R, M> {
+    fn is_active(&self, structure_item: &StructureItem) -> std::result::Result<bool, String> {
+        crate::conditions::is_active(self.manager, structure_item)
+    }
+
+    fn validators(&self) -> Option<&Validators> {
+        Some(self.validators)
+    }
+
+    fn compile_schema(
+        &self,
+        schema: &FileSchema,
+    ) -> std::result::Result<Option<JSONSchema>, String> {
+        crate::json_schema::compile(self.manager, schema).map(Some)
+    }
+
+    fn hooks(&self) -> &Hooks {
+        self.hooks
+    }
+
+    fn translation(&self) -> &Translation {
+        self.translation
+    }
+
+    fn repair_scope(&self) -> &RepairScope {
+        self.repair_scope
+    }
+
+    fn archive_source(&self, archive: &str) -> std::result::Result<PathBuf, String> {
+        crate::archive::source_of(self.manager, archive)
+    }
+
+    fn seed_source(
+        &self,
+        seed_from: SeedSource,
+        root: &Path,
+        path: &Path,
+    ) -> std::result::Result<PathBuf, String> {
+        seed::source_of(self.manager, seed_from, root, path)
+    }
+
+    fn download(
+        &self,
+        path: &Path,
+        url: &str,
+        sha256: Option<&str>,
+    ) -> std::result::Result<(), String> {
+        crate::download::download(self.manager, path, url, sha256)
+    }
+}
+
+/// Walks a structure item on disk, verifying and repairing it.
+pub(crate) struct Walker<'a, H: Host> {
+    host: H,
+    root: PathBuf,
+    tuning: ResolvedIoTuning,
+    max_traversal_depth: MaxTraversalDepth,
+    nested_roots: Vec<PathBuf>,
+    ignore: Vec<String>,
+    counts: Cell<VerificationCounts>,
+    warnings: RefCell<Vec<Violation>>,
+    info: RefCell<Vec<Violation>>,
+    aggregate: bool,
+    /// Whether the `repair` options are honored, see `with_repair`.
+    repair: bool,
+    /// Whether the repairs are only assumed, see `with_dry_run`.
+    dry_run: bool,
+    /// When the verification times out, and its `timeout`, see `with_timeout`.
+    deadline: Option<(Instant, Duration)>,
+    normalization: Option<UnicodeNormalization>,
+    errors: RefCell<Vec<String>>,
+    on_event: Option<&'a dyn Fn(VerificationEvent)>,
+    monitor: Option<Monitor>,
+    #[cfg(feature = "store")]
+    config_hash: String,
+}
+
+impl<'a, R: Runtime, M: Manager<R>> Walker<'a, App<'a, R, M>> {
+    /// Creates a walker for the root item of a verification of the running app, its `tuning` option is resolved for
+    /// `path`.
+    pub(crate) fn new(manager: &'a M, path: &Path, root: &StructureItem) -> Self {
+        let app = App {
+            manager,
+            validators: manager.state::<Validators>().inner(),
+            hooks: manager.state::<Hooks>().inner(),
+            translation: manager.state::<Translation>().inner(),
+            repair_scope: manager.state::<RepairScope>().inner(),
+            _runtime: PhantomData,
+        };
+        let mut walker = Self::with_host(app, path, root);
+        walker.max_traversal_depth = *manager.state::<MaxTraversalDepth>();
+        walker
+    }
+
+    /// Runs a verification of `root`, emitting the verification lifecycle events around it.
+    ///
+    /// Heartbeats are emitted while the verification runs longer than the heartbeat threshold.
+    pub(crate) fn run<F>(mut self, root: &str, verification: F) -> std::result::Result<(), String>
+    where
+        F: FnOnce(&Self) -> std::result::Result<(), String>,
+    {
+        let monitor = Monitor::start(self.host.manager, OperationKind::Verification, root);
+        let operation_id = monitor.id();
+        self.monitor = Some(monitor);
+        #[cfg(feature = "tracing")]
+        let span = tracing::info_span!(
+            "verification",
+            root,
+            operation_id,
+            files = tracing::field::Empty,
+            directories = tracing::field::Empty,
+            repaired = tracing::field::Empty,
+            duration_ms = tracing::field::Empty,
+        )
+        .entered();
+
+        events::emit(
+            self.host.manager,
+            events::VERIFICATION_STARTED,
+            VerificationStarted {
+                operation_id,
+                root: root.to_string(),
+            },
+        );
+
+        let start = Instant::now();
+        let result = verification(&self);
+        drop(self.monitor.take());
+        let summary = VerificationSummary {
+            operation_id,
+            root: root.to_string(),
+            duration_ms: start.elapsed().as_millis() as u64,
+            counts: self.counts.get(),
+            error: result.as_ref().err().cloned(),
+            warnings: self.warnings.take(),
+            info: self.info.take(),
+        };
+        #[cfg(feature = "tracing")]
+        {
+            span.record("files", summary.counts.files);
+            span.record("directories", summary.counts.directories);
+            span.record("repaired", summary.counts.repaired);
+            span.record("duration_ms", summary.duration_ms);
+            if let Some(error) = &summary.error {
+                tracing::error!(error = %error, "verification failed");
+            }
+        }
+
+        self.host.manager.state::<Stats>().record(&summary);
+        self.host
+            .manager
+            .state::<History>()
+            .record(self.host.manager, &summary);
+        #[cfg(feature = "store")]
+        crate::store::record(self.host.manager, &summary, &self.config_hash);
+        self.host.hooks.finished(&summary);
+
+        let event = match result {
+            Ok(()) => events::VERIFICATION_FINISHED,
+            Err(_) => events::VERIFICATION_FAILED,
+        };
+        events::emit(self.host.manager, event, summary);
+
+        result
+    }
+
+    /// Verifies that the top of an `exclusive` root contains no entry clearly belonging to other software.
+    ///
+    /// Declared entries, nested roots and `sharedEntries` are never reported.
+    pub(crate) fn verify_exclusive(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<(), String> {
+        if !extended(path).is_dir() {
+            return Ok(());
+        }
+
+        let shared_entries = structure_item
+            .options
+            .as_ref()
+            .and_then(|options| options.shared_entries.as_deref())
+            .unwrap_or_default();
+        let package_name = &self.host.manager.package_info().name;
+
+        let entries = std::fs::read_dir(extended(path))
+            .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        for entry in entries {
+            let entry = entry
+                .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if structure_item.declares(&name, self.normalization) || shared_entries.contains(&name)
+            {
+                continue;
+            }
+
+            let entry_path = path.join(entry.file_name());
+            if self
+                .nested_roots
+                .iter()
+                .any(|root| root.starts_with(&entry_path))
+            {
+                continue;
+            }
+
+            if let Some(reason) = crate::ownership::foreign_reason(&name, package_name) {
+                let message = Message::new(
+                    "foreignEntry",
+                    format!("Foreign entry: {:?} ({})", entry_path, reason),
+                )
+                .param("path", entry_path.display())
+                .param("reason", reason);
+                self.fail(ViolationKind::ForeignEntry, entry_path, message)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a, H: Host> Walker<'a, H> {
+    /// Creates a walker for the root item of a verification, its `tuning` option is resolved for `path`.
+    pub(crate) fn with_host(host: H, path: &Path, root: &StructureItem) -> Self {
+        let options = root.options.as_ref();
+        let tuning = options
+            .and_then(|options| options.tuning.clone())
+            .unwrap_or_default()
+            .resolve(path);
+        let aggregate = options.and_then(|options| options.aggregate) == Some(true);
+
+        Self {
+            host,
+            root: path.to_path_buf(),
+            tuning,
+            max_traversal_depth: MaxTraversalDepth::default(),
+            nested_roots: Vec::new(),
+            ignore: Vec::new(),
+            counts: Cell::default(),
+            warnings: RefCell::default(),
+            info: RefCell::default(),
+            aggregate,
+            repair: true,
+            dry_run: false,
+            deadline: None,
+            normalization: root.unicode_normalization(),
+            errors: RefCell::default(),
+            on_event: None,
+            monitor: None,
+            #[cfg(feature = "store")]
+            config_hash: crate::hash::config_hash(root),
+        }
+    }
+
+    /// Sets the paths of the other configured roots nested inside the verified root.
+    ///
+    /// A nested root owns its subtree: the walker doesn't descend into it, and strict mode doesn't flag it
+    /// (or the directories leading to it) as unexpected.
+    pub(crate) fn with_nested_roots(mut self, nested_roots: Vec<PathBuf>) -> Self {
+        self.nested_roots = nested_roots;
+        self
+    }
+
+    /// Sets the glob patterns of entry names ignored by strict comparisons in every directory.
+    pub(crate) fn with_ignore(mut self, ignore: Vec<String>) -> Self {
+        self.ignore = ignore;
+        self
+    }
+
+    /// Sets whether the `repair` options are honored, a walker that doesn't only verifies, whatever they are: nothing
+    /// is written to disk, and the entries pruning, retention and `deleteOldest` quotas would delete are reported.
+    pub(crate) fn with_repair(mut self, repair: bool) -> Self {
+        self.repair = repair;
+        self
+    }
+
+    /// Sets whether the repairs are only assumed: nothing is written to disk, and what a repair would fix is accepted,
+    /// e.g. a missing directory or a missing file with a default `content`, see `Preflight`.
+    pub(crate) fn with_dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Sets the `timeout` of the verification: past it, the next directory entered or left and the next repair fail
+    /// the verification with a `timedOut` violation.
+    pub(crate) fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.deadline = Instant::now()
+            .checked_add(timeout)
+            .map(|deadline| (deadline, timeout));
+        self
+    }
+
+    /// Sets a callback receiving the results of the verification as they are produced.
+    pub(crate) fn with_events(mut self, on_event: &'a dyn Fn(VerificationEvent)) -> Self {
+        self.on_event = Some(on_event);
+        self
+    }
+
+    /// Performs a depth-first search (DFS) verification of the structure of a directory.
+    ///
+    /// With the `aggregate` option, the errors found by `verify_exclusive` beforehand are returned too.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(())` if the directory structure is valid, or `Err(String)` with an error message if any issues are found.
+    pub(crate) fn verify(
+        &self,
+        path: PathBuf,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<(), String> {
+        self.verify_within(path.clone(), structure_item, None)?;
+        let options = structure_item.options.as_ref();
+        if options.and_then(|options| options.security_audit) == Some(true) {
+            self.audit(&path, structure_item)?;
+        }
+
+        let errors = self.errors.take();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("\n"))
+        }
+    }
+
+    /// Verifies a directory, descending at most `depth` levels of directories (`None` for no limit).
+    ///
+    /// The directories are walked depth-first with an explicit stack rather than recursively, so a deep structure
+    /// can't overflow the call stack. Descending past `Builder::max_traversal_depth` levels fails the verification.
+    fn verify_within(
+        &self,
+        path: PathBuf,
+        structure_item: &StructureItem,
+        depth: Option<usize>,
+    ) -> std::result::Result<(), String> {
+        let mut stack: Vec<Frame> = self
+            .enter(path, structure_item, depth)?
+            .into_iter()
+            .collect();
+        while let Some(frame) = stack.last_mut() {
+            let Some((dir_name, dir)) = frame.dirs.next() else {
+                if let Some(frame) = stack.pop() {
+                    self.leave(&frame.path, frame.structure_item, frame.listing.as_ref())?;
+                }
+                continue;
+            };
+            let depth = frame.depth;
+            let listing = frame.listing.as_ref();
+            let Some(dir_path) = self.verify_dir(&frame.path, listing, dir_name, dir)? else {
+                continue;
+            };
+            // Below the depth limit, directories are only checked for existence.
+            if let Some(0) = depth {
+                continue;
+            }
+            if stack.len() >= self.max_traversal_depth.0 {
+                let max = self.max_traversal_depth.0;
+                let message = Message::new(
+                    "traversalDepthExceeded",
+                    format!(
+                        "Directory {:?} is nested deeper than {} levels, its contents aren't verified",
+                        dir_path, max
+                    ),
+                )
+                .param("path", dir_path.display())
+                .param("max", max);
+                self.fail(ViolationKind::DepthLimitExceeded, dir_path, message)?;
+                continue;
+            }
+            stack.extend(self.enter(dir_path, dir, depth.map(|depth| depth - 1))?);
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the files of a directory, returning the frame verifying its declared subdirectories, or `None` if
+    /// its contents aren't verified.
+    ///
+    /// The directory is listed once, its declared entries are then matched against the listing instead of being
+    /// looked up one by one, which is much faster on network drives and scanned paths.
+    fn enter<'s>(
+        &self,
+        path: PathBuf,
+        structure_item: &'s StructureItem,
+        depth: Option<usize>,
+    ) -> std::result::Result<Option<Frame<'s>>, String> {
+        self.check_deadline(&path)?;
+        if !self.host.is_active(structure_item)? {
+            return Ok(None);
+        }
+        self.count(|counts| counts.directories += 1);
+        if let Some(monitor) = &self.monitor {
+            monitor.at(&path);
+        }
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("directory", path = %path.display()).entered();
+
+        // A redirected directory isn't walked, repairs could end up in another location.
+        let policy = structure_item.reparse_point_policy();
+        if policy != ReparsePointPolicy::Follow && crate::attributes::is_reparse_point(&path) {
+            let severity = match policy {
+                ReparsePointPolicy::Reject => structure_item.severity(),
+                _ => Severity::Warning,
+            };
+            let message = Message::new(
+                "reparsePoint",
+                format!(
+                    "Directory {:?} is a junction or reparse point, its contents aren't verified",
+                    path
+                ),
+            )
+            .param("path", path.display())
+            .explain(structure_item.explanation());
+            return self
+                .report(severity, ViolationKind::ReparsePoint, path, message)
+                .map(|()| None);
+        }
+
+        let depth = structure_item.depth_limit(depth);
+
+        let repair = self.repair
+            && structure_item
+                .options
+                .as_ref()
+                .and_then(|options| options.repair)
+                == Some(true);
+        let seeded = repair && structure_item.seed_from().is_some();
+        if let (true, Some(seed_from)) = (repair && !self.dry_run, structure_item.seed_from()) {
+            self.seed(&path, seed_from)?;
+        }
+        // Listed once seeded, the entries the seed source adds are flagged by strict mode like any other.
+        let listing = self.tuning.retry(|| Listing::read(&path)).ok();
+        if let Some(files) = &structure_item.files {
+            self.count(|counts| counts.files += files.len());
+            let created = if repair {
+                self.create_files(&path, listing.as_ref(), files, seeded)?
+            } else {
+                HashSet::new()
+            };
+            // When aggregating, every missing file is reported by the loop below instead.
+            if !self.aggregate {
+                if let Some(file) = self.first_missing(&path, listing.as_ref(), files, &created) {
+                    let file_path = path.join(&file.name);
+                    self.missing(&file_path, EntryKind::File);
+                    let message = not_found(&file_path).explain(file.explanation());
+                    return Err(self.violation(ViolationKind::MissingFile, file_path, message));
+                }
+            }
+
+            for file in files {
+                let Some(file_path) =
+                    locate_in(listing.as_ref(), &path, &file.name, self.normalization)
+                else {
+                    if created.contains(file.name.as_str()) {
+                        continue;
+                    }
+                    let file_path = path.join(&file.name);
+                    self.missing(&file_path, EntryKind::File);
+                    let message = not_found(&file_path);
+                    self.report(
+                        file.missing_severity(),
+                        ViolationKind::MissingFile,
+                        file_path,
+                        message.explain(file.explanation()),
+                    )?;
+                    continue;
+                };
+                if let (Some(name), Some(validators)) =
+                    (&file.options.validator, self.host.validators())
+                {
+                    let validator = validators
+                        .0
+                        .get(name)
+                        .ok_or_else(|| format!("Validator `{}` not registered", name))?;
+                    if let Err(e) = validator(&file_path) {
+                        let message = Message::new(
+                            "validationFailed",
+                            format!("Validation `{}` failed for {:?}: {}", name, file_path, e),
+                        )
+                        .param("path", file_path.display())
+                        .param("validator", name)
+                        .param("error", e);
+                        self.report(
+                            file.severity(),
+                            ViolationKind::ValidationFailed,
+                            file_path,
+                            message.explain(file.explanation()),
+                        )?;
+                        continue;
+                    }
+                }
+                if file.options.sqlite_integrity == Some(true) {
+                    if let Err(e) = crate::sqlite::integrity_check(&file_path) {
+                        let message = Message::new(
+                            "validationFailed",
+                            format!(
+                                "Validation `sqliteIntegrity` failed for {:?}: {}",
+                                file_path, e
+                            ),
+                        )
+                        .param("path", file_path.display())
+                        .param("validator", "sqliteIntegrity")
+                        .param("error", e);
+                        self.report(
+                            file.severity(),
+                            ViolationKind::ValidationFailed,
+                            file_path,
+                            message.explain(file.explanation()),
+                        )?;
+                        continue;
+                    }
+                }
+                if let Some(format) = file.options.format {
+                    if let Err(e) = format.check(&file_path) {
+                        let message = Message::new(
+                            "invalidFormat",
+                            format!("File {:?} isn't valid {}: {}", file_path, format.name(), e),
+                        )
+                        .param("path", file_path.display())
+                        .param("format", format.name())
+                        .param("error", e);
+                        self.report(
+                            file.severity(),
+                            ViolationKind::InvalidFormat,
+                            file_path,
+                            message.explain(file.explanation()),
+                        )?;
+                        continue;
+                    }
+                }
+                let schema = match &file.options.schema {
+                    Some(schema) => self.host.compile_schema(schema)?,
+                    None => None,
+                };
+                if let Some(schema) = schema {
+                    if let Err(e) = crate::json_schema::validate(&schema, &file_path) {
+                        let message = Message::new(
+                            "schemaViolation",
+                            format!(
+                                "File {:?} doesn't conform to its JSON Schema: {}",
+                                file_path, e
+                            ),
+                        )
+                        .param("path", file_path.display())
+                        .param("error", e);
+                        self.report(
+                            file.severity(),
+                            ViolationKind::SchemaViolation,
+                            file_path,
+                            message.explain(file.explanation()),
+                        )?;
+                        continue;
+                    }
+                }
+                if let Some(attributes) = &file.options.attributes {
+                    if !self.verify_attributes(
+                        &file_path,
+                        EntryKind::File,
+                        attributes,
+                        repair,
+                        file.severity(),
+                    )? {
+                        continue;
+                    }
+                }
+                let mut matching = true;
+                for permission in file.permissions() {
+                    matching &=
+                        self.verify_permission(&file_path, permission, repair, file.severity())?;
+                }
+                if !matching {
+                    continue;
+                }
+                let owned = file.options.owner.is_some() || file.options.group.is_some();
+                if owned
+                    && !self.verify_owner(
+                        &file_path,
+                        EntryKind::File,
+                        (file.options.owner.as_deref(), file.options.group.as_deref()),
+                        repair,
+                        file.severity(),
+                    )?
+                {
+                    continue;
+                }
+                self.entry(file_path, EntryKind::File, EntryStatus::Ok);
+            }
+        }
+
+        Ok(Some(Frame {
+            dirs: structure_item.dirs.iter().flatten(),
+            path,
+            structure_item,
+            depth,
+            listing,
+        }))
+    }
+
+    /// Verifies a declared subdirectory of a directory, repairing it when it is missing.
+    ///
+    /// # Returns
+    ///
+    /// Returns the path of the subdirectory if its contents are to be verified, `None` otherwise.
+    fn verify_dir(
+        &self,
+        path: &Path,
+        listing: Option<&Listing>,
+        dir_name: &str,
+        dir: &StructureItem,
+    ) -> std::result::Result<Option<PathBuf>, String> {
+        if !self.host.is_active(dir)? {
+            return Ok(None);
+        }
+
+        let dir_path = path.join(dir_name);
+        if self.nested_roots.contains(&dir_path) {
+            log::debug!(
+                "Skipping {:?}, it is verified as another configured root",
+                dir_path
+            );
+            return Ok(None);
+        }
+
+        // A missing directory is created with its name in the normalization form.
+        let (dir_path, exists) = match locate_in(listing, path, dir_name, self.normalization) {
+            Some(dir_path) => (dir_path, true),
+            None => (path.join(&*normalize(dir_name, self.normalization)), false),
+        };
+        if exists {
+            self.entry(dir_path.clone(), EntryKind::Directory, EntryStatus::Ok);
+        } else {
+            self.missing(&dir_path, EntryKind::Directory);
+
+            if let Some(options) = &dir.options {
+                let mut repair = false;
+                if let Some(value) = options.repair {
+                    repair = value && self.repair;
+                }
+
+                if repair && self.dry_run {
+                    return Ok(Some(dir_path));
+                }
+                if repair {
+                    self.check_deadline(&dir_path)?;
+                    if let Err(e) = self.host.repair_scope().check(Some(&self.root), &dir_path) {
+                        let message = Message::new("repairOutOfScope", e.clone())
+                            .param("path", dir_path.display())
+                            .param("error", e);
+                        self.fail(ViolationKind::RepairFailed, dir_path, message)?;
+                        return Ok(None);
+                    }
+                    if let Some(legacy) = crate::legacy::locate(path, dir, self.normalization) {
+                        // The legacy directory keeps the user's data, it is renamed rather than duplicated.
+                        let renamed = self
+                            .host
+                            .repair_scope()
+                            .check(Some(&self.root), &legacy)
+                            .and_then(|()| crate::legacy::rename(&legacy, &dir_path));
+                        if let Err(e) = renamed {
+                            let message = Message::new("moveLegacyFailed", e.clone())
+                                .param("path", dir_path.display())
+                                .param("from", legacy.display())
+                                .param("error", e);
+                            self.fail(ViolationKind::RepairFailed, dir_path, message)?;
+                            return Ok(None);
+                        }
+                    } else if let Some(archive) = &options.archive {
+                        let source = self.host.archive_source(archive);
+                        if let Ok(source) = &source {
+                            let required = crate::archive::extracted_size(source).unwrap_or(0);
+                            if !self.has_space(&dir_path, required)? {
+                                return Ok(None);
+                            }
+                        }
+                        let extracted =
+                            source.and_then(|archive| crate::archive::extract(&archive, &dir_path));
+                        if let Err(e) = extracted {
+                            let message = Message::new("extractArchiveFailed", e.clone())
+                                .param("path", dir_path.display())
+                                .param("error", e);
+                            self.fail(ViolationKind::RepairFailed, dir_path, message)?;
+                            return Ok(None);
+                        }
+                    } else {
+                        if !self.has_space(&dir_path, crate::space::ENTRY_SIZE)? {
+                            return Ok(None);
+                        }
+                        if let Err(e) = self
+                            .tuning
+                            .retry(|| std::fs::create_dir_all(extended(&dir_path)))
+                        {
+                            let message = Message::new(
+                                "createDirectoryFailed",
+                                format!(
+                                    "Failed to create directory: {:?}, error: {:?}",
+                                    dir_path, e
+                                ),
+                            )
+                            .param("path", dir_path.display())
+                            .param("error", e);
+                            self.fail(ViolationKind::RepairFailed, dir_path, message)?;
+                            return Ok(None);
+                        }
+                    }
+
+                    if let Some(template) = options.acl {
+                        if let Err(e) = crate::acl::apply(&dir_path, template) {
+                            let message = Message::new("aclFailed", e.clone())
+                                .param("path", dir_path.display())
+                                .param("error", e);
+                            self.fail(ViolationKind::RepairFailed, dir_path, message)?;
+                            return Ok(None);
+                        }
+                    }
+                    self.repaired(&dir_path, EntryKind::Directory);
+                } else {
+                    let message = Message::new(
+                        "directoryNotFound",
+                        format!("Directory not found: {:?}.", dir_path),
+                    )
+                    .param("path", dir_path.display());
+                    self.report(
+                        dir.missing_severity(),
+                        ViolationKind::MissingDirectory,
+                        dir_path,
+                        message.explain(dir.explanation()),
+                    )?;
+                    return Ok(None);
+                }
+            }
+        }
+        if let Some(options) = &dir.options {
+            if let Some(attributes) = &options.attributes {
+                self.verify_attributes(
+                    &dir_path,
+                    EntryKind::Directory,
+                    attributes,
+                    self.repair && options.repair == Some(true),
+                    dir.severity(),
+                )?;
+            }
+            if options.owner.is_some() || options.group.is_some() {
+                self.verify_owner(
+                    &dir_path,
+                    EntryKind::Directory,
+                    (options.owner.as_deref(), options.group.as_deref()),
+                    self.repair && options.repair == Some(true),
+                    dir.severity(),
+                )?;
+            }
+            if let Some(template) = options.acl {
+                self.verify_acl(
+                    &dir_path,
+                    template,
+                    self.repair && options.repair == Some(true),
+                    dir.severity(),
+                )?;
+            }
+        }
+        Ok(Some(dir_path))
+    }
+
+    /// Enforces the options of a directory covering all of its entries, once its subdirectories are verified.
+    fn leave(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+        listing: Option<&Listing>,
+    ) -> std::result::Result<(), String> {
+        self.check_deadline(path)?;
+        if let Some(options) = &structure_item.options {
+            if options.prune_empty_dirs == Some(true) {
+                self.prune_empty_dirs(path, structure_item)?;
+            }
+
+            if let Some(retention) = &options.retention {
+                self.enforce_retention(path, structure_item, retention)?;
+            }
+
+            if options.strict == Some(true) {
+                // Pruning and retention delete entries, the directory is listed again after them.
+                let mutated = self.repair
+                    && !self.dry_run
+                    && (options.prune_empty_dirs == Some(true) || options.retention.is_some());
+                self.verify_strict(path, structure_item, listing.filter(|_| !mutated))?;
+            }
+
+            if let Some(max_children) = options.max_children {
+                self.enforce_max_children(
+                    path,
+                    structure_item,
+                    max_children,
+                    options.quota_policy.unwrap_or_default(),
+                )?;
+            }
+
+            if let Some(max_entries) = options.max_entries {
+                self.enforce_max_entries(
+                    path,
+                    structure_item,
+                    max_entries,
+                    options.quota_policy.unwrap_or_default(),
+                )?;
+            }
+
+            if let Some(max_total_size) = options.max_total_size {
+                self.enforce_max_total_size(
+                    path,
+                    structure_item,
+                    max_total_size,
+                    options.quota_policy.unwrap_or_default(),
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verifies the Windows attributes of an existing entry, setting the expected ones when `repair` is enabled.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the attributes match or were repaired, `Ok(false)` if a mismatch was reported without
+    /// failing the verification, or `Err(String)` with an error message otherwise.
+    fn verify_attributes(
+        &self,
+        path: &Path,
+        kind: EntryKind,
+        attributes: &EntryAttributes,
+        repair: bool,
+        severity: Severity,
+    ) -> std::result::Result<bool, String> {
+        let mismatches = crate::attributes::mismatches(path, attributes)?;
+        if mismatches.is_empty() {
+            return Ok(true);
+        }
+
+        if repair && self.dry_run {
+            return Ok(true);
+        }
+        if repair {
+            self.check_deadline(path)?;
+            if let Err(e) = crate::attributes::apply(path, attributes) {
+                let message = Message::new("setAttributesFailed", e.clone())
+                    .param("path", path.display())
+                    .param("error", e);
+                self.fail(ViolationKind::RepairFailed, path.to_path_buf(), message)?;
+                return Ok(false);
+            }
+            self.repaired(path, kind);
+            return Ok(true);
+        }
+
+        let expected = crate::attributes::describe(&mismatches);
+        let message = Message::new(
+            "attributeMismatch",
+            format!("Entry {:?} should be {}", path, expected),
+        )
+        .param("path", path.display())
+        .param("attributes", expected);
+        self.report(
+            severity,
+            ViolationKind::AttributeMismatch,
+            path.to_path_buf(),
+            message,
+        )?;
+        Ok(false)
+    }
+
+    /// Verifies that an existing file has or lacks a permission as expected, changing it when `repair` is enabled.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the file matches or was repaired, `Ok(false)` if a mismatch was reported without failing
+    /// the verification, or `Err(String)` with an error message otherwise.
+    fn verify_permission(
+        &self,
+        path: &Path,
+        (permission, expected): (Permission, bool),
+        repair: bool,
+        severity: Severity,
+    ) -> std::result::Result<bool, String> {
+        if permission.is_set(path)? == expected {
+            return Ok(true);
+        }
+
+        if repair && self.dry_run {
+            return Ok(true);
+        }
+        if repair {
+            self.check_deadline(path)?;
+            if let Err(e) = permission.set(path, expected) {
+                let message = Message::new("setAttributesFailed", e.clone())
+                    .param("path", path.display())
+                    .param("error", e);
+                self.fail(ViolationKind::RepairFailed, path.to_path_buf(), message)?;
+                return Ok(false);
+            }
+            self.repaired(path, EntryKind::File);
+            return Ok(true);
+        }
+
+        let expected = crate::attributes::describe(&[(permission.name(), expected)]);
+        let message = Message::new(
+            "attributeMismatch",
+            format!("Entry {:?} should be {}", path, expected),
+        )
+        .param("path", path.display())
+        .param("attributes", expected);
+        self.report(
+            severity,
+            ViolationKind::AttributeMismatch,
+            path.to_path_buf(),
+            message,
+        )?;
+        Ok(false)
+    }
+
+    /// Verifies the owner and group of an existing entry on Unix, changing them when `repair` is enabled.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the owner and group match or were repaired, `Ok(false)` if a mismatch was reported
+    /// without failing the verification, or `Err(String)` with an error message otherwise.
+    fn verify_owner(
+        &self,
+        path: &Path,
+        kind: EntryKind,
+        (owner, group): (Option<&str>, Option<&str>),
+        repair: bool,
+        severity: Severity,
+    ) -> std::result::Result<bool, String> {
+        let mismatches = crate::owner::mismatches(path, owner, group)?;
+        if mismatches.is_empty() {
+            return Ok(true);
+        }
+
+        if repair && self.dry_run {
+            return Ok(true);
+        }
+        if repair {
+            self.check_deadline(path)?;
+            if let Err(e) = crate::owner::apply(path, owner, group) {
+                let message = Message::new("setOwnerFailed", e.clone())
+                    .param("path", path.display())
+                    .param("error", e);
+                self.fail(ViolationKind::RepairFailed, path.to_path_buf(), message)?;
+                return Ok(false);
+            }
+            self.repaired(path, kind);
+            return Ok(true);
+        }
+
+        let expected = crate::owner::describe(&mismatches);
+        let message = Message::new(
+            "ownerMismatch",
+            format!("Entry {:?} should be {}", path, expected),
+        )
+        .param("path", path.display())
+        .param("owner", expected);
+        self.report(
+            severity,
+            ViolationKind::OwnerMismatch,
+            path.to_path_buf(),
+            message,
+        )?;
+        Ok(false)
+    }
+
+    /// Verifies that the ACL of an existing directory matches its template, applying the template when `repair` is
+    /// enabled.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the ACL matches or was repaired, `Ok(false)` if a mismatch was reported without failing
+    /// the verification, or `Err(String)` with an error message otherwise.
+    fn verify_acl(
+        &self,
+        path: &Path,
+        template: AclTemplate,
+        repair: bool,
+        severity: Severity,
+    ) -> std::result::Result<bool, String> {
+        if !crate::acl::differs(path, template)? {
+            return Ok(true);
+        }
+
+        if repair && self.dry_run {
+            return Ok(true);
+        }
+        if repair {
+            self.check_deadline(path)?;
+            if let Err(e) = crate::acl::apply(path, template) {
+                let message = Message::new("aclFailed", e.clone())
+                    .param("path", path.display())
+                    .param("error", e);
+                self.fail(ViolationKind::RepairFailed, path.to_path_buf(), message)?;
+                return Ok(false);
+            }
+            self.repaired(path, EntryKind::Directory);
+            return Ok(true);
+        }
+
+        let name = crate::acl::name(template);
+        let message = Message::new(
+            "aclMismatch",
+            format!("The ACL of {:?} doesn't match the {} template", path, name),
+        )
+        .param("path", path.display())
+        .param("acl", name);
+        self.report(
+            severity,
+            ViolationKind::AclMismatch,
+            path.to_path_buf(),
+            message,
+        )?;
+        Ok(false)
+    }
+
+    /// Verifies that a directory doesn't contain entries that aren't declared by its structure item, reusing its
+    /// listing when it is still up to date.
+    fn verify_strict(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+        listing: Option<&Listing>,
+    ) -> std::result::Result<(), String> {
+        if !extended(path).is_dir() {
+            return Ok(());
+        }
+
+        let own_ignore = structure_item
+            .options
+            .as_ref()
+            .and_then(|options| options.ignore.as_deref())
+            .unwrap_or_default();
+        let ignore = IgnoreSet::for_dir(path, self.ignore.iter().chain(own_ignore))?;
+
+        let names: Vec<OsString> = match listing {
+            Some(listing) => listing.names().cloned().collect(),
+            None => self
+                .tuning
+                .retry(|| Listing::read(path))
+                .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?
+                .names()
+                .cloned()
+                .collect(),
+        };
+        for file_name in names {
+            let name = file_name.to_string_lossy().into_owned();
+            if structure_item.declares(&name, self.normalization) || ignore.is_ignored(&name) {
+                continue;
+            }
+
+            let entry_path = path.join(&file_name);
+            if self
+                .nested_roots
+                .iter()
+                .any(|root| root.starts_with(&entry_path))
+            {
+                continue;
+            }
+            // A listed entry may have been moved since, e.g. a legacy directory renamed by the repair.
+            if listing.is_some() && std::fs::symlink_metadata(extended(&entry_path)).is_err() {
+                continue;
+            }
+
+            let message = Message::new(
+                "unexpectedEntry",
+                format!("Unexpected entry: {:?}", entry_path),
+            )
+            .param("path", entry_path.display());
+            self.report(
+                structure_item.severity(),
+                ViolationKind::UnexpectedEntry,
+                entry_path,
+                message.explain(structure_item.explanation()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the root of the verification when it doesn't exist, with the `createRoot` option.
+    pub(crate) fn create_root(&self, path: &Path) -> std::result::Result<(), String> {
+        if extended(path).is_dir() || self.dry_run {
+            return Ok(());
+        }
+
+        self.check_deadline(path)?;
+        let result = self
+            .host
+            .repair_scope()
+            .check(Some(&self.root), path)
+            .map_err(|e| ("repairOutOfScope", e));
+        if result.is_ok() && !self.has_space(path, crate::space::ENTRY_SIZE)? {
+            return Ok(());
+        }
+        let result = result.and_then(|()| {
+            self.tuning
+                .retry(|| std::fs::create_dir_all(extended(path)))
+                .map_err(|e| {
+                    (
+                        "createDirectoryFailed",
+                        format!("Failed to create directory: {:?}, error: {:?}", path, e),
+                    )
+                })
+        });
+        match result {
+            Ok(()) => {
+                log::info!("Created root {:?}", path);
+                self.repaired(path, EntryKind::Directory);
+                Ok(())
+            }
+            Err((key, e)) => {
+                let message = Message::new(key, e.clone())
+                    .param("path", path.display())
+                    .param("error", e);
+                self.fail(ViolationKind::RepairFailed, path.to_path_buf(), message)
+            }
+        }
+    }
+
+    /// Reports the findings of the `securityAudit` option as warnings.
+    fn audit(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<(), String> {
+        let findings =
+            crate::audit::audit(path, structure_item, self.normalization, &self.nested_roots)?;
+        for (entry_path, finding) in findings {
+            let message = match finding {
+                Finding::WorldWritable => Message::new(
+                    "worldWritable",
+                    format!("World-writable directory: {:?}", entry_path),
+                ),
+                Finding::Setuid => Message::new(
+                    "setuidFile",
+                    format!("Setuid or setgid file: {:?}", entry_path),
+                ),
+                Finding::UnexpectedOwner { expected, found } => Message::new(
+                    "unexpectedOwner",
+                    format!(
+                        "Unexpected owner: {:?} (expected: {}, found: {})",
+                        entry_path, expected, found
+                    ),
+                )
+                .param("expected", expected)
+                .param("found", found),
+            }
+            .param("path", entry_path.display());
+            self.report(
+                Severity::Warning,
+                ViolationKind::SecurityIssue,
+                entry_path,
+                message,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Reports a warning if the version marker of the root records another version than `version`, the version of
+    /// the structure, so the app knows the root needs a migration.
+    pub(crate) fn verify_version(
+        &self,
+        path: &Path,
+        version: u32,
+    ) -> std::result::Result<(), String> {
+        let found = match crate::migrations::root_version(path)? {
+            Some(found) if found != version => found,
+            _ => return Ok(()),
+        };
+
+        let marker = path.join(crate::migrations::VERSION_MARKER);
+        let message = Message::new(
+            "structureVersionMismatch",
+            format!(
+                "Structure version mismatch: {:?} (expected: {}, found: {})",
+                path, version, found
+            ),
+        )
+        .param("path", path.display())
+        .param("expected", version)
+        .param("found", found);
+        self.report(
+            Severity::Warning,
+            ViolationKind::VersionMismatch,
+            marker,
+            message,
+        )
+    }
+
+    /// Notifies the missing hooks and the event callback of a missing entry.
+    fn missing(&self, path: &Path, kind: EntryKind) {
+        self.host.hooks().missing(path, kind);
+        self.entry(path.to_path_buf(), kind, EntryStatus::Missing);
+    }
+
+    /// Counts a repaired entry, notifying the repaired hooks and the event callback.
+    fn repaired(&self, path: &Path, kind: EntryKind) {
+        self.count(|counts| counts.repaired += 1);
+        self.host.hooks().repaired(path);
+        self.entry(path.to_path_buf(), kind, EntryStatus::Repaired);
+    }
+
+    fn entry(&self, path: PathBuf, kind: EntryKind, status: EntryStatus) {
+        if let Some(on_event) = self.on_event {
+            on_event(VerificationEvent::Entry { path, kind, status });
+        }
+    }
+
+    fn count(&self, update: impl FnOnce(&mut VerificationCounts)) {
+        let mut counts = self.counts.get();
+        update(&mut counts);
+        self.counts.set(counts);
+    }
+
+    /// Notifies the violation hooks, returning the (translated) message of the violation.
+    fn violation(&self, kind: ViolationKind, path: PathBuf, message: Message) -> String {
+        let violation = Violation {
+            kind,
+            code: kind.code(),
+            severity: Severity::Error,
+            path,
+            message: self.host.translation().translate(&message),
+            key: message.key.to_string(),
+            params: message.params,
+            explanation: message.explanation,
+            help_url: message.help_url,
+        };
+        self.host.hooks().violation(&violation);
+        let message = violation.message.clone();
+        if let Some(on_event) = self.on_event {
+            on_event(VerificationEvent::Violation(violation));
+        }
+        message
+    }
+
+    /// Fails the verification with a `timedOut` violation once its timeout is past.
+    ///
+    /// It is checked before each change to the disk too, so a verification that `timeout::run` stopped waiting for
+    /// doesn't keep repairing the root in the background.
+    fn check_deadline(&self, path: &Path) -> std::result::Result<(), String> {
+        if let Some((deadline, timeout)) = self.deadline {
+            if Instant::now() >= deadline {
+                let message = crate::timeout::timed_out(path, timeout);
+                return Err(self.violation(ViolationKind::TimedOut, path.to_path_buf(), message));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fails the verification with a violation, or records it and keeps walking with the `aggregate` option.
+    fn fail(
+        &self,
+        kind: ViolationKind,
+        path: PathBuf,
+        message: Message,
+    ) -> std::result::Result<(), String> {
+        let message = self.violation(kind, path, message);
+        if self.aggregate {
+            self.errors.borrow_mut().push(message);
+            Ok(())
+        } else {
+            Err(message)
+        }
+    }
+
+    /// Reports a violation with the severity of the rule it breaks: errors fail the verification, warnings and info
+    /// are logged and collected for the summary, notifying the event callback.
+    fn report(
+        &self,
+        severity: Severity,
+        kind: ViolationKind,
+        path: PathBuf,
+        message: Message,
+    ) -> std::result::Result<(), String> {
+        let text = self.host.translation().translate(&message);
+        let (bucket, event): (_, fn(Violation) -> VerificationEvent) = match severity {
+            Severity::Error => return self.fail(kind, path, message),
+            Severity::Warning => {
+                #[cfg(feature = "tracing")]
+                tracing::warn!(key = message.key, "{}", text);
+                #[cfg(not(feature = "tracing"))]
+                log::warn!("{}", text);
+                (&self.warnings, VerificationEvent::Warning)
+            }
+            Severity::Info => {
+                #[cfg(feature = "tracing")]
+                tracing::info!(key = message.key, "{}", text);
+                #[cfg(not(feature = "tracing"))]
+                log::info!("{}", text);
+                (&self.info, VerificationEvent::Info)
+            }
+        };
+
+        let violation = Violation {
+            kind,
+            code: kind.code(),
+            severity,
+            path,
+            message: text,
+            key: message.key.to_string(),
+            params: message.params,
+            explanation: message.explanation,
+            help_url: message.help_url,
+        };
+        bucket.borrow_mut().push(violation.clone());
+        if let Some(on_event) = self.on_event {
+            on_event(event(violation));
+        }
+        Ok(())
+    }
+
+    /// Verifies that the volume of `path` has room for the `required` bytes a repair is about to write, reporting
+    /// an `insufficientSpace` violation otherwise.
+    ///
+    /// # Returns
+    ///
+    /// Returns `Ok(true)` if the repair can go ahead, `Ok(false)` if the violation was reported without failing the
+    /// verification, or `Err(String)` with an error message otherwise.
+    fn has_space(&self, path: &Path, required: u64) -> std::result::Result<bool, String> {
+        let Err(
+            e @ crate::Error::InsufficientSpace {
+                required,
+                available,
+            },
+        ) = crate::space::check(path, required)
+        else {
+            return Ok(true);
+        };
+
+        let message = Message::new("insufficientSpace", e.to_string())
+            .param("path", path.display())
+            .param("required", required)
+            .param("available", available);
+        self.fail(ViolationKind::RepairFailed, path.to_path_buf(), message)?;
+        Ok(false)
+    }
+
+    /// Copies the entries missing from a directory from its seed source, before they are verified.
+    fn seed(&self, path: &Path, seed_from: SeedSource) -> std::result::Result<(), String> {
+        let entries = self
+            .host
+            .seed_source(seed_from, &self.root, path)
+            .and_then(|source| seed::missing_entries(&source, path));
+        let entries = match entries {
+            Ok(entries) => entries,
+            Err(e) => {
+                let message = Message::new("seedFailed", e.clone())
+                    .param("path", path.display())
+                    .param("error", e);
+                return self.fail(ViolationKind::RepairFailed, path.to_path_buf(), message);
+            }
+        };
+        if !self.has_space(path, crate::space::seed_size(&entries))? {
+            return Ok(());
+        }
+
+        for entry in entries {
+            self.check_deadline(&entry.target)?;
+            let result = self
+                .host
+                .repair_scope()
+                .check(Some(&self.root), &entry.target)
+                .map_err(|e| ("repairOutOfScope", e))
+                .and_then(|_| {
+                    seed::copy(&entry.source, &entry.target, entry.kind)
+                        .map_err(|e| ("seedFailed", e))
+                });
+            match result {
+                Ok(()) => self.repaired(&entry.target, entry.kind),
+                Err((key, e)) => {
+                    let message = Message::new(key, e.clone())
+                        .param("path", entry.target.display())
+                        .param("error", e);
+                    self.fail(ViolationKind::RepairFailed, entry.target, message)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the missing files of a directory that have a default `content`, and downloads those with a `url`,
+    /// before they are verified.
+    ///
+    /// # Returns
+    ///
+    /// Returns the names of the files assumed to be created in a dry run, including the missing files the seed source
+    /// of a `seeded` directory would copy, see `with_dry_run`.
+    fn create_files<'f>(
+        &self,
+        path: &Path,
+        listing: Option<&Listing>,
+        files: &'f [StructureFile],
+        seeded: bool,
+    ) -> std::result::Result<HashSet<&'f str>, String> {
+        let mut created = HashSet::new();
+        for file in files {
+            let options = &file.options;
+            let seeded = seeded && self.dry_run;
+            if options.content.is_none() && options.url.is_none() && !seeded {
+                continue;
+            }
+            if locate_in(listing, path, &file.name, self.normalization).is_some() {
+                continue;
+            }
+            if self.dry_run {
+                created.insert(file.name.as_str());
+                continue;
+            }
+
+            let file_path = path.join(&*normalize(&file.name, self.normalization));
+            self.check_deadline(&file_path)?;
+            let result = self
+                .host
+                .repair_scope()
+                .check(Some(&self.root), &file_path)
+                .map_err(|e| ("repairOutOfScope", e));
+            // A download checks the space for its size once started.
+            let required = match (&options.content, &options.url) {
+                (None, Some(_)) => crate::space::ENTRY_SIZE,
+                (content, _) => crate::space::content_size(content.as_ref()),
+            };
+            if result.is_ok() && !self.has_space(&file_path, required)? {
+                continue;
+            }
+            let result = result.and_then(|_| match (&options.content, &options.url) {
+                (None, Some(url)) => self
+                    .host
+                    .download(&file_path, url, options.sha256.as_deref())
+                    .map_err(|e| ("downloadFailed", e)),
+                (content, _) => {
+                    crate::content::create_file(&file_path, content.as_ref(), &self.tuning)
+                        .map_err(|e| ("createFileFailed", e))
+                }
+            });
+            match result {
+                Ok(()) => self.repaired(&file_path, EntryKind::File),
+                Err((key, e)) => {
+                    let message = Message::new(key, e.clone())
+                        .param("path", file_path.display())
+                        .param("error", e);
+                    self.fail(ViolationKind::RepairFailed, file_path, message)?;
+                }
+            }
+        }
+
+        Ok(created)
+    }
+
+    /// Returns the first declared file whose absence fails the verification and that doesn't exist, leaving out the
+    /// files assumed to be `created`, spreading the checks over `parallelism` threads when there are enough files.
+    fn first_missing<'f>(
+        &self,
+        path: &Path,
+        listing: Option<&Listing>,
+        files: &'f [StructureFile],
+        created: &HashSet<&str>,
+    ) -> Option<&'f StructureFile> {
+        let normalization = self.normalization;
+        let parallelism = self.tuning.parallelism.min(files.len());
+        if parallelism <= 1 {
+            return files
+                .iter()
+                .filter(|file| {
+                    file.missing_severity() == Severity::Error
+                        && !created.contains(file.name.as_str())
+                })
+                .find(|file| locate_in(listing, path, &file.name, normalization).is_none());
+        }
+
+        let chunk_size = (files.len() + parallelism - 1) / parallelism;
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = files
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .filter(|file| {
+                                file.missing_severity() == Severity::Error
+                                    && !created.contains(file.name.as_str())
+                            })
+                            .find(|file| {
+                                locate_in(listing, path, &file.name, normalization).is_none()
+                            })
+                    })
+                })
+                .collect();
+
+            // Chunks are joined in order, so the first missing file is the same as a sequential check.
+            handles
+                .into_iter()
+                .filter_map(|handle| handle.join().ok().flatten())
+                .next()
+        })
+    }
+
+    /// Verifies that a directory doesn't contain more than `max_children` entries, deleting the oldest
+    /// undeclared entries when the policy allows it.
+    fn enforce_max_children(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+        max_children: usize,
+        policy: QuotaPolicy,
+    ) -> std::result::Result<(), String> {
+        let Some(excess) = quota_excess(
+            path,
+            structure_item,
+            max_children,
+            self.normalization,
+            &self.nested_roots,
+        )?
+        else {
+            return Ok(());
+        };
+
+        // Without repairs, the quota is reported whatever its policy.
+        if policy == QuotaPolicy::Report || !self.repair {
+            let message = Message::new(
+                "quotaExceeded",
+                format!(
+                    "Directory {:?} has {} entries, more than the maximum of {}",
+                    path, excess.entries, max_children
+                ),
+            )
+            .param("path", path.display())
+            .param("entries", excess.entries)
+            .param("max", max_children);
+            return self.report(
+                structure_item.severity(),
+                ViolationKind::QuotaExceeded,
+                path.to_path_buf(),
+                message.explain(structure_item.explanation()),
+            );
+        }
+
+        let Some(deleted) = excess.deletable() else {
+            let message = Message::new(
+                "declaredEntriesExceedQuota",
+                format!(
+                    "Directory {:?} has more declared entries than the maximum of {}",
+                    path, max_children
+                ),
+            )
+            .param("path", path.display())
+            .param("max", max_children);
+            return self.report(
+                structure_item.severity(),
+                ViolationKind::QuotaExceeded,
+                path.to_path_buf(),
+                message.explain(structure_item.explanation()),
+            );
+        };
+
+        self.delete_oldest(path, deleted)
+    }
+
+    /// Removes the empty undeclared subdirectories of a directory with the `pruneEmptyDirs` option, or reports them
+    /// without repairs.
+    fn prune_empty_dirs(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+    ) -> std::result::Result<(), String> {
+        for dir_path in empty_dirs(path, structure_item, self.normalization, &self.nested_roots)? {
+            if !self.repair {
+                self.unrepaired(
+                    structure_item,
+                    dir_path.clone(),
+                    Message::new(
+                        "emptyDirectory",
+                        format!("Empty undeclared directory: {:?}", dir_path),
+                    )
+                    .param("path", dir_path.display()),
+                )?;
+                continue;
+            }
+            if self.dry_run {
+                continue;
+            }
+            self.check_deadline(&dir_path)?;
+            let result = self
+                .host
+                .repair_scope()
+                .check(Some(&self.root), &dir_path)
+                .map_err(|e| ("repairOutOfScope", e))
+                .and_then(|()| {
+                    remove_empty_dir(&dir_path).map_err(|e| {
+                        (
+                            "deleteFailed",
+                            format!("Failed to delete: {:?}, error: {:?}", dir_path, e),
+                        )
+                    })
+                });
+            match result {
+                Ok(()) => self.repaired(&dir_path, EntryKind::Directory),
+                Err((key, e)) => {
+                    let message = Message::new(key, e.clone())
+                        .param("path", dir_path.display())
+                        .param("error", e);
+                    self.fail(ViolationKind::RepairFailed, dir_path, message)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Deletes the undeclared entries of a directory older than its `retention`, or moves them into its `archiveTo`
+    /// directory. Without repairs, they are reported instead.
+    fn enforce_retention(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+        retention: &Retention,
+    ) -> std::result::Result<(), String> {
+        let expired = expired_entries(
+            path,
+            structure_item,
+            retention,
+            self.normalization,
+            &self.nested_roots,
+        )?;
+        if expired.is_empty() {
+            return Ok(());
+        }
+        if !self.repair {
+            for entry_path in expired {
+                let message = Message::new(
+                    "retentionExpired",
+                    format!(
+                        "Entry {:?} is older than the retention of {} days",
+                        entry_path, retention.max_age_days
+                    ),
+                )
+                .param("path", entry_path.display())
+                .param("maxAgeDays", retention.max_age_days);
+                self.unrepaired(structure_item, entry_path, message)?;
+            }
+            return Ok(());
+        }
+        if self.dry_run {
+            return Ok(());
+        }
+        let Some(archive_to) = &retention.archive_to else {
+            return self.delete_oldest(path, &expired);
+        };
+
+        let archive = path.join(archive_to);
+        let mut repaired = true;
+        for entry_path in expired {
+            let Some(name) = entry_path.file_name() else {
+                continue;
+            };
+            let target = archive.join(name);
+            self.check_deadline(&target)?;
+            let result = self
+                .host
+                .repair_scope()
+                .check(Some(&self.root), &target)
+                .map_err(|e| ("repairOutOfScope", e))
+                .and_then(|()| {
+                    archive_entry(&entry_path, &target).map_err(|e| ("archiveFailed", e))
+                });
+            if let Err((key, e)) = result {
+                let message = Message::new(key, e.clone())
+                    .param("path", entry_path.display())
+                    .param("error", e);
+                self.fail(ViolationKind::RepairFailed, entry_path, message)?;
+                repaired = false;
+            }
+        }
+        if repaired {
+            self.repaired(path, EntryKind::Directory);
+        }
+
+        Ok(())
+    }
+
+    /// Enforces the `maxEntries` quota of a directory, reporting the violation or deleting its least recently
+    /// modified undeclared entries depending on the quota policy.
+    fn enforce_max_entries(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+        max_entries: u64,
+        policy: QuotaPolicy,
+    ) -> std::result::Result<(), String> {
+        let Some(excess) = entries_excess(
+            path,
+            structure_item,
+            max_entries,
+            self.normalization,
+            &self.nested_roots,
+        )?
+        else {
+            return Ok(());
+        };
+
+        // Without repairs, the quota is reported whatever its policy.
+        if policy == QuotaPolicy::Report || !self.repair {
+            let message = Message::new(
+                "entryQuotaExceeded",
+                format!(
+                    "Directory {:?} has {} entries in total, more than the maximum of {}",
+                    path, excess.total, max_entries
+                ),
+            )
+            .param("path", path.display())
+            .param("entries", excess.total)
+            .param("max", max_entries);
+            return self.report(
+                structure_item.severity(),
+                ViolationKind::QuotaExceeded,
+                path.to_path_buf(),
+                message.explain(structure_item.explanation()),
+            );
+        }
+
+        let Some(deleted) = excess.deletable() else {
+            let message = Message::new(
+                "declaredEntriesExceedQuota",
+                format!(
+                    "Directory {:?} has more declared entries than the maximum of {}",
+                    path, max_entries
+                ),
+            )
+            .param("path", path.display())
+            .param("max", max_entries);
+            return self.report(
+                structure_item.severity(),
+                ViolationKind::QuotaExceeded,
+                path.to_path_buf(),
+                message.explain(structure_item.explanation()),
+            );
+        };
+
+        self.delete_oldest(path, &deleted)
+    }
+
+    /// Enforces the `maxTotalSize` quota of a directory, reporting the violation or deleting its least recently
+    /// modified undeclared files depending on the quota policy.
+    fn enforce_max_total_size(
+        &self,
+        path: &Path,
+        structure_item: &StructureItem,
+        max_total_size: u64,
+        policy: QuotaPolicy,
+    ) -> std::result::Result<(), String> {
+        let Some(excess) = size_excess(
+            path,
+            structure_item,
+            max_total_size,
+            self.normalization,
+            &self.nested_roots,
+        )?
+        else {
+            return Ok(());
+        };
+
+        // Without repairs, the quota is reported whatever its policy.
+        if policy == QuotaPolicy::Report || !self.repair {
+            let message = Message::new(
+                "sizeQuotaExceeded",
+                format!(
+                    "Directory {:?} holds {} bytes, more than the maximum of {}",
+                    path, excess.total, max_total_size
+                ),
+            )
+            .param("path", path.display())
+            .param("size", excess.total)
+            .param("max", max_total_size);
+            return self.report(
+                structure_item.severity(),
+                ViolationKind::QuotaExceeded,
+                path.to_path_buf(),
+                message.explain(structure_item.explanation()),
+            );
+        }
+
+        let Some(deleted) = excess.deletable() else {
+            let message = Message::new(
+                "declaredFilesExceedSizeQuota",
+                format!(
+                    "Directory {:?} has declared files larger than the maximum of {} bytes",
+                    path, max_total_size
+                ),
+            )
+            .param("path", path.display())
+            .param("max", max_total_size);
+            return self.report(
+                structure_item.severity(),
+                ViolationKind::QuotaExceeded,
+                path.to_path_buf(),
+                message.explain(structure_item.explanation()),
+            );
+        };
+
+        self.delete_oldest(path, &deleted)
+    }
+
+    /// Reports an undeclared entry that pruning or retention would delete, when repairs are disabled.
+    ///
+    /// In `strict` directories the entry is already reported as unexpected, it isn't reported twice.
+    fn unrepaired(
+        &self,
+        structure_item: &StructureItem,
+        entry_path: PathBuf,
+        message: Message,
+    ) -> std::result::Result<(), String> {
+        let strict = structure_item
+            .options
+            .as_ref()
+            .and_then(|options| options.strict);
+        if strict == Some(true) {
+            return Ok(());
+        }
+
+        self.report(
+            structure_item.severity(),
+            ViolationKind::UnexpectedEntry,
+            entry_path,
+            message.explain(structure_item.explanation()),
+        )
+    }
+
+    /// Deletes the entries of a directory selected by its quota policy, counting the directory as repaired if they
+    /// all are.
+    fn delete_oldest(&self, path: &Path, deleted: &[PathBuf]) -> std::result::Result<(), String> {
+        if self.dry_run {
+            return Ok(());
+        }
+        let mut repaired = true;
+        for entry_path in deleted {
+            self.check_deadline(entry_path)?;
+            if let Err(e) = self.host.repair_scope().check(Some(&self.root), entry_path) {
+                let message = Message::new("repairOutOfScope", e.clone())
+                    .param("path", entry_path.display())
+                    .param("error", e);
+                self.fail(ViolationKind::RepairFailed, entry_path.clone(), message)?;
+                repaired = false;
+                continue;
+            }
+            let result = if extended(entry_path).is_dir() {
+                std::fs::remove_dir_all(extended(entry_path))
+            } else {
+                std::fs::remove_file(extended(entry_path))
+            };
+            if let Err(e) = result {
+                let message = Message::new(
+                    "deleteFailed",
+                    format!("Failed to delete: {:?}, error: {:?}", entry_path, e),
+                )
+                .param("path", entry_path.display())
+                .param("error", e);
+                self.fail(ViolationKind::RepairFailed, entry_path.clone(), message)?;
+                repaired = false;
+            }
+        }
+        if repaired {
+            self.repaired(path, EntryKind::Directory);
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the message of a missing file.
+fn not_found(file_path: &Path) -> Message {
+    Message::new("fileNotFound", format!("File not found: {:?}", file_path))
+        .param("path", file_path.display())
+}
+
+/// The entries of a directory over its `maxChildren` quota.
+pub(crate) struct QuotaExcess {
+    /// The number of entries of the directory.
+    pub(crate) entries: usize,
+    /// The number of entries over the quota.
+    pub(crate) excess: usize,
+    /// The undeclared entries, oldest first.
+    pub(crate) candidates: Vec<PathBuf>,
+}
+
+impl QuotaExcess {
+    /// Returns the entries to delete to get back under the quota, or `None` if there aren't enough undeclared entries.
+    pub(crate) fn deletable(&self) -> Option<&[PathBuf]> {
+        self.candidates.get(..self.excess)
+    }
+}
+
+/// Returns the undeclared entries of a directory last modified more than `maxAgeDays` ago, leaving out its `archiveTo`
+/// directory, the marker files of the plugin and the other configured roots nested inside it.
+pub(crate) fn expired_entries(
+    path: &Path,
+    structure_item: &StructureItem,
+    retention: &Retention,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<Vec<PathBuf>, String> {
+    if !extended(path).is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let max_age =
+        std::time::Duration::from_secs(retention.max_age_days.saturating_mul(24 * 60 * 60));
+    let entries = std::fs::read_dir(extended(path))
+        .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+    let mut expired = Vec::new();
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if is_marker(&name)
+            || structure_item.declares(&name, normalization)
+            || retention.archive_to.as_deref() == Some(name.as_str())
+        {
+            continue;
+        }
+        let entry_path = path.join(entry.file_name());
+        if nested_roots
+            .iter()
+            .any(|root| root.starts_with(&entry_path))
+        {
+            continue;
+        }
+
+        // An entry whose modification time can't be read, or lies in the future, isn't expired.
+        let age = entry
+            .metadata()
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok());
+        if age.is_some_and(|age| age > max_age) {
+            expired.push(entry_path);
+        }
+    }
+    expired.sort();
+
+    Ok(expired)
+}
+
+/// Returns the undeclared subdirectories of a directory that contain no files, only empty directories if any, leaving
+/// out the other configured roots nested inside it.
+pub(crate) fn empty_dirs(
+    path: &Path,
+    structure_item: &StructureItem,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<Vec<PathBuf>, String> {
+    if !extended(path).is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let entries = std::fs::read_dir(extended(path))
+        .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+    let mut empty = Vec::new();
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let entry_path = path.join(entry.file_name());
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+            || is_marker(&name)
+            || structure_item.declares(&name, normalization)
+            || nested_roots
+                .iter()
+                .any(|root| root.starts_with(&entry_path))
+        {
+            continue;
+        }
+        if is_empty_dir(&entry_path) {
+            empty.push(entry_path);
+        }
+    }
+    empty.sort();
+
+    Ok(empty)
+}
+
+/// Returns whether a directory contains no files, only empty directories if any. Symbolic links count as files.
+pub(crate) fn is_empty_dir(path: &Path) -> bool {
+    let Ok(entries) = std::fs::read_dir(extended(path)) else {
+        return false;
+    };
+    entries.into_iter().all(|entry| {
+        entry.is_ok_and(|entry| {
+            entry.file_type().is_ok_and(|file_type| file_type.is_dir())
+                && is_empty_dir(&entry.path())
+        })
+    })
+}
+
+/// Removes a directory containing no files, bottom-up, so a file added in the meantime is never deleted: the removal
+/// then fails instead.
+pub(crate) fn remove_empty_dir(path: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(extended(path))? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            remove_empty_dir(&entry.path())?;
+        }
+    }
+    std::fs::remove_dir(extended(path))
+}
+
+/// Moves an expired entry into the archive directory of its directory, created when needed.
+pub(crate) fn archive_entry(path: &Path, target: &Path) -> std::result::Result<(), String> {
+    if std::fs::symlink_metadata(extended(target)).is_ok() {
+        return Err(format!(
+            "Failed to archive {:?} to {:?}: the target already exists",
+            path, target
+        ));
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(extended(parent))
+            .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", parent, e))?;
+    }
+    std::fs::rename(extended(path), extended(target)).map_err(|e| {
+        format!(
+            "Failed to archive {:?} to {:?}, error: {:?}",
+            path, target, e
+        )
+    })
+}
+
+/// The contents of a directory over a recursive quota: its `maxTotalSize` in bytes or its `maxEntries`.
+pub(crate) struct RecursiveExcess {
+    /// The total of the directory, recursively.
+    pub(crate) total: u64,
+    /// The amount over the quota.
+    pub(crate) excess: u64,
+    /// The undeclared entries and what deleting each one frees, least recently modified first.
+    pub(crate) candidates: Vec<(PathBuf, u64)>,
+}
+
+impl RecursiveExcess {
+    /// Sorts the candidates of a directory over its quota, or returns `None` if it is within it.
+    fn over(
+        total: u64,
+        max: u64,
+        mut candidates: Vec<(std::time::SystemTime, PathBuf, u64)>,
+    ) -> Option<Self> {
+        if total <= max {
+            return None;
+        }
+        candidates.sort();
+
+        Some(RecursiveExcess {
+            total,
+            excess: total - max,
+            candidates: candidates
+                .into_iter()
+                .map(|(_, path, freed)| (path, freed))
+                .collect(),
+        })
+    }
+
+    /// Returns the entries to delete to get back under the quota, or `None` if there aren't enough undeclared entries.
+    pub(crate) fn deletable(&self) -> Option<Vec<PathBuf>> {
+        let mut freed = 0;
+        let mut deleted = Vec::new();
+        for (path, amount) in &self.candidates {
+            if freed >= self.excess {
+                break;
+            }
+            freed += amount;
+            deleted.push(path.clone());
+        }
+        (freed >= self.excess).then_some(deleted)
+    }
+}
+
+/// Returns the excess of a directory over `max_total_size` bytes of files, or `None` if it is within its quota or
+/// doesn't exist. Files of the other configured roots nested inside it are left out.
+pub(crate) fn size_excess(
+    path: &Path,
+    structure_item: &StructureItem,
+    max_total_size: u64,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<Option<RecursiveExcess>, String> {
+    if !extended(path).is_dir() {
+        return Ok(None);
+    }
+
+    let mut size = 0;
+    let mut candidates = Vec::new();
+    collect_sizes(
+        path,
+        Some(structure_item),
+        normalization,
+        nested_roots,
+        &mut size,
+        &mut candidates,
+    )?;
+    Ok(RecursiveExcess::over(size, max_total_size, candidates))
+}
+
+/// Returns the excess of a directory over `max_entries` files and directories, recursively, or `None` if it is within
+/// its quota or doesn't exist. Entries of the other configured roots nested inside it are left out.
+pub(crate) fn entries_excess(
+    path: &Path,
+    structure_item: &StructureItem,
+    max_entries: u64,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<Option<RecursiveExcess>, String> {
+    if !extended(path).is_dir() {
+        return Ok(None);
+    }
+
+    let mut entries = 0;
+    let mut candidates = Vec::new();
+    collect_entries(
+        path,
+        Some(structure_item),
+        normalization,
+        nested_roots,
+        &mut entries,
+        &mut candidates,
+    )?;
+    Ok(RecursiveExcess::over(entries, max_entries, candidates))
+}
+
+/// Counts the entries of a directory, recursively, collecting the undeclared ones with their modification times and
+/// the number of entries deleting each one removes: an undeclared directory is deleted with its contents, unless it
+/// contains a nested root.
+fn collect_entries(
+    path: &Path,
+    structure_item: Option<&StructureItem>,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+    entries: &mut u64,
+    candidates: &mut Vec<(std::time::SystemTime, PathBuf, u64)>,
+) -> std::result::Result<(), String> {
+    let read_dir = std::fs::read_dir(extended(path))
+        .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+    for entry in read_dir {
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        let metadata = entry.metadata().map_err(|e| {
+            format!(
+                "Failed to read metadata: {:?}, error: {:?}",
+                entry.path(),
+                e
+            )
+        })?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let entry_path = path.join(entry.file_name());
+        if nested_roots.iter().any(|root| entry_path.starts_with(root)) {
+            continue;
+        }
+
+        let declared = structure_item.is_some_and(|item| item.declares(&name, normalization));
+        // A directory containing a nested root is never deleted as a whole, only its other entries are.
+        let contains_root = nested_roots
+            .iter()
+            .any(|root| root.starts_with(&entry_path));
+        if metadata.is_dir() && (declared || contains_root) {
+            *entries += 1;
+            let dir = structure_item
+                .and_then(|item| item.dirs.as_ref())
+                .and_then(|dirs| {
+                    dirs.iter()
+                        .find(|(declared, _)| same_name(declared, &name, normalization))
+                })
+                .map(|(_, dir)| dir);
+            collect_entries(
+                &entry_path,
+                dir,
+                normalization,
+                nested_roots,
+                entries,
+                candidates,
+            )?;
+            continue;
+        }
+
+        let removed = match metadata.is_dir() {
+            true => 1 + count_entries(&entry_path)?,
+            false => 1,
+        };
+        *entries += removed;
+        if !declared && !is_marker(&name) {
+            let modified = metadata
+                .modified()
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            candidates.push((modified, entry_path, removed));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the number of entries inside a directory, recursively. Symbolic links aren't followed.
+fn count_entries(path: &Path) -> std::result::Result<u64, String> {
+    let read_dir = std::fs::read_dir(extended(path))
+        .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+    let mut entries = 0;
+    for entry in read_dir {
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        entries += 1;
+        if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            entries += count_entries(&entry.path())?;
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Adds up the sizes of the files of a directory, recursively, collecting the undeclared ones with their
+/// modification times. Symbolic links aren't followed.
+fn collect_sizes(
+    path: &Path,
+    structure_item: Option<&StructureItem>,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+    size: &mut u64,
+    candidates: &mut Vec<(std::time::SystemTime, PathBuf, u64)>,
+) -> std::result::Result<(), String> {
+    let entries = std::fs::read_dir(extended(path))
+        .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+    for entry in entries {
+        let entry =
+            entry.map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+        let metadata = entry.metadata().map_err(|e| {
+            format!(
+                "Failed to read metadata: {:?}, error: {:?}",
+                entry.path(),
+                e
+            )
+        })?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let entry_path = path.join(entry.file_name());
+
+        if metadata.is_dir() {
+            if nested_roots.iter().any(|root| entry_path.starts_with(root)) {
+                continue;
+            }
+            let dir = structure_item
+                .and_then(|item| item.dirs.as_ref())
+                .and_then(|dirs| {
+                    dirs.iter()
+                        .find(|(declared, _)| same_name(declared, &name, normalization))
+                })
+                .map(|(_, dir)| dir);
+            collect_sizes(
+                &entry_path,
+                dir,
+                normalization,
+                nested_roots,
+                size,
+                candidates,
+            )?;
+        } else if metadata.is_file() {
+            *size += metadata.len();
+            // Declared files are part of the structure and are never deleted, nor are the marker files.
+            if !is_marker(&name)
+                && !structure_item.is_some_and(|item| item.declares(&name, normalization))
+            {
+                let modified = metadata
+                    .modified()
+                    .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+                candidates.push((modified, entry_path, metadata.len()));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the excess of a directory over `max_children` entries, or `None` if it is within its quota or doesn't exist.
+/// Entries that are or contain other configured roots nested inside it are never deleted.
+pub(crate) fn quota_excess(
+    path: &Path,
+    structure_item: &StructureItem,
+    max_children: usize,
+    normalization: Option<UnicodeNormalization>,
+    nested_roots: &[PathBuf],
+) -> std::result::Result<Option<QuotaExcess>, String> {
+    if !extended(path).is_dir() {
+        return Ok(None);
+    }
+
+    let entries = std::fs::read_dir(extended(path))
+        .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+        .map_err(|e| format!("Failed to read directory: {:?}, error: {:?}", path, e))?;
+    if entries.len() <= max_children {
+        return Ok(None);
+    }
+
+    // Declared entries are part of the structure and are never deleted, nor are the marker files.
+    let mut candidates: Vec<_> = entries
+        .iter()
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            !is_marker(&name) && !structure_item.declares(&name, normalization)
+        })
+        .filter_map(|entry| {
+            let entry_path = path.join(entry.file_name());
+            if nested_roots
+                .iter()
+                .any(|root| root.starts_with(&entry_path))
+            {
+                return None;
+            }
+            let modified = entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+            Some((modified, entry_path))
+        })
+        .collect();
+    candidates.sort();
+
+    Ok(Some(QuotaExcess {
+        entries: entries.len(),
+        excess: entries.len() - max_children,
+        candidates: candidates.into_iter().map(|(_, path)| path).collect(),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a root holding a declared file, an undeclared one, and an undeclared directory with a configured root
+    /// nested two levels deep inside it.
+    fn nested_root_fixture(name: &str) -> (PathBuf, PathBuf) {
+        let root =
+            std::env::temp_dir().join(format!("structure-manager-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        let nested = root.join("data").join("nested");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(root.join("settings.json"), "{}").unwrap();
+        std::fs::write(root.join("stale.txt"), "stale").unwrap();
+        std::fs::write(root.join("data").join("old.txt"), "old").unwrap();
+        std::fs::write(nested.join("a.txt"), "nested").unwrap();
+        std::fs::write(nested.join("b.txt"), "nested").unwrap();
+        (root, nested)
+    }
+
+    fn candidates(excess: &RecursiveExcess) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = excess
+            .candidates
+            .iter()
+            .map(|(path, _)| path.clone())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn entries_excess_leaves_out_nested_roots() {
+        let (root, nested) = nested_root_fixture("entries");
+        let structure_item = StructureItem::from(vec!["settings.json"]);
+
+        let excess = entries_excess(&root, &structure_item, 0, None, &[nested])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(excess.total, 4);
+        assert_eq!(
+            candidates(&excess),
+            vec![root.join("data").join("old.txt"), root.join("stale.txt")]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn size_excess_leaves_out_nested_roots() {
+        let (root, nested) = nested_root_fixture("sizes");
+        let structure_item = StructureItem::from(vec!["settings.json"]);
+
+        let excess = size_excess(&root, &structure_item, 0, None, &[nested])
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(excess.total, 10);
+        assert_eq!(
+            candidates(&excess),
+            vec![root.join("data").join("old.txt"), root.join("stale.txt")]
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
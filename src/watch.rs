@@ -0,0 +1,95 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+    time::Duration,
+};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::{AppHandle, Runtime};
+
+use crate::{models::*, roots::RootSelector, StructureManagerExt};
+
+/// How long a watched root must stay unchanged before it is verified again, so a burst of changes (e.g. a file being
+/// written in chunks) triggers a single verification.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// The watched roots, keyed like the `root` of the verification events, see `StructureManagerExt::watch`.
+#[derive(Default)]
+pub(crate) struct Watchers(Mutex<HashMap<String, RecommendedWatcher>>);
+
+impl Watchers {
+    /// Watches a root, at `path`, verifying it once and again whenever it changes, and sending the violations of
+    /// each verification to `on_event`. A previous watch of the root is replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `nested_roots` - The paths of the other configured roots nested inside the root, whose changes are left out.
+    pub(crate) fn watch<R: Runtime>(
+        &self,
+        app: AppHandle<R>,
+        root: RootSelector,
+        path: &Path,
+        nested_roots: Vec<PathBuf>,
+        on_event: impl Fn(VerificationEvent) + Send + 'static,
+    ) -> std::result::Result<(), String> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                match event {
+                    Ok(event)
+                        if event.paths.iter().any(|path| {
+                            !nested_roots.iter().any(|root| path.starts_with(root))
+                        }) =>
+                    {
+                        // The worker is gone once the watcher is dropped.
+                        let _ = sender.send(());
+                    }
+                    Ok(_) => {}
+                    Err(e) => log::warn!("Failed to watch a root: {}", e),
+                }
+            })
+            .map_err(|e| format!("Failed to watch {:?}, error: {}", path, e))?;
+        watcher
+            .watch(path, RecursiveMode::Recursive)
+            .map_err(|e| format!("Failed to watch {:?}, error: {}", path, e))?;
+
+        let name = root.name().to_string();
+        std::thread::spawn(move || loop {
+            let on_violation = |event: VerificationEvent| {
+                if !matches!(event, VerificationEvent::Entry { .. }) {
+                    on_event(event);
+                }
+            };
+            if let Err(e) = app.verify_streamed(root.clone(), &on_violation) {
+                log::debug!(
+                    "Watched root {} failed its verification: {}",
+                    root.name(),
+                    e
+                );
+            }
+            // The changes made by the verification itself, e.g. its repairs or its version marker, are dropped.
+            while receiver.try_recv().is_ok() {}
+
+            if receiver.recv().is_err() {
+                break;
+            }
+            loop {
+                match receiver.recv_timeout(DEBOUNCE) {
+                    Ok(()) => continue,
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+
+        log::info!("Watching {} at {:?}", name, path);
+        self.0.lock().unwrap().insert(name, watcher);
+        Ok(())
+    }
+
+    /// Stops watching a root, returning whether it was watched.
+    pub(crate) fn unwatch(&self, root: &str) -> bool {
+        self.0.lock().unwrap().remove(root).is_some()
+    }
+}
@@ -0,0 +1,179 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use tauri::{Manager, Runtime};
+
+use crate::{history::DIRECTORY, models::*};
+
+/// The file persisting the workspace roots, in the plugin's own directory of `appLocalData`.
+const FILE_NAME: &str = "workspaces.json";
+
+/// The workspace roots added by the user, see `StructureManagerExt::add_workspace`.
+pub(crate) struct Workspaces(Mutex<Vec<WorkspaceRoot>>);
+
+impl Workspaces {
+    /// Loads the persisted workspace roots and installs them as custom roots of the structure configuration.
+    ///
+    /// A workspace whose name is taken by a configured custom root is dropped with a warning. One whose structure is
+    /// no longer declared is left out with a warning but kept, so a later version of the configuration can verify it
+    /// again.
+    pub(crate) fn load<R: Runtime, M: Manager<R>>(
+        manager: &M,
+        structure_config: &mut StructureConfig,
+    ) -> Self {
+        let mut workspaces = match path(manager).and_then(|path| read(&path)) {
+            Ok(workspaces) => workspaces,
+            Err(e) => {
+                log::warn!("Failed to load the workspace roots: {}", e);
+                Vec::new()
+            }
+        };
+        workspaces.retain(|workspace| {
+            let taken = structure_config
+                .custom
+                .as_ref()
+                .is_some_and(|custom| custom.contains_key(&workspace.name));
+            if taken {
+                log::warn!(
+                    "Dropping the workspace root `{}`: custom root `{}` already exists",
+                    workspace.name,
+                    workspace.name
+                );
+            }
+            !taken
+        });
+        for workspace in &workspaces {
+            if let Err(e) = install(structure_config, workspace) {
+                log::warn!("Skipping the workspace root `{}`: {}", workspace.name, e);
+            }
+        }
+
+        Self(Mutex::new(workspaces))
+    }
+
+    /// Returns the workspace roots, in the order they were added.
+    pub(crate) fn list(&self) -> Vec<WorkspaceRoot> {
+        self.0.lock().unwrap().clone()
+    }
+
+    /// Adds a workspace root, replacing the one with the same name, installs it and persists the workspace roots.
+    pub(crate) fn add<R: Runtime, M: Manager<R>>(
+        &self,
+        manager: &M,
+        structure_config: &mut StructureConfig,
+        workspace: WorkspaceRoot,
+    ) -> std::result::Result<(), String> {
+        if !workspace.path.is_absolute() {
+            return Err(format!(
+                "The path of a workspace root must be absolute: {:?}",
+                workspace.path
+            ));
+        }
+        let mut workspaces = self.0.lock().unwrap();
+        let existing = workspaces.iter().position(|w| w.name == workspace.name);
+        let taken = structure_config
+            .custom
+            .as_ref()
+            .is_some_and(|custom| custom.contains_key(&workspace.name));
+        if taken && existing.is_none() {
+            return Err(format!("Custom root `{}` already exists", workspace.name));
+        }
+
+        install(structure_config, &workspace)?;
+        match existing {
+            Some(index) => workspaces[index] = workspace,
+            None => workspaces.push(workspace),
+        }
+        write(&path(manager)?, &workspaces)
+    }
+
+    /// Removes a workspace root, uninstalls it and persists the workspace roots.
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the workspace root existed, or `Err(String)` if the workspace roots can't be persisted.
+    pub(crate) fn remove<R: Runtime, M: Manager<R>>(
+        &self,
+        manager: &M,
+        structure_config: &mut StructureConfig,
+        name: &str,
+    ) -> std::result::Result<bool, String> {
+        let mut workspaces = self.0.lock().unwrap();
+        let Some(index) = workspaces.iter().position(|w| w.name == name) else {
+            return Ok(false);
+        };
+
+        workspaces.remove(index);
+        if let Some(custom) = &mut structure_config.custom {
+            custom.remove(name);
+        }
+        write(&path(manager)?, &workspaces).map(|_| true)
+    }
+}
+
+/// Installs a workspace root as a custom root, with its named structure.
+fn install(
+    structure_config: &mut StructureConfig,
+    workspace: &WorkspaceRoot,
+) -> std::result::Result<(), String> {
+    let structure = structure_config
+        .structures
+        .as_ref()
+        .and_then(|structures| structures.get(&workspace.structure))
+        .ok_or_else(|| format!("Structure `{}` not found", workspace.structure))?
+        .resolve(
+            structure_config
+                .definitions
+                .as_ref()
+                .unwrap_or(&BTreeMap::new()),
+        )?;
+
+    structure_config
+        .custom
+        .get_or_insert_with(BTreeMap::new)
+        .insert(
+            workspace.name.clone(),
+            CustomRoot {
+                path: workspace.path.clone(),
+                structure,
+            },
+        );
+    Ok(())
+}
+
+/// Returns the path of the workspaces file.
+fn path<R: Runtime, M: Manager<R>>(manager: &M) -> std::result::Result<PathBuf, String> {
+    manager
+        .path()
+        .app_local_data_dir()
+        .map(|directory| directory.join(DIRECTORY).join(FILE_NAME))
+        .map_err(|e| format!("Failed to resolve the workspaces file: {}", e))
+}
+
+fn read(path: &Path) -> std::result::Result<Vec<WorkspaceRoot>, String> {
+    let content = match std::fs::read(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {:?}, error: {:?}", path, e)),
+    };
+
+    serde_json::from_slice(&content).map_err(|e| format!("Invalid {:?}, error: {}", path, e))
+}
+
+/// Writes the workspaces file, through a temporary file swapped in so a crash never leaves it half written.
+fn write(path: &Path, workspaces: &[WorkspaceRoot]) -> std::result::Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create directory: {:?}, error: {:?}", parent, e))?;
+    }
+    let content = serde_json::to_vec_pretty(workspaces).map_err(|e| e.to_string())?;
+
+    let temporary = path.with_extension("json.tmp");
+    std::fs::write(&temporary, content)
+        .map_err(|e| format!("Failed to write {:?}, error: {:?}", temporary, e))?;
+    std::fs::rename(&temporary, path)
+        .map_err(|e| format!("Failed to replace {:?}, error: {:?}", path, e))
+}